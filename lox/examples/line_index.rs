@@ -0,0 +1,71 @@
+//! Compares finding lines and columns lazily with `LineIndex` against tracking them for every
+//! character, for a 10 000 line program with 20 error positions:
+//! `cargo run --release --example line_index`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lox::scanner::LineIndex;
+
+const LINES: usize = 10_000;
+const LOOKUPS: usize = 20;
+const RUNS: u32 = 100;
+
+/// Advances through the whole source keeping the line and column of every character, the
+/// way a scanner tracking positions eagerly does, and picks out those of `offsets`.
+fn track_every_character(source: &str, offsets: &[usize]) -> Vec<(usize, usize)> {
+    let mut locations = Vec::with_capacity(offsets.len());
+    let mut targets = offsets.iter().peekable();
+    let (mut line, mut column) = (1, 1);
+
+    for (offset, c) in source.char_indices() {
+        while targets.next_if(|&&target| target == offset).is_some() {
+            locations.push((line, column));
+        }
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+
+    locations
+}
+
+fn look_up_lazily(source: &str, offsets: &[usize]) -> Vec<(usize, usize)> {
+    let index = LineIndex::from_source(source);
+    offsets
+        .iter()
+        .map(|&offset| index.line_col_of(offset))
+        .collect()
+}
+
+fn time(run: impl Fn() -> Vec<(usize, usize)>) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(run());
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let source = (0..LINES)
+        .map(|i| format!("var value{i} = \"ä\" + {i} * (value{i} - 1);\n"))
+        .collect::<String>();
+    let offsets = (0..LOOKUPS)
+        .map(|i| source.len() / LOOKUPS * i)
+        .map(|offset| (offset..).find(|&o| source.is_char_boundary(o)).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        track_every_character(&source, &offsets),
+        look_up_lazily(&source, &offsets)
+    );
+
+    let eager = time(|| track_every_character(black_box(&source), &offsets));
+    let lazy = time(|| look_up_lazily(black_box(&source), &offsets));
+    println!("{LINES} lines, {LOOKUPS} lookups, average of {RUNS} runs");
+    println!("per-character tracking: {eager:?}");
+    println!("LineIndex:              {lazy:?}");
+}
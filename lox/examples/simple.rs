@@ -0,0 +1,17 @@
+//! Runs Lox programs with the one-shot API: `cargo run --example simple`.
+
+use lox::{evaluate_program, evaluate_program_with_env, Interpreter, LoxError};
+
+fn main() -> Result<(), LoxError> {
+    // One value per statement: nil for the declaration, then the value of the expression.
+    let values = evaluate_program("var greeting = \"Hello\"; greeting + \", Lox!\";")?;
+    println!("{values:?}");
+
+    // Programs evaluated with the same interpreter share their globals.
+    let mut interpreter = Interpreter::new();
+    evaluate_program_with_env("fun square(x) { return x * x; }", &mut interpreter)?;
+    let values = evaluate_program_with_env("square(12);", &mut interpreter)?;
+    println!("{values:?}");
+
+    Ok(())
+}
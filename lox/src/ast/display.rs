@@ -0,0 +1,207 @@
+use crate::expression::Expression::*;
+use crate::expression::{ExpressionNode, Parameters, PatternElement, UnaryOp};
+
+/// Prints an expression as a Lisp-style S-expression, e.g. `(+ 1 (group (- 2)))`.
+pub fn to_s_expression(node: &ExpressionNode) -> String {
+    match &node.expression {
+        Unary { inner, op } => {
+            let op = match op {
+                UnaryOp::Not => "!",
+                UnaryOp::Negative => "-",
+            };
+            parenthesize(op, &[inner])
+        }
+        Binary { left, right, op } => parenthesize(&op.to_string(), &[left, right]),
+        Logical { left, right, op } => parenthesize(&op.to_string(), &[left, right]),
+        Literal(value) => value.to_string(),
+        Grouping(inner) => parenthesize("group", &[inner]),
+        Variable(name) => format!("(var {name})"),
+        Assignment { name, value, .. } => format!("(set! {name} {})", to_s_expression(value)),
+        Lambda { arguments, .. } => format!("(fun ({arguments}))"),
+        Call { callee, arguments } => {
+            let mut parts = vec![callee.as_ref()];
+            parts.extend(arguments);
+            parenthesize("call", &parts)
+        }
+        Array(elements) => parenthesize("array", &elements.iter().collect::<Vec<_>>()),
+        Index { object, index } => parenthesize("index", &[object, index]),
+        Get { object, name } => format!("(get {} {name})", to_s_expression(object)),
+        IndexAssignment {
+            object,
+            index,
+            value,
+        } => parenthesize("set-index!", &[object, index, value]),
+        PatternAssignment { pattern, value } => {
+            format!(
+                "(set! {} {})",
+                pattern_to_string(pattern),
+                to_s_expression(value)
+            )
+        }
+        Spread(inner) => parenthesize("...", &[inner]),
+    }
+}
+
+/// Prints an expression back as Lox source, e.g. `foo(1, [a, b])[0] + 2`. Groupings are
+/// kept, so the result parses into the same tree. Lambda bodies are left out as `{ ... }`.
+pub fn to_source(node: &ExpressionNode) -> String {
+    match &node.expression {
+        Unary { inner, op } => {
+            let op = match op {
+                UnaryOp::Not => "!",
+                UnaryOp::Negative => "-",
+            };
+            format!("{op}{}", to_source(inner))
+        }
+        Binary { left, right, op } => format!("{} {op} {}", to_source(left), to_source(right)),
+        Logical { left, right, op } => format!("{} {op} {}", to_source(left), to_source(right)),
+        Literal(value) => value.to_string(),
+        Grouping(inner) => format!("({})", to_source(inner)),
+        Variable(name) => name.clone(),
+        Assignment { name, value, .. } => format!("{name} = {}", to_source(value)),
+        Lambda { arguments, .. } => format!("fun ({}) {{ ... }}", parameter_list(arguments)),
+        Call { callee, arguments } => {
+            format!("{}({})", to_source(callee), source_list(arguments))
+        }
+        Array(elements) => format!("[{}]", source_list(elements)),
+        Index { object, index } => format!("{}[{}]", to_source(object), to_source(index)),
+        Get { object, name } => format!("{}.{name}", to_source(object)),
+        IndexAssignment {
+            object,
+            index,
+            value,
+        } => format!(
+            "{}[{}] = {}",
+            to_source(object),
+            to_source(index),
+            to_source(value)
+        ),
+        PatternAssignment { pattern, value } => {
+            format!("{} = {}", pattern_to_source(pattern), to_source(value))
+        }
+        Spread(inner) => format!("...{}", to_source(inner)),
+    }
+}
+
+fn source_list(nodes: &[ExpressionNode]) -> String {
+    nodes.iter().map(to_source).collect::<Vec<_>>().join(", ")
+}
+
+/// The parameters as written in a declaration, e.g. `a, b, ...rest`.
+pub fn parameter_list(parameters: &Parameters) -> String {
+    let mut names = parameters.names.clone();
+    names.extend(parameters.rest.iter().map(|rest| format!("...{rest}")));
+    names.join(", ")
+}
+
+/// A destructuring pattern as written in the source, e.g. `[a, _, b]`.
+pub fn pattern_to_source(pattern: &[PatternElement]) -> String {
+    let names = pattern
+        .iter()
+        .map(|element| element.name.as_deref().unwrap_or("_"))
+        .collect::<Vec<_>>();
+
+    format!("[{}]", names.join(", "))
+}
+
+fn parenthesize(name: &str, nodes: &[&ExpressionNode]) -> String {
+    let mut result = format!("({name}");
+    for node in nodes {
+        result.push(' ');
+        result.push_str(&to_s_expression(node));
+    }
+    result.push(')');
+    result
+}
+
+fn pattern_to_string(pattern: &[PatternElement]) -> String {
+    let names = pattern
+        .iter()
+        .map(|element| element.name.as_deref().unwrap_or("_"))
+        .collect::<Vec<_>>();
+
+    format!("[{}]", names.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+    use crate::scanner::Scanner;
+    use crate::statement::Statement;
+
+    use super::*;
+
+    fn s_expression(source: &str) -> String {
+        let tokens = Scanner::new(source.to_string()).scan();
+        match parse(&tokens).unwrap().as_slice() {
+            [Statement::Expression(expr)] => to_s_expression(expr),
+            _ => panic!("expected a single expression statement"),
+        }
+    }
+
+    fn source(source: &str) -> String {
+        let tokens = Scanner::new(source.to_string()).scan();
+        match parse(&tokens).unwrap().as_slice() {
+            [Statement::Expression(expr)] => to_source(expr),
+            _ => panic!("expected a single expression statement"),
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(s_expression("1 + 2 * 3;"), "(+ 1 (* 2 3))");
+        assert_eq!(s_expression("-(1);"), "(- (group 1))");
+        assert_eq!(s_expression("!true == false;"), "(== (! true) false)");
+        assert_eq!(
+            s_expression("a and b or c;"),
+            "(or (and (var a) (var b)) (var c))"
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(s_expression("\"hi\";"), "\"hi\"");
+        assert_eq!(s_expression("nil;"), "nil");
+        assert_eq!(s_expression("1.5;"), "1.5");
+    }
+
+    #[test]
+    fn test_assignments() {
+        assert_eq!(s_expression("x = 5;"), "(set! x 5)");
+        assert_eq!(s_expression("a[0] = 1;"), "(set-index! (var a) 0 1)");
+        assert_eq!(s_expression("[a, _] = b;"), "(set! [a _] (var b))");
+    }
+
+    #[test]
+    fn test_calls_and_access() {
+        assert_eq!(s_expression("foo(1, x);"), "(call (var foo) 1 (var x))");
+        assert_eq!(s_expression("foo();"), "(call (var foo))");
+        assert_eq!(s_expression("[1, 2][0];"), "(index (array 1 2) 0)");
+        assert_eq!(s_expression("math.pi;"), "(get (var math) pi)");
+        assert_eq!(
+            s_expression("(fun (a, b) { return a; });"),
+            "(group (fun (a b)))"
+        );
+    }
+
+    #[test]
+    fn test_source_round_trips() {
+        for expression in [
+            "1 + 2 * (3 - -x)",
+            "!a and b or c",
+            "foo(1, \"s\", ...rest)[0].bar",
+            "x = a[0] = [1, nil, true]",
+            "[a, _, b] = pair",
+        ] {
+            assert_eq!(source(&format!("{expression};")), expression);
+        }
+    }
+
+    #[test]
+    fn test_source_leaves_out_lambda_bodies() {
+        assert_eq!(
+            source("(fun (a, ...rest) { return a; });"),
+            "(fun (a, ...rest) { ... })"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! The methods of booleans: `and`, `or`, `not` and `toString`.
+//!
+//! `and` and `or` behave like the operators of the same name, except that their argument
+//! is evaluated before the call, so they never short-circuit.
+
+use crate::builtins::BuiltinMethod;
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::Value;
+
+pub(crate) static METHODS: &[BuiltinMethod] = &[
+    BuiltinMethod {
+        name: "and",
+        arity: 1..=1,
+        function: and,
+    },
+    BuiltinMethod {
+        name: "or",
+        arity: 1..=1,
+        function: or,
+    },
+    BuiltinMethod {
+        name: "not",
+        arity: 0..=0,
+        function: not,
+    },
+    BuiltinMethod {
+        name: "toString",
+        arity: 0..=0,
+        function: to_string,
+    },
+];
+
+/// `boolean.and(value)` returns `value` if the boolean is true, otherwise `false`.
+fn and(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    match boolean(&ctx)? {
+        true => Ok(ctx.arguments[1].value.clone()),
+        false => Ok(Value::Boolean(false)),
+    }
+}
+
+/// `boolean.or(value)` returns `true` if the boolean is true, otherwise `value`.
+fn or(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    match boolean(&ctx)? {
+        true => Ok(Value::Boolean(true)),
+        false => Ok(ctx.arguments[1].value.clone()),
+    }
+}
+
+/// `boolean.not()` returns the negation, like `!boolean`.
+fn not(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Boolean(!boolean(&ctx)?))
+}
+
+/// `boolean.toString()` returns `"true"` or `"false"`.
+fn to_string(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Str(boolean(&ctx)?.to_string().into()))
+}
+
+fn boolean(ctx: &NativeContext) -> EvaluationResult<bool> {
+    match ctx.arguments[0].value {
+        Value::Boolean(b) => Ok(b),
+        _ => Err(ctx.type_error(0, "Boolean")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::evaluate_source;
+
+    use super::*;
+
+    fn assert_evaluates(source: &str, expected: Value) {
+        assert_eq!(evaluate_source(source).unwrap(), expected, "{source}");
+    }
+
+    #[test]
+    fn test_and_and_or() {
+        assert_evaluates("true.and(false);", Value::Boolean(false));
+        assert_evaluates("true.and(1);", Value::Number(1.0));
+        assert_evaluates("false.and(true);", Value::Boolean(false));
+        assert_evaluates("true.or(false);", Value::Boolean(true));
+        assert_evaluates("false.or(\"b\");", Value::Str("b".into()));
+    }
+
+    #[test]
+    fn test_not() {
+        assert_evaluates("true.not();", Value::Boolean(false));
+        assert_evaluates("var b = false; b.not();", Value::Boolean(true));
+    }
+
+    #[test]
+    fn test_to_string() {
+        assert_evaluates("true.toString();", Value::Str("true".into()));
+        assert_evaluates("(1 > 2).toString();", Value::Str("false".into()));
+    }
+}
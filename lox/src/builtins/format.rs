@@ -0,0 +1,252 @@
+//! `format(template, ...args)`, string interpolation as a native function.
+//!
+//! A placeholder is replaced by the string form of one of the arguments after the template:
+//! `{0}`, `{1}`, ... select an argument by position, `{}` takes the next argument in order
+//! and `{name}` names an argument, in the order the names first appear. A name can be
+//! repeated to use the same argument again. `{{` and `}}` produce literal braces.
+
+use std::collections::HashMap;
+
+use crate::callable::NativeContext;
+use crate::error::RuntimeError;
+use crate::evaluation::{EvaluationResult, Interpreter, ReturnOrError};
+use crate::value::Value;
+
+/// `format(template, ...args)` returns `template` with its placeholders replaced by `args`.
+pub(crate) fn format(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let template = ctx.string(0)?;
+    let arguments = ctx.arguments[1..]
+        .iter()
+        .map(|argument| argument.value.clone())
+        .collect::<Vec<_>>();
+
+    match format_str(&template, &arguments) {
+        Ok(formatted) => Ok(Value::Str(formatted.into())),
+        Err(FormatError::MissingArgument(index)) => {
+            Err(ReturnOrError::Error(RuntimeError::index_out_of_bounds(
+                index,
+                arguments.len(),
+                ctx.arguments[0].position.clone(),
+            )))
+        }
+        Err(FormatError::InvalidPlaceholder(placeholder)) => Err(ctx.invalid_argument_with_help(
+            0,
+            format!("invalid placeholder {{{placeholder}}}"),
+            "placeholders are {}, {0} or {name}; write {{ and }} for literal braces".to_string(),
+        )),
+        Err(FormatError::Unclosed) => Err(ctx.invalid_argument_with_help(
+            0,
+            "unclosed placeholder".to_string(),
+            "write {{ for a literal brace".to_string(),
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum FormatError {
+    /// A placeholder refers to the argument at this index, but there are fewer arguments.
+    MissingArgument(usize),
+    InvalidPlaceholder(String),
+    Unclosed,
+}
+
+/// Replaces the placeholders in `template` with the string form of `arguments`.
+pub(crate) fn format_str(template: &str, arguments: &[Value]) -> Result<String, FormatError> {
+    let mut formatted = String::with_capacity(template.len());
+    let mut names = HashMap::new();
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                formatted.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                formatted.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(FormatError::Unclosed),
+                    }
+                }
+
+                let index = if placeholder.is_empty() {
+                    next += 1;
+                    next - 1
+                } else if placeholder.chars().all(|c| c.is_ascii_digit()) {
+                    placeholder
+                        .parse()
+                        .map_err(|_| FormatError::InvalidPlaceholder(placeholder))?
+                } else if is_identifier(&placeholder) {
+                    *names.entry(placeholder).or_insert_with(|| {
+                        next += 1;
+                        next - 1
+                    })
+                } else {
+                    return Err(FormatError::InvalidPlaceholder(placeholder));
+                };
+
+                match arguments.get(index) {
+                    Some(argument) => formatted.push_str(&argument.to_string()),
+                    None => return Err(FormatError::MissingArgument(index)),
+                }
+            }
+            c => formatted.push(c),
+        }
+    }
+
+    Ok(formatted)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::LoxError;
+    use crate::evaluation::evaluate_source;
+    use crate::position::Position;
+
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<Value> {
+        values
+            .iter()
+            .map(|value| Value::Str((*value).into()))
+            .collect()
+    }
+
+    #[test]
+    fn test_no_placeholders() {
+        assert_eq!(format_str("plain text", &[]).unwrap(), "plain text");
+        assert_eq!(format_str("", &strings(&["unused"])).unwrap(), "");
+    }
+
+    #[test]
+    fn test_positional() {
+        let arguments = strings(&["a", "b", "c"]);
+        assert_eq!(format_str("{2}{0}{1}", &arguments).unwrap(), "cab");
+    }
+
+    #[test]
+    fn test_sequential() {
+        let arguments = strings(&["a", "b"]);
+        assert_eq!(format_str("{} and {}", &arguments).unwrap(), "a and b");
+    }
+
+    #[test]
+    fn test_named() {
+        let arguments = strings(&["World", "!"]);
+        assert_eq!(
+            format_str("Hello {name}{punctuation}", &arguments).unwrap(),
+            "Hello World!"
+        );
+    }
+
+    #[test]
+    fn test_repeated_positional() {
+        let arguments = strings(&["ab"]);
+        assert_eq!(format_str("{0}-{0}-{0}", &arguments).unwrap(), "ab-ab-ab");
+    }
+
+    #[test]
+    fn test_repeated_name() {
+        let arguments = strings(&["x", "y"]);
+        assert_eq!(format_str("{a}{b}{a}{b}{a}", &arguments).unwrap(), "xyxyx");
+    }
+
+    #[test]
+    fn test_named_and_sequential_share_the_order() {
+        let arguments = strings(&["a", "b", "c"]);
+        assert_eq!(
+            format_str("{first} {} {third}", &arguments).unwrap(),
+            "a b c"
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces() {
+        let arguments = strings(&["x"]);
+        assert_eq!(format_str("{{{}}} }}{{", &arguments).unwrap(), "{x} }{");
+    }
+
+    #[test]
+    fn test_unicode() {
+        let arguments = strings(&["wörld"]);
+        assert_eq!(
+            format_str("héllo {näme} ✓", &arguments).unwrap(),
+            "héllo wörld ✓"
+        );
+    }
+
+    #[test]
+    fn test_missing_argument() {
+        let arguments = strings(&["a"]);
+        assert_eq!(
+            format_str("{} {}", &arguments),
+            Err(FormatError::MissingArgument(1))
+        );
+        assert_eq!(
+            format_str("{3}", &arguments),
+            Err(FormatError::MissingArgument(3))
+        );
+    }
+
+    #[test]
+    fn test_invalid_placeholders() {
+        assert_eq!(
+            format_str("{a b}", &[]),
+            Err(FormatError::InvalidPlaceholder("a b".to_string()))
+        );
+        assert_eq!(format_str("text {0", &[]), Err(FormatError::Unclosed));
+    }
+
+    #[test]
+    fn test_format_native() {
+        let source = "var name = \"Lox\"; format(\"Hello {name}, {}!\", name, 1 + 2);";
+        assert_eq!(
+            evaluate_source(source).unwrap(),
+            Value::Str("Hello Lox, 3!".into())
+        );
+    }
+
+    #[test]
+    fn test_format_stringifies_every_type() {
+        let source = "format(\"{} {} {} {}\", nil, true, 1.5, [1, \"a\"]);";
+        assert_eq!(
+            evaluate_source(source).unwrap(),
+            Value::Str("Nil true 1.5 [1, a]".into())
+        );
+    }
+
+    #[test]
+    fn test_format_with_spread_arguments() {
+        let source = "var parts = [\"a\", \"b\"]; format(\"{1}{0}\", ...parts);";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Str("ba".into()));
+    }
+
+    #[test]
+    fn test_format_reports_missing_arguments() {
+        match evaluate_source("format(\"{0} {1}\", 1);") {
+            Err(LoxError::RuntimeError(RuntimeError::IndexOutOfBounds {
+                index,
+                length,
+                position,
+            })) => {
+                assert_eq!((index, length), (1, 1));
+                assert_eq!(position, Position::new(7, 9));
+            }
+            result => panic!("expected an index out of bounds error, got {result:?}"),
+        }
+    }
+}
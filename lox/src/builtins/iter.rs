@@ -0,0 +1,214 @@
+//! `forEach`, `mapArray`, `filterArray` and `reduce`, which call a function for every element
+//! of an array.
+//!
+//! They iterate over the elements the array had when they were called, so the function can
+//! push to or pop from the array without changing which elements it is called with.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::{Value, ValueNode};
+
+/// `forEach(array, f)` calls `f(element, index)` for every element and returns nil. The index
+/// is only passed if `f` takes at least two arguments, so `forEach(array, print)`-style
+/// calls with one-argument functions work as well.
+pub(crate) fn for_each(
+    ctx: NativeContext,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let elements = elements(&ctx)?;
+    let with_index = ctx.arguments[1]
+        .value
+        .arity()
+        .is_some_and(|arity| arity >= 2);
+
+    for (index, element) in elements.into_iter().enumerate() {
+        let mut arguments = vec![element];
+        if with_index {
+            arguments.push(Value::Number(index as f64));
+        }
+        call(&ctx, 1, arguments, interpreter)?;
+    }
+
+    Ok(Value::Nil)
+}
+
+/// `mapArray(array, f)` returns a new array of `f(element)` for every element.
+pub(crate) fn map_array(
+    ctx: NativeContext,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let mapped = elements(&ctx)?
+        .into_iter()
+        .map(|element| call(&ctx, 1, vec![element], interpreter))
+        .collect::<EvaluationResult<Vec<_>>>()?;
+
+    Ok(Value::Array(Rc::new(RefCell::new(mapped))))
+}
+
+/// `filterArray(array, predicate)` returns a new array of the elements for which
+/// `predicate(element)` is truthy.
+pub(crate) fn filter_array(
+    ctx: NativeContext,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let mut filtered = Vec::new();
+    for element in elements(&ctx)? {
+        if call(&ctx, 1, vec![element.clone()], interpreter)?.is_truthy() {
+            filtered.push(element);
+        }
+    }
+
+    Ok(Value::Array(Rc::new(RefCell::new(filtered))))
+}
+
+/// `reduce(array, initial, f)` folds the elements from the left: `f(f(initial, a), b)` for
+/// `[a, b]`. Returns `initial` for an empty array.
+pub(crate) fn reduce(ctx: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    let mut accumulator = ctx.arguments[1].value.clone();
+    for element in elements(&ctx)? {
+        accumulator = call(&ctx, 2, vec![accumulator, element], interpreter)?;
+    }
+
+    Ok(accumulator)
+}
+
+/// The elements of the array argument, after checking that the last argument is callable.
+fn elements(ctx: &NativeContext) -> EvaluationResult<Vec<Value>> {
+    let array = ctx.array(0)?;
+    let function = ctx.argument_count() - 1;
+    if !ctx.arguments[function].value.is_callable() {
+        return Err(ctx.type_error(function, "Function"));
+    }
+
+    let elements = array.borrow().clone();
+    Ok(elements)
+}
+
+/// Calls the function argument at `index` with `arguments`, which are reported at the array.
+fn call(
+    ctx: &NativeContext,
+    index: usize,
+    arguments: Vec<Value>,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let arguments = arguments
+        .into_iter()
+        .map(|argument| ValueNode::new(argument, &ctx.arguments[0].position))
+        .collect();
+
+    ctx.arguments[index].call(arguments, &ctx.position, interpreter)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+
+    fn evaluate_to_string(source: &str) -> String {
+        evaluate_source(source).unwrap().to_string()
+    }
+
+    const DOUBLE: &str = "fun double(x) { return x * 2; }";
+    const IS_ODD: &str = "fun isOdd(x) { return x - 2 * (x / 2).toInt() == 1; }";
+    const ADD: &str = "fun add(a, b) { return a + b; }";
+
+    #[test]
+    fn test_map_array() {
+        assert_eq!(
+            evaluate_to_string(&format!("{DOUBLE} mapArray([1, 2, 3], double);")),
+            "[2, 4, 6]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{DOUBLE} mapArray([], double);")),
+            "[]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{DOUBLE} mapArray([5], double);")),
+            "[10]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{DOUBLE} var a = [1]; mapArray(a, double); a;")),
+            "[1]"
+        );
+    }
+
+    #[test]
+    fn test_filter_array() {
+        assert_eq!(
+            evaluate_to_string(&format!("{IS_ODD} filterArray([1, 2, 3, 4, 5], isOdd);")),
+            "[1, 3, 5]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{IS_ODD} filterArray([], isOdd);")),
+            "[]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{IS_ODD} filterArray([2], isOdd);")),
+            "[]"
+        );
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(
+            evaluate_to_string(&format!("{ADD} reduce([1, 2, 3, 4], 0, add);")),
+            "10"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{ADD} reduce([], 7, add);")),
+            "7"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{ADD} reduce([\"b\", \"c\"], \"a\", add);")),
+            "abc"
+        );
+    }
+
+    #[test]
+    fn test_for_each() {
+        let source =
+            "var seen = []; forEach([\"a\", \"b\"], fun (e, i) { seen.push([e, i]); }); seen;";
+        assert_eq!(evaluate_to_string(source), "[[a, 0], [b, 1]]");
+
+        let source = "var seen = []; forEach([1, 2], fun (e) { seen.push(e); }); seen;";
+        assert_eq!(evaluate_to_string(source), "[1, 2]");
+
+        let source = "var calls = 0; forEach([], fun (e) { calls = calls + 1; }); calls;";
+        assert_eq!(evaluate_to_string(source), "0");
+    }
+
+    #[test]
+    fn test_iterates_over_a_snapshot() {
+        let source = "var a = [1, 2]; forEach(a, fun (e) { a.push(e); }); a;";
+        assert_eq!(evaluate_to_string(source), "[1, 2, 1, 2]");
+    }
+
+    #[test]
+    fn test_errors() {
+        for source in [
+            "mapArray([1], 1);",
+            "mapArray(1, ord);",
+            "reduce([], 0, nil);",
+        ] {
+            assert!(
+                matches!(
+                    evaluate_source(source),
+                    Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+                ),
+                "{source}"
+            );
+        }
+
+        assert!(matches!(
+            evaluate_source("filterArray([1], fun (a, b) { return true; });"),
+            Err(LoxError::RuntimeError(RuntimeError::ArityMismatch { .. }))
+        ));
+        assert!(matches!(
+            evaluate_source("mapArray([1], fun (x) { return -\"a\"; });"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+    }
+}
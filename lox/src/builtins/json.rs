@@ -0,0 +1,235 @@
+//! `jsonParse` and `jsonStringify`.
+//!
+//! JSON objects map to maps with string keys, arrays to arrays, strings, numbers and
+//! booleans to their Lox counterparts and `null` to `nil`.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::Serializer;
+
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::{MapKey, Value};
+
+/// `jsonParse(text)` parses `text` as JSON.
+pub(crate) fn json_parse(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let text = ctx.string(0)?;
+
+    match serde_json::from_str(&text) {
+        Ok(json) => Ok(from_json(json)),
+        Err(error) => Err(ctx.invalid_argument(0, format!("invalid JSON: {error}"))),
+    }
+}
+
+/// `jsonStringify(value, indent?)` converts `value` to JSON, pretty-printed with `indent`
+/// spaces per level if given. Map keys are sorted. Functions, namespaces, non-finite
+/// numbers and cyclic structures can't be converted.
+pub(crate) fn json_stringify(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let json = to_json(&ctx, &ctx.arguments[0].value, &mut Vec::new())?;
+
+    let text = match ctx.argument_count() {
+        2 => {
+            let width = ctx.integer(1)?;
+            if !(0..=16).contains(&width) {
+                return Err(ctx.invalid_argument(1, "indent must be between 0 and 16".to_string()));
+            }
+
+            let indent = " ".repeat(width as usize);
+            let mut output = Vec::new();
+            let mut serializer = Serializer::with_formatter(
+                &mut output,
+                PrettyFormatter::with_indent(indent.as_bytes()),
+            );
+            json.serialize(&mut serializer)
+                .expect("serializing to memory can't fail");
+            String::from_utf8(output).expect("JSON output is valid UTF-8")
+        }
+        _ => json.to_string(),
+    };
+
+    Ok(Value::Str(text.into()))
+}
+
+fn from_json(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(s) => Value::Str(s.into()),
+        serde_json::Value::Array(elements) => {
+            let elements = elements.into_iter().map(from_json).collect();
+            Value::Array(Rc::new(RefCell::new(elements)))
+        }
+        serde_json::Value::Object(entries) => {
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| (MapKey::Str(key.into()), from_json(value)))
+                .collect();
+            Value::Map(Rc::new(RefCell::new(entries)))
+        }
+    }
+}
+
+/// Converts `value`, which is the first argument or nested inside it. `path` holds the arrays
+/// and maps currently being converted, to detect cycles.
+fn to_json(
+    ctx: &NativeContext,
+    value: &Value,
+    path: &mut Vec<*const ()>,
+) -> EvaluationResult<serde_json::Value> {
+    let error = |reason: String| ctx.invalid_argument(0, reason);
+
+    let json = match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Boolean(b) => serde_json::Value::Bool(*b),
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+            serde_json::Value::from(*n as i64)
+        }
+        Value::Number(n) => match serde_json::Number::from_f64(*n) {
+            Some(n) => serde_json::Value::Number(n),
+            None => return Err(error(format!("{n} can't be represented in JSON"))),
+        },
+        Value::Str(s) => serde_json::Value::String(s.to_string()),
+        Value::Array(array) => {
+            enter(path, Rc::as_ptr(array) as *const ()).map_err(error)?;
+            let elements = array
+                .borrow()
+                .iter()
+                .map(|element| to_json(ctx, element, path))
+                .collect::<EvaluationResult<Vec<_>>>()?;
+            path.pop();
+
+            serde_json::Value::Array(elements)
+        }
+        Value::Map(map) => {
+            enter(path, Rc::as_ptr(map) as *const ()).map_err(error)?;
+            let entries = map
+                .borrow()
+                .iter()
+                .map(|(key, value)| Ok((key.to_string(), to_json(ctx, value, path)?)))
+                .collect::<EvaluationResult<serde_json::Map<_, _>>>()?;
+            path.pop();
+
+            serde_json::Value::Object(entries)
+        }
+        _ => {
+            return Err(error(format!(
+                "a {} can't be converted to JSON",
+                value.type_name()
+            )))
+        }
+    };
+
+    Ok(json)
+}
+
+fn enter(path: &mut Vec<*const ()>, pointer: *const ()) -> Result<(), String> {
+    if path.contains(&pointer) {
+        return Err("cyclic structures can't be converted to JSON".to_string());
+    }
+
+    path.push(pointer);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::callable::{NativeFunction, NativeFunctionContainer};
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+    use crate::position::Position;
+    use crate::value::ValueNode;
+
+    use super::*;
+
+    /// Calls `function` with the single `argument`, bypassing the scanner since Lox strings
+    /// can't contain quotes.
+    fn call(function: NativeFunction, argument: Value, interpreter: &mut Interpreter) -> Value {
+        let position = Position::new(0, 1);
+        let argument = ValueNode::new(argument, &position);
+
+        NativeFunctionContainer::new("native", 1..=2, function)
+            .call_with_receiver(None, vec![argument], &position, interpreter)
+            .unwrap_or_else(|_| panic!())
+    }
+
+    fn round_trip(json: &str) -> String {
+        let mut interpreter = Interpreter::new();
+        let value = call(json_parse, Value::Str(json.into()), &mut interpreter);
+        let json = call(json_stringify, value, &mut interpreter);
+
+        json.to_string()
+    }
+
+    #[test]
+    fn test_round_trip_nested_structures() {
+        let json = r#"{"a":[1,2.5,{"b":null}],"c":true,"d":{"e":"f"}}"#;
+        assert_eq!(round_trip(json), json);
+        assert_eq!(round_trip("[]"), "[]");
+    }
+
+    #[test]
+    fn test_round_trip_unicode() {
+        assert_eq!(
+            round_trip(r#"["äöü","漢字","😀"]"#),
+            r#"["äöü","漢字","😀"]"#
+        );
+        assert_eq!(round_trip(r#""ä""#), r#""ä""#);
+    }
+
+    #[test]
+    fn test_parse_maps_values() {
+        let mut interpreter = Interpreter::new();
+        let json = Value::Str(r#"{"xs": [1, 2], "n": null}"#.into());
+        let value = call(json_parse, json, &mut interpreter);
+
+        assert_eq!(value.to_string(), "{n: Nil, xs: [1, 2]}");
+    }
+
+    #[test]
+    fn test_parse_error_includes_position() {
+        match evaluate_source("jsonParse(\"[1, 2\");") {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { reason, .. })) => {
+                assert!(reason.contains("line 1 column 5"), "{reason}")
+            }
+            _ => panic!("expected an invalid argument error"),
+        }
+    }
+
+    #[test]
+    fn test_stringify_with_indent() {
+        assert_eq!(
+            evaluate_source("jsonStringify([1, [2]], 2);")
+                .unwrap()
+                .to_string(),
+            "[\n  1,\n  [\n    2\n  ]\n]"
+        );
+    }
+
+    #[test]
+    fn test_stringify_rejects_cycles_and_functions() {
+        for source in [
+            "var a = [1]; a.push(a); jsonStringify(a);",
+            "var m = Map(); m[\"self\"] = [m]; jsonStringify(m);",
+            "jsonStringify([ord]);",
+        ] {
+            assert!(
+                matches!(
+                    evaluate_source(source),
+                    Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+                ),
+                "{source}"
+            );
+        }
+
+        assert_eq!(
+            evaluate_source("var a = [1]; jsonStringify([a, a]);")
+                .unwrap()
+                .to_string(),
+            "[[1],[1]]"
+        );
+    }
+}
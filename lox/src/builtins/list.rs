@@ -0,0 +1,283 @@
+//! The built-in `List` class.
+//!
+//! `List()` creates an empty array and arrays answer the list methods `push`, `pop`, `size`,
+//! `get` and `set`, so `var l = List(); l.push(1);` and `var l = [1];` create the same
+//! value. Elements live in a Rust `Vec` behind an `Rc<RefCell<_>>`: indexing and `push` are
+//! O(1), and copying a list only copies the pointer. Implementing `List` as a class in Lox
+//! with a hidden field would keep the language uniform, but every method call would then go
+//! through a Lox function call and an environment lookup before reaching the `Vec`.
+//!
+//! `sort` and `sortBy` sort arrays in place with a merge sort of their own rather than
+//! `slice::sort_by`: a comparison can fail, and a Lox comparator is not guaranteed to be
+//! a total order, which `sort_by` is allowed to panic on.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::builtins::BuiltinMethod;
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::{Value, ValueNode};
+
+pub(crate) static METHODS: &[BuiltinMethod] = &[
+    BuiltinMethod {
+        name: "push",
+        arity: 1..=1,
+        function: push,
+    },
+    BuiltinMethod {
+        name: "pop",
+        arity: 0..=0,
+        function: pop,
+    },
+    BuiltinMethod {
+        name: "size",
+        arity: 0..=0,
+        function: size,
+    },
+    BuiltinMethod {
+        name: "get",
+        arity: 1..=1,
+        function: get,
+    },
+    BuiltinMethod {
+        name: "set",
+        arity: 2..=2,
+        function: set,
+    },
+];
+
+/// `List()` creates an empty list.
+pub(crate) fn new_list(_: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Array(Rc::new(RefCell::new(Vec::new()))))
+}
+
+/// `list.push(value)` appends `value` and returns the new size.
+fn push(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let list = ctx.array(0)?;
+    let mut list = list.borrow_mut();
+    list.push(ctx.arguments[1].value.clone());
+
+    Ok(Value::Number(list.len() as f64))
+}
+
+/// `list.pop()` removes and returns the last element, or `nil` if the list is empty.
+fn pop(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let list = ctx.array(0)?;
+    let value = list.borrow_mut().pop();
+
+    Ok(value.unwrap_or(Value::Nil))
+}
+
+/// `list.size()` returns the number of elements.
+fn size(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let list = ctx.array(0)?;
+    let size = list.borrow().len();
+
+    Ok(Value::Number(size as f64))
+}
+
+/// `list.get(index)` is the same as `list[index]`.
+fn get(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    ctx.arguments[0].get_index(&ctx.arguments[1])
+}
+
+/// `list.set(index, value)` is the same as `list[index] = value`.
+fn set(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    ctx.arguments[0].set_index(&ctx.arguments[1], ctx.arguments[2].value.clone())?;
+
+    Ok(Value::Nil)
+}
+
+/// `sort(array)` sorts numbers, strings or booleans in ascending order, in place, and returns
+/// the array. Elements of different types can't be compared and make the sort fail.
+pub(crate) fn sort(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let array = ctx.array(0)?;
+    let values = array.borrow().clone();
+
+    let sorted = merge_sort(values, &mut |left, right| {
+        left.partial_cmp(right).ok_or_else(|| {
+            ctx.invalid_argument(
+                0,
+                format!(
+                    "cannot compare {} with {}",
+                    left.type_name(),
+                    right.type_name()
+                ),
+            )
+        })
+    })?;
+    *array.borrow_mut() = sorted;
+
+    Ok(ctx.arguments[0].value.clone())
+}
+
+/// `sortBy(array, comparator)` sorts the array in place and returns it. `comparator(a, b)`
+/// returns a negative number if `a` goes before `b`, a positive number if it goes after and
+/// zero if their order does not matter. The sort is stable.
+pub(crate) fn sort_by(
+    ctx: NativeContext,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let array = ctx.array(0)?;
+    let values = array.borrow().clone();
+    let comparator = &ctx.arguments[1];
+
+    // The array is not borrowed while the comparator runs, so it can read the array.
+    let sorted = merge_sort(values, &mut |left, right| {
+        let arguments = vec![
+            ValueNode::new(left.clone(), &ctx.arguments[0].position),
+            ValueNode::new(right.clone(), &ctx.arguments[0].position),
+        ];
+
+        match comparator.call(arguments, &ctx.position, interpreter)? {
+            Value::Number(n) if n < 0.0 => Ok(Ordering::Less),
+            Value::Number(n) if n > 0.0 => Ok(Ordering::Greater),
+            Value::Number(_) => Ok(Ordering::Equal),
+            result => Err(ctx.invalid_argument(
+                1,
+                format!(
+                    "comparator must return a Number, returned {}",
+                    result.type_name()
+                ),
+            )),
+        }
+    })?;
+    *array.borrow_mut() = sorted;
+
+    Ok(ctx.arguments[0].value.clone())
+}
+
+/// A stable merge sort that stops at the first failed comparison.
+fn merge_sort(
+    mut values: Vec<Value>,
+    compare: &mut dyn FnMut(&Value, &Value) -> EvaluationResult<Ordering>,
+) -> EvaluationResult<Vec<Value>> {
+    if values.len() <= 1 {
+        return Ok(values);
+    }
+
+    let right = values.split_off(values.len() / 2);
+    let mut left = merge_sort(values, compare)?.into_iter().peekable();
+    let mut right = merge_sort(right, compare)?.into_iter().peekable();
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        let next = if compare(r, l)?.is_lt() {
+            right.next()
+        } else {
+            left.next()
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+
+    use super::*;
+
+    #[test]
+    fn test_push_pop_and_size() {
+        let source = "var l = List(); l.push(1); l.push(2); l.push(3); l.pop(); l;";
+        assert_eq!(evaluate_source(source).unwrap().to_string(), "[1, 2]");
+
+        let source = "var l = List(); l.push(\"a\"); l.size();";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(1.0));
+
+        assert_eq!(evaluate_source("List().pop();").unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_get_and_set() {
+        let source = "var l = [1, 2]; l.set(0, 5); l.get(0) + l[1];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(7.0));
+
+        assert!(matches!(
+            evaluate_source("[1].get(1);"),
+            Err(LoxError::RuntimeError(RuntimeError::InvalidArgument { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_methods_are_values() {
+        let source = "var l = List(); var push = l.push; push(1); push(2); l.size();";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(2.0));
+    }
+
+    #[test]
+    fn test_unknown_method_and_arity() {
+        assert!(matches!(
+            evaluate_source("List().shift();"),
+            Err(LoxError::RuntimeError(RuntimeError::UnknownMember { .. }))
+        ));
+        assert!(matches!(
+            evaluate_source("List().push();"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_sort() {
+        let source = "var l = [3, 1, 2]; sort(l); l;";
+        assert_eq!(evaluate_source(source).unwrap().to_string(), "[1, 2, 3]");
+
+        let source = "sort([\"pear\", \"apple\", \"fig\"]);";
+        assert_eq!(
+            evaluate_source(source).unwrap().to_string(),
+            "[apple, fig, pear]"
+        );
+
+        assert_eq!(evaluate_source("sort([]);").unwrap().to_string(), "[]");
+    }
+
+    #[test]
+    fn test_sort_mixed_types() {
+        match evaluate_source("sort([1, \"a\"]);") {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { reason, .. })) => {
+                assert_eq!(reason, "cannot compare String with Number");
+            }
+            result => panic!("expected an error, got {result:?}"),
+        }
+
+        assert!(evaluate_source("sort([2, 1, nil]);").is_err());
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let source = "var l = [1, 3, 2]; sortBy(l, fun (a, b) { return b - a; }); l;";
+        assert_eq!(evaluate_source(source).unwrap().to_string(), "[3, 2, 1]");
+
+        let source = "sortBy([], fun (a, b) { return 0; });";
+        assert_eq!(evaluate_source(source).unwrap().to_string(), "[]");
+    }
+
+    #[test]
+    fn test_sort_by_is_stable() {
+        let source = "sortBy([[2, \"a\"], [1, \"b\"], [2, \"c\"], [1, \"d\"]],
+            fun (a, b) { return a[0] - b[0]; });";
+        assert_eq!(
+            evaluate_source(source).unwrap().to_string(),
+            "[[1, b], [1, d], [2, a], [2, c]]"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_comparator_errors() {
+        assert!(matches!(
+            evaluate_source("sortBy([1, 2], fun (a, b) { return \"less\"; });"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+        assert!(matches!(
+            evaluate_source("sortBy([1, 2], fun (a, b) { return a + nil; });"),
+            Err(LoxError::RuntimeError(RuntimeError::NilOperation { .. }))
+        ));
+    }
+}
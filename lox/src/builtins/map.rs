@@ -0,0 +1,212 @@
+//! The methods of maps: `get`, `set`, `keys`, `values`, `contains` and `delete`.
+//!
+//! Maps are created with `Map()` and indexed like arrays, `map[key]`. Keys are strings,
+//! numbers or booleans. `keys()` and `values()` list the entries in the order maps are
+//! printed in, sorted by the text of their keys, so the order does not change between runs.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::builtins::BuiltinMethod;
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::{MapKey, Value};
+
+pub(crate) static METHODS: &[BuiltinMethod] = &[
+    BuiltinMethod {
+        name: "get",
+        arity: 1..=1,
+        function: get,
+    },
+    BuiltinMethod {
+        name: "set",
+        arity: 2..=2,
+        function: set,
+    },
+    BuiltinMethod {
+        name: "keys",
+        arity: 0..=0,
+        function: keys,
+    },
+    BuiltinMethod {
+        name: "values",
+        arity: 0..=0,
+        function: values,
+    },
+    BuiltinMethod {
+        name: "contains",
+        arity: 1..=1,
+        function: contains,
+    },
+    BuiltinMethod {
+        name: "delete",
+        arity: 1..=1,
+        function: delete,
+    },
+];
+
+/// `map.get(key)` is the same as `map[key]`: the value of `key`, or `nil` if it is not set.
+fn get(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    ctx.arguments[0].get_index(&ctx.arguments[1])
+}
+
+/// `map.set(key, value)` is the same as `map[key] = value`, but returns the map so calls
+/// can be chained.
+fn set(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    ctx.arguments[0].set_index(&ctx.arguments[1], ctx.arguments[2].value.clone())?;
+
+    Ok(ctx.arguments[0].value.clone())
+}
+
+/// `map.keys()` returns an array of the keys.
+fn keys(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let keys = sorted_entries(&ctx)?
+        .into_iter()
+        .map(|(key, _)| key.to_value())
+        .collect();
+
+    Ok(Value::Array(Rc::new(RefCell::new(keys))))
+}
+
+/// `map.values()` returns an array of the values, in the same order as `keys()`.
+fn values(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let values = sorted_entries(&ctx)?
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect();
+
+    Ok(Value::Array(Rc::new(RefCell::new(values))))
+}
+
+/// `map.contains(key)` returns whether `key` is set, even if it is set to `nil`.
+fn contains(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let map = ctx.map(0)?;
+    let key = MapKey::from_value_node(&ctx.arguments[1])?;
+    let contains = map.borrow().contains_key(&key);
+
+    Ok(Value::Boolean(contains))
+}
+
+/// `map.delete(key)` removes `key` and returns whether it was set.
+fn delete(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let map = ctx.map(0)?;
+    let key = MapKey::from_value_node(&ctx.arguments[1])?;
+    let removed = map.borrow_mut().remove(&key);
+
+    Ok(Value::Boolean(removed.is_some()))
+}
+
+fn sorted_entries(ctx: &NativeContext) -> EvaluationResult<Vec<(MapKey, Value)>> {
+    let map = ctx.map(0)?;
+    let mut entries = map
+        .borrow()
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<Vec<_>>();
+    entries.sort_by_cached_key(|(key, _)| key.to_string());
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+
+    fn evaluate_to_string(source: &str) -> String {
+        evaluate_source(source).unwrap().to_string()
+    }
+
+    const EMPTY: &str = "var m = Map();";
+    const SINGLE: &str = "var m = Map(); m[\"a\"] = 1;";
+    const MULTIPLE: &str = "var m = Map(); m[\"b\"] = 2; m[\"a\"] = 1; m[\"c\"] = nil;";
+
+    #[test]
+    fn test_get() {
+        assert_eq!(evaluate_to_string(&format!("{EMPTY} m.get(\"a\");")), "Nil");
+        assert_eq!(evaluate_to_string(&format!("{SINGLE} m.get(\"a\");")), "1");
+        assert_eq!(
+            evaluate_to_string(&format!("{SINGLE} m.get(\"b\");")),
+            "Nil"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{MULTIPLE} [m.get(\"b\"), m[\"b\"]];")),
+            "[2, 2]"
+        );
+    }
+
+    #[test]
+    fn test_set_returns_the_map() {
+        assert_eq!(
+            evaluate_to_string(&format!("{EMPTY} m.set(\"a\", 1).set(2, true); m;")),
+            "{2: true, a: 1}"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{SINGLE} m.set(\"a\", 5); m;")),
+            "{a: 5}"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{MULTIPLE} m.set(\"d\", 4) == m;")),
+            "true"
+        );
+    }
+
+    #[test]
+    fn test_keys_and_values() {
+        assert_eq!(evaluate_to_string(&format!("{EMPTY} m.keys();")), "[]");
+        assert_eq!(evaluate_to_string(&format!("{EMPTY} m.values();")), "[]");
+        assert_eq!(evaluate_to_string(&format!("{SINGLE} m.keys();")), "[a]");
+        assert_eq!(evaluate_to_string(&format!("{SINGLE} m.values();")), "[1]");
+        assert_eq!(
+            evaluate_to_string(&format!("{MULTIPLE} [m.keys(), m.values()];")),
+            "[[a, b, c], [1, 2, Nil]]"
+        );
+    }
+
+    #[test]
+    fn test_contains() {
+        assert_eq!(
+            evaluate_to_string(&format!("{EMPTY} m.contains(\"a\");")),
+            "false"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{SINGLE} [m.contains(\"a\"), m.contains(1)];")),
+            "[true, false]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{MULTIPLE} m.contains(\"c\");")),
+            "true"
+        );
+    }
+
+    #[test]
+    fn test_delete() {
+        assert_eq!(
+            evaluate_to_string(&format!("{EMPTY} [m.delete(\"a\"), m];")),
+            "[false, {}]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!("{SINGLE} [m.delete(\"a\"), m];")),
+            "[true, {}]"
+        );
+        assert_eq!(
+            evaluate_to_string(&format!(
+                "{MULTIPLE} [m.delete(\"b\"), m.delete(\"b\"), m];"
+            )),
+            "[true, false, {a: 1, c: Nil}]"
+        );
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        for method in ["get(nil)", "set([], 1)", "contains(nil)", "delete(Map())"] {
+            assert!(
+                matches!(
+                    evaluate_source(&format!("{EMPTY} m.{method};")),
+                    Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+                ),
+                "{method}"
+            );
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::ops::RangeInclusive;
+
+use crate::callable::{NativeFunction, NativeFunctionContainer};
+use crate::value::Value;
+
+pub(crate) mod boolean;
+pub(crate) mod format;
+pub(crate) mod iter;
+pub(crate) mod json;
+pub(crate) mod list;
+pub(crate) mod map;
+pub(crate) mod number;
+pub(crate) mod optional;
+pub(crate) mod pattern;
+#[cfg(feature = "random")]
+pub(crate) mod random;
+pub(crate) mod string;
+
+/// A native method of a built-in type. Its function receives the receiver as the first
+/// argument, followed by the call's arguments. `arity` does not count the receiver.
+pub(crate) struct BuiltinMethod {
+    pub(crate) name: &'static str,
+    pub(crate) arity: RangeInclusive<usize>,
+    pub(crate) function: NativeFunction,
+}
+
+impl BuiltinMethod {
+    pub(crate) fn to_container(&self) -> NativeFunctionContainer {
+        NativeFunctionContainer::new(self.name, self.arity.clone(), self.function)
+    }
+}
+
+/// The methods available on `value` through dot access, or `None` if its type has none.
+pub(crate) fn methods(value: &Value) -> Option<&'static [BuiltinMethod]> {
+    match value {
+        Value::Array(_) => Some(list::METHODS),
+        Value::Boolean(_) => Some(boolean::METHODS),
+        Value::Map(_) => Some(map::METHODS),
+        Value::Number(_) => Some(number::METHODS),
+        Value::Str(_) => Some(string::METHODS),
+        _ => None,
+    }
+}
+
+/// The method `name` of `value`: one of its type's methods, or else one of the methods every
+/// value has, see [`optional`].
+pub(crate) fn method(value: &Value, name: &str) -> Option<&'static BuiltinMethod> {
+    methods(value)
+        .unwrap_or_default()
+        .iter()
+        .chain(optional::METHODS)
+        .find(|method| method.name == name)
+}
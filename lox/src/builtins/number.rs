@@ -0,0 +1,123 @@
+//! The methods of numbers: `toInt`, `toFloat`, `toString`, `isNaN`, `isInfinite` and `abs`.
+//!
+//! Lox has a single number type, a 64-bit float, so `toInt` returns a number without a
+//! fractional part rather than a value of a different type, and `toFloat` returns the
+//! number as it is. Number literals need parentheses to call a method on them, `(42).abs()`.
+
+use crate::builtins::BuiltinMethod;
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::Value;
+
+pub(crate) static METHODS: &[BuiltinMethod] = &[
+    BuiltinMethod {
+        name: "toInt",
+        arity: 0..=0,
+        function: to_int,
+    },
+    BuiltinMethod {
+        name: "toFloat",
+        arity: 0..=0,
+        function: to_float,
+    },
+    BuiltinMethod {
+        name: "toString",
+        arity: 0..=0,
+        function: to_string,
+    },
+    BuiltinMethod {
+        name: "isNaN",
+        arity: 0..=0,
+        function: is_nan,
+    },
+    BuiltinMethod {
+        name: "isInfinite",
+        arity: 0..=0,
+        function: is_infinite,
+    },
+    BuiltinMethod {
+        name: "abs",
+        arity: 0..=0,
+        function: abs,
+    },
+];
+
+/// `number.toInt()` drops the fractional part, rounding toward zero. `NaN` and infinities
+/// are returned as they are.
+fn to_int(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Number(ctx.number(0)?.trunc()))
+}
+
+/// `number.toFloat()` returns the number, which already is a float.
+fn to_float(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Number(ctx.number(0)?))
+}
+
+/// `number.toString()` returns the number as `print` shows it.
+fn to_string(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let number = Value::Number(ctx.number(0)?);
+
+    Ok(Value::Str(number.to_string().into()))
+}
+
+/// `number.isNaN()` returns whether the number is not a number, e.g. the result of `0 / 0`.
+fn is_nan(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Boolean(ctx.number(0)?.is_nan()))
+}
+
+/// `number.isInfinite()` returns whether the number is positive or negative infinity.
+fn is_infinite(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Boolean(ctx.number(0)?.is_infinite()))
+}
+
+/// `number.abs()` returns the absolute value.
+fn abs(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Number(ctx.number(0)?.abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::evaluate_source;
+
+    use super::*;
+
+    fn assert_evaluates(source: &str, expected: Value) {
+        assert_eq!(evaluate_source(source).unwrap(), expected, "{source}");
+    }
+
+    #[test]
+    fn test_to_int_truncates_toward_zero() {
+        assert_evaluates("(42).toInt();", Value::Number(42.0));
+        assert_evaluates("(42.7).toInt();", Value::Number(42.0));
+        assert_evaluates("(-42.7).toInt();", Value::Number(-42.0));
+        assert_evaluates("(1 / 0).toInt();", Value::Number(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_to_float() {
+        assert_evaluates("(42.5).toFloat();", Value::Number(42.5));
+        assert_evaluates("(42).toFloat();", Value::Number(42.0));
+    }
+
+    #[test]
+    fn test_to_string() {
+        assert_evaluates("(42).toString();", Value::Str("42".into()));
+        assert_evaluates("(3.14).toString();", Value::Str("3.14".into()));
+        assert_evaluates("(-0.5).toString();", Value::Str("-0.5".into()));
+    }
+
+    #[test]
+    fn test_is_nan_and_is_infinite() {
+        assert_evaluates("(0 / 0).isNaN();", Value::Boolean(true));
+        assert_evaluates("(1).isNaN();", Value::Boolean(false));
+        assert_evaluates("(1 / 0).isInfinite();", Value::Boolean(true));
+        assert_evaluates("(-1 / 0).isInfinite();", Value::Boolean(true));
+        assert_evaluates("(0 / 0).isInfinite();", Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_abs() {
+        assert_evaluates("(-2.5).abs();", Value::Number(2.5));
+        assert_evaluates("var n = 3; n.abs();", Value::Number(3.0));
+    }
+}
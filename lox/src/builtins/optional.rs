@@ -0,0 +1,107 @@
+//! The methods every value has, which treat `nil` as the absence of a value: `orElse`,
+//! `andThen` and `isNil`.
+//!
+//! They are looked up after the methods of the value's type, see [`super::method`], so
+//! `nil.orElse(1)` works although `nil` has no other members.
+
+use crate::builtins::BuiltinMethod;
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::{Value, ValueNode};
+
+pub(crate) static METHODS: &[BuiltinMethod] = &[
+    BuiltinMethod {
+        name: "orElse",
+        arity: 1..=1,
+        function: or_else,
+    },
+    BuiltinMethod {
+        name: "andThen",
+        arity: 1..=1,
+        function: and_then,
+    },
+    BuiltinMethod {
+        name: "isNil",
+        arity: 0..=0,
+        function: is_nil,
+    },
+];
+
+/// `value.orElse(default)` returns `default` if the value is nil, otherwise the value.
+fn or_else(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let value = match ctx.arguments[0].is_nil() {
+        true => &ctx.arguments[1],
+        false => &ctx.arguments[0],
+    };
+
+    Ok(value.value.clone())
+}
+
+/// `value.andThen(f)` returns nil if the value is nil, otherwise the result of `f(value)`.
+fn and_then(ctx: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    let value = &ctx.arguments[0];
+    if value.is_nil() {
+        return Ok(Value::Nil);
+    }
+
+    let argument = ValueNode::new(value.value.clone(), &value.position);
+    ctx.arguments[1].call(vec![argument], &ctx.position, interpreter)
+}
+
+/// `value.isNil()` returns whether the value is nil.
+fn is_nil(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Boolean(ctx.arguments[0].is_nil()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+
+    use super::*;
+
+    fn assert_evaluates(source: &str, expected: Value) {
+        assert_eq!(evaluate_source(source).unwrap(), expected, "{source}");
+    }
+
+    #[test]
+    fn test_or_else() {
+        assert_evaluates("nil.orElse(42);", Value::Number(42.0));
+        assert_evaluates("(5).orElse(42);", Value::Number(5.0));
+        assert_evaluates("false.orElse(42);", Value::Boolean(false));
+        assert_evaluates("var m = Map(); m[\"a\"].orElse(0);", Value::Number(0.0));
+    }
+
+    #[test]
+    fn test_and_then() {
+        assert_evaluates("nil.andThen(fun (x) { return x * 2; });", Value::Nil);
+        assert_evaluates(
+            "(5).andThen(fun (x) { return x * 2; });",
+            Value::Number(10.0),
+        );
+        assert_evaluates(
+            "var calls = 0; nil.andThen(fun (x) { calls = calls + 1; }); calls;",
+            Value::Number(0.0),
+        );
+        assert!(matches!(
+            evaluate_source("(5).andThen(1);"),
+            Err(LoxError::RuntimeError(RuntimeError::NotCallable { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_is_nil() {
+        assert_evaluates("nil.isNil();", Value::Boolean(true));
+        assert_evaluates("(0).isNil();", Value::Boolean(false));
+        assert_evaluates("\"\".isNil();", Value::Boolean(false));
+        assert_evaluates("[].isNil();", Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_other_members_of_nil_fail() {
+        assert!(matches!(
+            evaluate_source("nil.size();"),
+            Err(LoxError::RuntimeError(RuntimeError::NilOperation { .. }))
+        ));
+    }
+}
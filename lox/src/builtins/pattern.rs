@@ -0,0 +1,102 @@
+//! `matches`, `find` and `findAll`, built on the `regex` crate.
+//!
+//! Patterns use the `regex` crate's syntax and are compiled once per interpreter, see
+//! `Interpreter::compile_pattern`. Capture groups are accepted in patterns but not yet
+//! exposed: every function works with the text of the whole match.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use regex::Regex;
+
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::Value;
+
+/// `matches(s, pattern)` returns whether `pattern` matches anywhere in `s`.
+pub(crate) fn matches(
+    ctx: NativeContext,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let text = ctx.string(0)?;
+    let regex = compile(&ctx, interpreter)?;
+
+    Ok(Value::Boolean(regex.is_match(&text)))
+}
+
+/// `find(s, pattern)` returns the first match of `pattern` in `s`, or nil.
+pub(crate) fn find(ctx: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    let text = ctx.string(0)?;
+    let regex = compile(&ctx, interpreter)?;
+
+    match regex.find(&text) {
+        Some(found) => Ok(Value::Str(found.as_str().into())),
+        None => Ok(Value::Nil),
+    }
+}
+
+/// `findAll(s, pattern)` returns an array of all non-overlapping matches of `pattern` in `s`.
+pub(crate) fn find_all(
+    ctx: NativeContext,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Value> {
+    let text = ctx.string(0)?;
+    let regex = compile(&ctx, interpreter)?;
+
+    let found = regex
+        .find_iter(&text)
+        .map(|found| Value::Str(found.as_str().into()))
+        .collect();
+
+    Ok(Value::Array(Rc::new(RefCell::new(found))))
+}
+
+fn compile(ctx: &NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Regex> {
+    let pattern = ctx.string(1)?;
+
+    interpreter
+        .compile_pattern(&pattern)
+        .map_err(|error| ctx.invalid_argument(1, error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+    use crate::position::Position;
+
+    #[test]
+    fn test_match() {
+        let source = "var s = \"call 555-1234 or 555-9876\";
+            [matches(s, \"[0-9]+-[0-9]+\"), find(s, \"[0-9]+-[0-9]+\"), findAll(s, \"[0-9]+\")];";
+
+        assert_eq!(
+            evaluate_source(source).unwrap().to_string(),
+            "[true, 555-1234, [555, 1234, 555, 9876]]"
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let source = "var s = \"no digits\";
+            [matches(s, \"[0-9]\"), find(s, \"[0-9]\"), findAll(s, \"[0-9]\")];";
+
+        assert_eq!(
+            evaluate_source(source).unwrap().to_string(),
+            "[false, Nil, []]"
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        match evaluate_source("find(\"text\", \"(unclosed\");") {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError {
+                reason, position, ..
+            })) => {
+                assert!(reason.contains("unclosed group"), "{reason}");
+                assert_eq!(position, Position::new(13, 11));
+            }
+            _ => panic!("expected an invalid pattern error"),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! `rand`, `randInt` and `seed`, built on the `rand` crate. Only available with the `random`
+//! feature.
+//!
+//! All interpreters on a thread share one generator. It starts from a random seed, so
+//! programs that need the same numbers on every run call `seed(n)` first.
+
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::Value;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// `rand()` returns a number in `[0, 1)`.
+pub(crate) fn rand(_: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let number = RNG.with(|rng| rng.borrow_mut().gen::<f64>());
+    Ok(Value::Number(number))
+}
+
+/// `randInt(min, max)` returns an integer between `min` and `max`, both included.
+pub(crate) fn rand_int(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let min = ctx.integer(0)?;
+    let max = ctx.integer(1)?;
+    if min > max {
+        return Err(ctx.invalid_argument(1, format!("max {max} is less than min {min}")));
+    }
+
+    let number = RNG.with(|rng| rng.borrow_mut().gen_range(min..=max));
+    Ok(Value::Number(number as f64))
+}
+
+/// `seed(n)` restarts the generator from the non-negative integer `n`. The same seed gives
+/// the same numbers.
+pub(crate) fn seed(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let seed = u64::try_from(ctx.integer(0)?)
+        .map_err(|_| ctx.invalid_argument(0, "seed must not be negative".to_string()))?;
+
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+    Ok(Value::Nil)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+
+    use super::*;
+
+    fn numbers(source: &str) -> Vec<f64> {
+        match evaluate_source(source).unwrap() {
+            Value::Array(values) => values
+                .borrow()
+                .iter()
+                .map(|value| match value {
+                    Value::Number(number) => *number,
+                    _ => panic!("expected a number"),
+                })
+                .collect(),
+            _ => panic!("expected an array"),
+        }
+    }
+
+    #[test]
+    fn test_seed_repeats_the_sequence() {
+        let source = "seed(42); [rand(), rand(), randInt(1, 100)];";
+        let first = numbers(source);
+
+        assert_eq!(numbers(source), first);
+        assert_ne!(
+            numbers("seed(43); [rand(), rand(), randInt(1, 100)];"),
+            first
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let source = "var xs = List(); for (var i = 0; i < 200; i = i + 1) xs.push(rand()); xs;";
+        assert!(numbers(source).iter().all(|x| (0.0..1.0).contains(x)));
+
+        let source =
+            "var xs = List(); for (var i = 0; i < 200; i = i + 1) xs.push(randInt(-2, 2)); xs;";
+        let integers = numbers(source);
+        assert!(integers
+            .iter()
+            .all(|x| (-2.0..=2.0).contains(x) && x.fract() == 0.0));
+        assert!(integers.contains(&-2.0) && integers.contains(&2.0));
+
+        assert_eq!(numbers("[randInt(7, 7)];"), vec![7.0]);
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        for source in [
+            "randInt(3, 1);",
+            "randInt(1.5, 2);",
+            "seed(-1);",
+            "seed(\"a\");",
+        ] {
+            assert!(
+                matches!(
+                    evaluate_source(source),
+                    Err(LoxError::RuntimeError(
+                        RuntimeError::NativeError { .. } | RuntimeError::TypeError { .. }
+                    ))
+                ),
+                "{source}"
+            );
+        }
+
+        match evaluate_source("randInt(3, 1);") {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { reason, .. })) => {
+                assert_eq!(reason, "max 1 is less than min 3")
+            }
+            _ => panic!("expected a native error"),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! The methods of strings: `split`, `trim`, `startsWith`, `endsWith` and `replace`.
+//!
+//! Strings are immutable, so every method returns a new value and leaves the receiver as it
+//! is. Characters are Unicode scalar values, so splitting on `""` never cuts a character in
+//! half.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::builtins::BuiltinMethod;
+use crate::callable::NativeContext;
+use crate::evaluation::{EvaluationResult, Interpreter};
+use crate::value::Value;
+
+pub(crate) static METHODS: &[BuiltinMethod] = &[
+    BuiltinMethod {
+        name: "split",
+        arity: 1..=1,
+        function: split,
+    },
+    BuiltinMethod {
+        name: "trim",
+        arity: 0..=0,
+        function: trim,
+    },
+    BuiltinMethod {
+        name: "startsWith",
+        arity: 1..=1,
+        function: starts_with,
+    },
+    BuiltinMethod {
+        name: "endsWith",
+        arity: 1..=1,
+        function: ends_with,
+    },
+    BuiltinMethod {
+        name: "replace",
+        arity: 2..=2,
+        function: replace,
+    },
+];
+
+/// `string.split(delimiter)` returns an array of the parts between occurrences of
+/// `delimiter`, which can be empty. An empty `delimiter` splits into single characters.
+fn split(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let string = ctx.string(0)?;
+    let delimiter = ctx.string(1)?;
+
+    let parts = match delimiter.as_ref() {
+        "" => string
+            .chars()
+            .map(|c| Value::Str(c.to_string().into()))
+            .collect(),
+        delimiter => string
+            .split(delimiter)
+            .map(|part| Value::Str(part.into()))
+            .collect(),
+    };
+
+    Ok(Value::Array(Rc::new(RefCell::new(parts))))
+}
+
+/// `string.trim()` returns the string without leading and trailing whitespace.
+fn trim(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let string = ctx.string(0)?;
+
+    Ok(Value::Str(string.trim().into()))
+}
+
+/// `string.startsWith(prefix)` returns whether the string starts with `prefix`.
+fn starts_with(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let string = ctx.string(0)?;
+    let prefix = ctx.string(1)?;
+
+    Ok(Value::Boolean(string.starts_with(prefix.as_ref())))
+}
+
+/// `string.endsWith(suffix)` returns whether the string ends with `suffix`.
+fn ends_with(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let string = ctx.string(0)?;
+    let suffix = ctx.string(1)?;
+
+    Ok(Value::Boolean(string.ends_with(suffix.as_ref())))
+}
+
+/// `string.replace(from, to)` returns the string with every occurrence of `from` replaced by
+/// `to`. An empty `from` inserts `to` between every two characters.
+fn replace(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let string = ctx.string(0)?;
+    let from = ctx.string(1)?;
+    let to = ctx.string(2)?;
+
+    let replaced = match from.as_ref() {
+        "" => string
+            .chars()
+            .map(String::from)
+            .collect::<Vec<_>>()
+            .join(&to),
+        from => string.replace(from, &to),
+    };
+
+    Ok(Value::Str(replaced.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+
+    use super::*;
+
+    fn string(value: &str) -> Value {
+        Value::Str(value.into())
+    }
+
+    fn strings(values: &[&str]) -> Value {
+        let values = values.iter().map(|value| string(value)).collect();
+        Value::Array(Rc::new(RefCell::new(values)))
+    }
+
+    fn assert_evaluates(source: &str, expected: Value) {
+        assert_eq!(evaluate_source(source).unwrap(), expected, "{source}");
+    }
+
+    #[test]
+    fn test_split() {
+        assert_evaluates("\"a,b,c\".split(\",\");", strings(&["a", "b", "c"]));
+        assert_evaluates("\"a, b\".split(\", \");", strings(&["a", "b"]));
+        assert_evaluates("\"abc\".split(\",\");", strings(&["abc"]));
+    }
+
+    #[test]
+    fn test_split_keeps_empty_parts() {
+        assert_evaluates("\"a,,b\".split(\",\");", strings(&["a", "", "b"]));
+        assert_evaluates("\",a,\".split(\",\");", strings(&["", "a", ""]));
+        assert_evaluates("\"\".split(\",\");", strings(&[""]));
+    }
+
+    #[test]
+    fn test_split_on_empty_delimiter() {
+        assert_evaluates("\"abc\".split(\"\");", strings(&["a", "b", "c"]));
+        assert_evaluates("\"äö😀\".split(\"\");", strings(&["ä", "ö", "😀"]));
+        assert_evaluates("\"\".split(\"\");", strings(&[]));
+    }
+
+    #[test]
+    fn test_trim() {
+        assert_evaluates("\"  a b  \".trim();", string("a b"));
+        assert_evaluates("\"\t\na\r\n\".trim();", string("a"));
+        assert_evaluates("\"   \".trim();", string(""));
+        assert_evaluates("\"a\".trim();", string("a"));
+    }
+
+    #[test]
+    fn test_trim_leaves_the_receiver() {
+        assert_evaluates("var s = \" a \"; s.trim(); s;", string(" a "));
+    }
+
+    #[test]
+    fn test_starts_with() {
+        assert_evaluates("\"lox\".startsWith(\"lo\");", Value::Boolean(true));
+        assert_evaluates("\"lox\".startsWith(\"ox\");", Value::Boolean(false));
+        assert_evaluates("\"lox\".startsWith(\"\");", Value::Boolean(true));
+        assert_evaluates("\"lo\".startsWith(\"lox\");", Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_ends_with() {
+        assert_evaluates("\"lox\".endsWith(\"ox\");", Value::Boolean(true));
+        assert_evaluates("\"lox\".endsWith(\"lo\");", Value::Boolean(false));
+        assert_evaluates("\"lox\".endsWith(\"\");", Value::Boolean(true));
+        assert_evaluates("\"\".endsWith(\"x\");", Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_replace() {
+        assert_evaluates("\"a-b-c\".replace(\"-\", \"+\");", string("a+b+c"));
+        assert_evaluates("\"aaa\".replace(\"aa\", \"b\");", string("ba"));
+        assert_evaluates("\"abc\".replace(\"x\", \"y\");", string("abc"));
+        assert_evaluates("\"abc\".replace(\"b\", \"\");", string("ac"));
+    }
+
+    #[test]
+    fn test_replace_empty_pattern() {
+        assert_evaluates("\"abc\".replace(\"\", \"-\");", string("a-b-c"));
+        assert_evaluates("\"a\".replace(\"\", \"-\");", string("a"));
+        assert_evaluates("\"\".replace(\"\", \"-\");", string(""));
+    }
+
+    #[test]
+    fn test_methods_are_values() {
+        assert_evaluates(
+            "var split = \"a b\".split; split(\" \");",
+            strings(&["a", "b"]),
+        );
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        for source in [
+            "\"a\".split(1);",
+            "\"a\".startsWith(nil);",
+            "\"a\".replace(\"a\", 1);",
+            "\"a\".trim(1);",
+        ] {
+            assert!(
+                matches!(
+                    evaluate_source(source),
+                    Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+                ),
+                "{source}"
+            );
+        }
+
+        assert!(matches!(
+            evaluate_source("\"a\".length();"),
+            Err(LoxError::RuntimeError(RuntimeError::UnknownMember { .. }))
+        ));
+    }
+}
@@ -1,58 +1,362 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
 use std::rc::Rc;
 
 use crate::environment::Environment;
-use crate::evaluation::{evaluate_statement, EvaluationResult};
+use crate::error::RuntimeError;
+use crate::evaluation::ReturnOrError::{Error, Return};
+use crate::evaluation::{
+    execute_statement, EvaluationResult, Interpreter, ReturnOrError, SourceId,
+};
+use crate::expression::Parameters;
+use crate::position::Position;
+use crate::profile::FunctionKey;
 use crate::statement::Statement;
-use crate::value::{Value, ValueNode};
+use crate::value::{MapKey, Value, ValueNode};
 
-pub(crate) trait Callable {
-    fn call(&self, arguments: Vec<ValueNode>) -> EvaluationResult<Value>;
+/// Everything a Lox program can call: functions declared in Lox, native functions and
+/// built-in methods bound to their receiver. `Value::Function` holds any of them, so a new
+/// kind of callable only needs to implement this trait. It is displayed as the value.
+pub trait Callable: Display {
+    /// Calls the function from the call at `call_position`, which errors about the
+    /// arguments as a whole, like an arity mismatch, are reported at.
+    fn call(
+        &self,
+        arguments: Vec<ValueNode>,
+        call_position: &Position,
+        interpreter: &mut Interpreter,
+    ) -> EvaluationResult<Value>;
 
+    /// The number of arguments the function requires. Optional arguments and rest
+    /// parameters are not counted.
     fn arity(&self) -> usize;
+
+    /// The name the function was declared or registered with, empty for lambdas.
+    fn name(&self) -> &str;
+}
+
+/// Functions are equal if they are the same function value.
+impl PartialEq for dyn Callable {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
 }
 
 pub struct FunctionContainer {
     pub id: String,
-    parameters: Vec<String>,
+    parameters: Parameters,
     body: Rc<Statement>,
     closure: Rc<RefCell<Environment>>,
+    /// The source the function was defined in, which the positions in its body point into.
+    source: SourceId,
 }
 
 impl FunctionContainer {
     pub(crate) fn new(
         name: &str,
-        parameters: &[String],
+        parameters: &Parameters,
         body: Rc<Statement>,
         closure: Rc<RefCell<Environment>>,
+        source: SourceId,
     ) -> FunctionContainer {
         FunctionContainer {
             id: name.to_string(),
-            parameters: parameters.to_owned(),
+            parameters: parameters.clone(),
             body,
             closure,
+            source,
+        }
+    }
+
+    pub(crate) fn accepts(&self, argument_count: usize) -> bool {
+        match self.parameters.rest {
+            Some(_) => argument_count >= self.parameters.names.len(),
+            None => argument_count == self.parameters.names.len(),
+        }
+    }
+
+    pub(crate) fn expected_arguments(&self) -> String {
+        let count = self.parameters.names.len();
+        match self.parameters.rest {
+            Some(_) => format!("at least {count}"),
+            None => count.to_string(),
         }
     }
 }
 
-impl PartialEq for FunctionContainer {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+impl Display for FunctionContainer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fun {}", self.id)
     }
 }
 
 impl Callable for FunctionContainer {
-    fn call(&self, arguments: Vec<ValueNode>) -> EvaluationResult<Value> {
+    fn call(
+        &self,
+        arguments: Vec<ValueNode>,
+        call_position: &Position,
+        interpreter: &mut Interpreter,
+    ) -> EvaluationResult<Value> {
+        if !self.accepts(arguments.len()) {
+            return Err(Error(RuntimeError::arity_mismatch(
+                self.expected_arguments(),
+                arguments.len(),
+                call_position.clone(),
+            )));
+        }
+
         let mut env = Environment::wrap(self.closure.clone());
+        let mut arguments = arguments.into_iter();
 
-        for (key, value) in self.parameters.iter().zip(arguments) {
+        for (key, value) in self.parameters.names.iter().zip(&mut arguments) {
             env.register(key.to_string(), Some(value.value))
         }
 
-        evaluate_statement(&self.body, Rc::new(RefCell::new(env)))
+        if let Some(rest) = &self.parameters.rest {
+            let values = arguments.map(|argument| argument.value).collect();
+            env.register(
+                rest.to_string(),
+                Some(Value::Array(Rc::new(RefCell::new(values)))),
+            );
+        }
+
+        // Closures of the same declaration share their body, and with it their profile.
+        interpreter.profile_enter(FunctionKey(Rc::as_ptr(&self.body) as usize), &self.id);
+        interpreter.enter_call();
+        let result = execute_statement(&self.body, Rc::new(RefCell::new(env)), interpreter);
+        interpreter.exit_call();
+        interpreter.profile_exit();
+
+        match result {
+            Ok(()) => Ok(Value::Nil),
+            Err(Return(value)) => Ok(value),
+            Err(Error(error)) => {
+                interpreter.raised_in(self.source);
+                Err(Error(error))
+            }
+            Err(other) => Err(other),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        self.parameters.names.len()
+    }
+
+    fn name(&self) -> &str {
+        &self.id
+    }
+}
+
+pub(crate) type NativeFunction = fn(NativeContext, &mut Interpreter) -> EvaluationResult<Value>;
+
+/// The call a native function is handling: the function's name, the position of the call and
+/// its arguments. For a built-in method the receiver is argument 0.
+///
+/// Natives report errors through the helpers, so that all of them render the same way.
+pub(crate) struct NativeContext {
+    pub(crate) name: String,
+    pub(crate) position: Position,
+    pub(crate) arguments: Vec<ValueNode>,
+    has_receiver: bool,
+}
+
+impl NativeContext {
+    /// The number of arguments, not counting the receiver.
+    pub(crate) fn argument_count(&self) -> usize {
+        self.arguments.len() - usize::from(self.has_receiver)
+    }
+
+    pub(crate) fn number(&self, index: usize) -> EvaluationResult<f64> {
+        match self.arguments[index].value {
+            Value::Number(number) => Ok(number),
+            _ => Err(self.type_error(index, "Number")),
+        }
+    }
+
+    /// Returns the argument if it is an integer, as [`ValueNode::as_integer`] checks it.
+    pub(crate) fn integer(&self, index: usize) -> EvaluationResult<i64> {
+        self.number(index)?;
+        self.arguments[index]
+            .as_integer()
+            .map_err(|_| self.type_error(index, "Integer"))
+    }
+
+    pub(crate) fn string(&self, index: usize) -> EvaluationResult<Rc<str>> {
+        match &self.arguments[index].value {
+            Value::Str(string) => Ok(string.clone()),
+            _ => Err(self.type_error(index, "String")),
+        }
+    }
+
+    pub(crate) fn array(&self, index: usize) -> EvaluationResult<Rc<RefCell<Vec<Value>>>> {
+        match &self.arguments[index].value {
+            Value::Array(array) => Ok(array.clone()),
+            _ => Err(self.type_error(index, "Array")),
+        }
+    }
+
+    pub(crate) fn map(
+        &self,
+        index: usize,
+    ) -> EvaluationResult<Rc<RefCell<HashMap<MapKey, Value>>>> {
+        match &self.arguments[index].value {
+            Value::Map(map) => Ok(map.clone()),
+            _ => Err(self.type_error(index, "Map")),
+        }
+    }
+
+    pub(crate) fn type_error(&self, index: usize, expected: &str) -> ReturnOrError {
+        let found = self.arguments[index].value.type_name();
+        self.invalid_argument(index, format!("expected {expected}, found {found}"))
+    }
+
+    pub(crate) fn arity_error(&self, expected: String) -> ReturnOrError {
+        let reason = format!(
+            "expected {expected} arguments but got {}",
+            self.argument_count()
+        );
+        self.error(reason, self.position.clone(), None)
+    }
+
+    pub(crate) fn invalid_argument(&self, index: usize, reason: String) -> ReturnOrError {
+        self.error(reason, self.arguments[index].position.clone(), None)
+    }
+
+    pub(crate) fn invalid_argument_with_help(
+        &self,
+        index: usize,
+        reason: String,
+        help: String,
+    ) -> ReturnOrError {
+        self.error(reason, self.arguments[index].position.clone(), Some(help))
+    }
+
+    fn error(&self, reason: String, position: Position, help: Option<String>) -> ReturnOrError {
+        Error(RuntimeError::native_error(
+            self.name.clone(),
+            reason,
+            position,
+            help,
+        ))
+    }
+}
+
+pub struct NativeFunctionContainer {
+    pub id: String,
+    arity: RangeInclusive<usize>,
+    function: NativeFunction,
+}
+
+impl NativeFunctionContainer {
+    pub(crate) fn new(
+        name: &str,
+        arity: RangeInclusive<usize>,
+        function: NativeFunction,
+    ) -> NativeFunctionContainer {
+        NativeFunctionContainer {
+            id: name.to_string(),
+            arity,
+            function,
+        }
+    }
+
+    pub(crate) fn expected_arguments(&self) -> String {
+        let (min, max) = (*self.arity.start(), *self.arity.end());
+        match max {
+            _ if min == max => min.to_string(),
+            usize::MAX => format!("at least {min}"),
+            _ => format!("{min} to {max}"),
+        }
+    }
+
+    /// Calls the function from the call at `position`. A `receiver` is passed as the first
+    /// argument, but does not count against the arity.
+    pub(crate) fn call_with_receiver(
+        &self,
+        receiver: Option<ValueNode>,
+        arguments: Vec<ValueNode>,
+        position: &Position,
+        interpreter: &mut Interpreter,
+    ) -> EvaluationResult<Value> {
+        let context = NativeContext {
+            name: self.id.clone(),
+            position: position.clone(),
+            has_receiver: receiver.is_some(),
+            arguments: receiver.into_iter().chain(arguments).collect(),
+        };
+
+        if !self.arity.contains(&context.argument_count()) {
+            return Err(context.arity_error(self.expected_arguments()));
+        }
+
+        interpreter.profile_enter(FunctionKey(self.function as usize), &self.id);
+        let result = (self.function)(context, interpreter);
+        interpreter.profile_exit();
+        result
+    }
+}
+
+impl Callable for NativeFunctionContainer {
+    fn call(
+        &self,
+        arguments: Vec<ValueNode>,
+        call_position: &Position,
+        interpreter: &mut Interpreter,
+    ) -> EvaluationResult<Value> {
+        self.call_with_receiver(None, arguments, call_position, interpreter)
     }
 
+    /// The minimum number of arguments.
     fn arity(&self) -> usize {
-        self.parameters.len()
+        *self.arity.start()
+    }
+
+    fn name(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Display for NativeFunctionContainer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "native fun {}", self.id)
+    }
+}
+
+/// A built-in method together with the value it was accessed on, e.g. `list.push`. The
+/// receiver is passed to the method's native function as the first argument.
+pub struct BoundMethod {
+    pub(crate) receiver: ValueNode,
+    pub(crate) method: NativeFunctionContainer,
+}
+
+impl Callable for BoundMethod {
+    fn call(
+        &self,
+        arguments: Vec<ValueNode>,
+        call_position: &Position,
+        interpreter: &mut Interpreter,
+    ) -> EvaluationResult<Value> {
+        self.method.call_with_receiver(
+            Some(self.receiver.clone()),
+            arguments,
+            call_position,
+            interpreter,
+        )
+    }
+
+    fn arity(&self) -> usize {
+        self.method.arity()
+    }
+
+    fn name(&self) -> &str {
+        self.method.name()
+    }
+}
+
+impl Display for BoundMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.method.fmt(f)
     }
 }
@@ -0,0 +1,83 @@
+//! `lox check`: finds the problems in a program without running it.
+
+use crate::error::{LoxError, Warning};
+use crate::parser;
+use crate::resolver;
+use crate::scanner::Scanner;
+
+/// The problems `check` found in a program.
+pub struct CheckResult {
+    pub errors: Vec<LoxError>,
+    pub warnings: Vec<Warning>,
+}
+
+impl CheckResult {
+    /// Whether the program has no errors. It may still have warnings.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Scans, parses and resolves `source`, collecting every error and warning. Nothing is
+/// evaluated, so checking a program has no side effects. The resolver checks the statements
+/// that could be parsed.
+pub fn check(source: &str) -> CheckResult {
+    let (tokens, mut errors) = Scanner::new(source.to_string()).scan_with_errors();
+    let parsed = parser::parse_with_recovery(&tokens);
+    errors.extend(parsed.errors);
+
+    CheckResult {
+        errors,
+        warnings: resolver::resolve(&parsed.statements),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{ParseError, ScanError};
+
+    use super::*;
+
+    #[test]
+    fn test_valid_program() {
+        let result = check("var a = 1;\nprint a;");
+        assert!(result.is_ok());
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_does_not_evaluate() {
+        assert!(check("panic(\"checked\"); print undefined;").is_ok());
+    }
+
+    #[test]
+    fn test_collects_scan_and_parse_errors() {
+        let result = check("var a = 1 #;\nprint ;\nloop {}");
+
+        assert!(!result.is_ok());
+        assert!(matches!(
+            result.errors.as_slice(),
+            [
+                LoxError::ScanError(ScanError::UnrecognizedCharacter { .. }),
+                LoxError::ParseError(ParseError::IllegalToken { .. })
+            ]
+        ));
+        assert!(matches!(
+            result.warnings.as_slice(),
+            [Warning::InfiniteLoop { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_warnings_are_not_errors() {
+        let result = check("loop { print 1; }");
+
+        assert!(result.is_ok());
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_program() {
+        assert!(check("// nothing here\n").is_ok());
+    }
+}
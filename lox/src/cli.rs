@@ -0,0 +1,1023 @@
+//! The `lox` command line: the REPL, running scripts and the `fmt`, `check` and
+//! `tokenize` tools.
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::{env, fs, io};
+
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme, NamedSource, SourceCode};
+use reedline::{
+    default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
+    EditCommand, Emacs, KeyCode, KeyModifiers, Keybindings, Prompt, PromptEditMode,
+    PromptHistorySearch, PromptHistorySearchStatus, PromptViMode, Reedline, ReedlineEvent, Signal,
+    Vi,
+};
+
+use crate::error::{AppError, Located, LoxError, Report, RuntimeError, ScanError, ScriptError};
+use crate::evaluation::Interpreter;
+use crate::repl::{ReplAction, ReplHighlighter, ReplValidator, Theme};
+use crate::scanner::Scanner;
+use crate::stdlib::Prelude;
+use crate::token::Token;
+use crate::watch::WatchEvent;
+use crate::{check, error, formatter, parser, repl, resolver, tokenize, watch};
+
+/// Debugging output requested on the command line.
+#[derive(Clone, Copy, Default)]
+struct DebugOutput {
+    /// Print the tokens of the source.
+    tokens: bool,
+    /// Print the statements the source parses into.
+    ast: bool,
+    /// Run the source after printing the debugging output, instead of stopping there.
+    run: bool,
+    /// Print the calls and times of every function to stderr after running.
+    profile: bool,
+    /// Print every statement to stderr before running it.
+    trace: bool,
+}
+
+impl DebugOutput {
+    fn stops_before_running(&self) -> bool {
+        (self.tokens || self.ast) && !self.run
+    }
+
+    /// An interpreter that prints to stdout, profiling and tracing as requested.
+    fn interpreter(&self, prelude: Prelude) -> Interpreter {
+        let mut interpreter =
+            Interpreter::with_prelude(prelude, Box::new(io::stdout())).with_profiling(self.profile);
+        if self.trace {
+            interpreter.set_trace(Some(Box::new(io::stderr())));
+        }
+        interpreter
+    }
+}
+
+/// The keybindings of the REPL's line editor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum EditingMode {
+    #[default]
+    Emacs,
+    Vi,
+}
+
+impl EditingMode {
+    /// Vi if `vi` is set by `--vi`, otherwise the mode named by the `LOX_EDIT_MODE`
+    /// environment variable, `emacs` or `vi`.
+    fn from_env(vi: bool) -> EditingMode {
+        let setting = env::var("LOX_EDIT_MODE").ok();
+        EditingMode::from_setting(vi, setting.as_deref())
+    }
+
+    fn from_setting(vi: bool, setting: Option<&str>) -> EditingMode {
+        match setting {
+            _ if vi => EditingMode::Vi,
+            Some(setting) if setting.eq_ignore_ascii_case("vi") => EditingMode::Vi,
+            _ => EditingMode::Emacs,
+        }
+    }
+}
+
+/// Everything the REPL's line editor is built from, see `create_repl`.
+struct ReplConfig {
+    editing_mode: EditingMode,
+    theme: Theme,
+    /// Suggest the rest of a line from history as it is typed.
+    hints: bool,
+}
+
+/// Prints diagnostics with their source, in color or as plain ASCII. Chosen once at startup
+/// and passed to everything that reports.
+#[derive(Clone, Copy)]
+struct Reporter {
+    color: bool,
+}
+
+impl Reporter {
+    /// Colors are used unless `no_color` is set, the `NO_COLOR` environment variable is set
+    /// or stdout is not a terminal.
+    fn from_env(no_color: bool) -> Reporter {
+        let no_color_env = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Reporter {
+            color: !no_color && !no_color_env && io::stdout().is_terminal(),
+        }
+    }
+
+    /// The theme for the REPL's syntax highlighting.
+    fn theme(&self) -> Theme {
+        if self.color {
+            Theme::colored()
+        } else {
+            Theme::plain()
+        }
+    }
+
+    fn render(
+        &self,
+        diagnostic: impl Diagnostic + Send + Sync + 'static,
+        source: impl SourceCode + 'static,
+    ) -> String {
+        let theme = if self.color {
+            GraphicalTheme::unicode()
+        } else {
+            GraphicalTheme::none()
+        };
+        let report = miette::Report::new(diagnostic).with_source_code(source);
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(theme)
+            .render_report(&mut rendered, report.as_ref())
+            .expect("rendering to a string does not fail");
+        rendered
+    }
+
+    /// Prints `diagnostic` together with the source it points into, with the line and
+    /// column it points to after its message.
+    fn print(&self, diagnostic: impl Diagnostic + Send + Sync + 'static, source: &str) {
+        let diagnostic = Located::new(diagnostic, source);
+        println!("{}", self.render(diagnostic, rendered_source(source)));
+    }
+
+    /// Reports `error` with the source it occurred in, named `name` if it is not the source
+    /// being run. A panic raised by the program with `panic()` is reported to stderr as just
+    /// its message, and so is the message of `exitWithError()`. `exit()` is not reported at
+    /// all.
+    fn report(&self, error: &LoxError, name: Option<&str>, source: &str) {
+        match error {
+            LoxError::RuntimeError(RuntimeError::UserPanic { message, .. }) => {
+                eprintln!("Panic: {message}");
+                return;
+            }
+            LoxError::RuntimeError(RuntimeError::Exit { message, .. }) => {
+                if let Some(message) = message {
+                    eprintln!("{message}");
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        match name {
+            Some(name) => {
+                let diagnostic = Located::new(error.clone(), source);
+                let source = NamedSource::new(name, rendered_source(source));
+                println!("{}", self.render(diagnostic, source));
+            }
+            None => self.print(error.clone(), source),
+        }
+    }
+
+    /// Prints every diagnostic of `report` in one block with the source they point into.
+    /// Prints nothing if the report is empty.
+    fn print_report(&self, report: Report, source: &str) {
+        if !report.is_empty() {
+            println!("{}", self.render(report, rendered_source(source)));
+        }
+    }
+
+    /// Prints `error` to stderr and returns its exit code. Lox errors are not printed, they
+    /// have already been reported by `run` together with their source.
+    fn report_app_error(&self, error: AppError) -> i32 {
+        let exit_code = error.exit_code();
+        match error {
+            AppError::Io(error) => eprintln!("{error}"),
+            AppError::Script(error) => eprint!("{}", self.render(error, String::new())),
+            AppError::Lox(_) => {}
+        }
+        exit_code
+    }
+
+    /// Like `print_report`, for the diagnostics of the script `name`.
+    fn report_in_file(&self, report: Report, name: &str, source: &str) {
+        if !report.is_empty() {
+            let source = NamedSource::new(name, rendered_source(source));
+            println!("{}", self.render(report, source));
+        }
+    }
+}
+
+/// `source` as it is rendered with diagnostics. miette only shows a label at the very end of
+/// the source, like the one of an error about input that ends too early, if a line break
+/// follows it, so one is added if the source doesn't end in one.
+fn rendered_source(source: &str) -> String {
+    let mut source = source.to_string();
+    if !source.ends_with('\n') {
+        source.push('\n');
+    }
+    source
+}
+
+/// Runs the `lox` command line with the arguments of the process and exits with its
+/// exit code.
+pub fn main() {
+    let mut file = None;
+    let mut prelude = Prelude::new();
+    let mut debug = DebugOutput::default();
+    let mut watch = false;
+    let mut vi = false;
+
+    let mut args = env::args_os().skip(1).collect::<Vec<_>>();
+    // Everything after `--` is for the script, see `args()`.
+    if let Some(index) = args.iter().position(|arg| arg == "--") {
+        let script_args = args.split_off(index).into_iter().skip(1);
+        prelude = prelude.with_script_args(
+            script_args
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+        );
+    }
+
+    // `--no-color` applies to every subcommand, so it is taken out before they parse theirs.
+    let no_color = args.iter().any(|arg| arg == "--no-color");
+    args.retain(|arg| arg != "--no-color");
+    let reporter = Reporter::from_env(no_color);
+
+    if args.first().is_some_and(|arg| arg == "fmt") {
+        std::process::exit(run_fmt(&args[1..], reporter));
+    }
+    if args.first().is_some_and(|arg| arg == "check") {
+        std::process::exit(run_check(&args[1..], reporter));
+    }
+    if args.first().is_some_and(|arg| arg == "tokenize") {
+        std::process::exit(run_tokenize(&args[1..], reporter));
+    }
+    if args.first().is_some_and(|arg| arg == "--explain") {
+        std::process::exit(run_explain(&args[1..]));
+    }
+
+    for arg in args {
+        if arg == "--allow-io" {
+            prelude = prelude.with_file_io(true);
+        } else if arg == "--print-tokens" {
+            debug.tokens = true;
+        } else if arg == "--print-ast" {
+            debug.ast = true;
+        } else if arg == "--run" {
+            debug.run = true;
+        } else if arg == "--profile" {
+            debug.profile = true;
+        } else if arg == "--trace" {
+            debug.trace = true;
+        } else if arg == "--watch" {
+            watch = true;
+        } else if arg == "--vi" {
+            vi = true;
+        } else if file.is_none() {
+            file = Some(arg);
+        } else {
+            println!(
+                "Usage: lox [--allow-io] [--print-tokens] [--print-ast] [--run] [--profile] [--trace] [script | -] [-- args...]"
+            );
+            println!("       lox --no-color ...");
+            println!("       lox --vi");
+            println!("       lox --watch [--allow-io] script");
+            println!("       lox fmt [--check] [script | -]");
+            println!("       lox check script...");
+            println!("       lox tokenize [script | -]");
+            println!("       lox --explain CODE");
+            std::process::exit(64);
+        }
+    }
+
+    // `-` or a pipe instead of a script reads the script from stdin.
+    let result = match file {
+        Some(file) if watch && file != "-" => run_watch(file, prelude, debug, reporter),
+        _ if watch => {
+            println!("Usage: lox --watch [--allow-io] script");
+            std::process::exit(64);
+        }
+        Some(file) if file == "-" => run_stdin(prelude, debug, reporter),
+        Some(file) => run_file(file, prelude, debug, reporter),
+        None if !io::stdin().is_terminal() => run_stdin(prelude, debug, reporter),
+        None => run_repl(prelude, debug, reporter, EditingMode::from_env(vi)),
+    };
+
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(error) => std::process::exit(reporter.report_app_error(error)),
+    }
+}
+
+fn run_file(
+    file: OsString,
+    prelude: Prelude,
+    debug: DebugOutput,
+    reporter: Reporter,
+) -> Result<(), AppError> {
+    let source = read_script(&file)?;
+    run_script(source, prelude, debug, reporter)
+}
+
+fn run_stdin(prelude: Prelude, debug: DebugOutput, reporter: Reporter) -> Result<(), AppError> {
+    let source = read_stdin()?;
+    run_script(source, prelude, debug, reporter)
+}
+
+fn read_script(file: &OsString) -> Result<String, ScriptError> {
+    fs::read_to_string(file).map_err(|error| {
+        let path = file.to_string_lossy().to_string();
+        match error.kind() {
+            io::ErrorKind::NotFound => {
+                let help = similar_file(Path::new(file))
+                    .map(|similar| format!("did you mean `{}`?", similar.display()));
+                ScriptError::NotFound { path, error, help }
+            }
+            io::ErrorKind::PermissionDenied => ScriptError::PermissionDenied { path, error },
+            io::ErrorKind::IsADirectory => ScriptError::IsADirectory { path, error },
+            _ => ScriptError::Unreadable { path, error },
+        }
+    })
+}
+
+/// The file next to `path` whose name is the closest to that of `path`, if it is close enough
+/// to be a typo: a third of the characters of the name may differ, but at least one and at
+/// most three.
+fn similar_file(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let max_distance = (name.chars().count() / 3).clamp(1, 3);
+
+    fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(|other| (edit_distance(name, &other), other))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, other)| path.with_file_name(other))
+}
+
+/// The Levenshtein distance of `a` and `b`: how many characters have to be inserted, removed
+/// or replaced to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn read_stdin() -> io::Result<String> {
+    let mut source = String::new();
+    io::stdin().read_to_string(&mut source)?;
+    Ok(source)
+}
+
+/// `lox fmt [--check] [script | -]` formats a script in place, or stdin to stdout. With
+/// `--check`, nothing is written and the exit code is 1 if the script is not formatted.
+/// Scripts that do not parse are never written. Returns the exit code.
+fn run_fmt(args: &[OsString], reporter: Reporter) -> i32 {
+    let mut check = false;
+    let mut file = None;
+
+    for arg in args {
+        if arg == "--check" {
+            check = true;
+        } else if file.is_none() {
+            file = Some(arg).filter(|file| *file != "-");
+        } else {
+            println!("Usage: lox fmt [--check] [script | -]");
+            return 64;
+        }
+    }
+
+    let source = match file {
+        Some(file) => read_script(file).map_err(AppError::from),
+        None => read_stdin().map_err(AppError::from),
+    };
+    let source = match source {
+        Ok(source) => source,
+        Err(error) => return reporter.report_app_error(error),
+    };
+
+    let formatted = match formatter::format_source(&source) {
+        Ok(formatted) => formatted,
+        Err(errors) => {
+            let mut report = Report::default();
+            for error in &errors {
+                report.push(error.clone(), &source);
+            }
+            reporter.print_report(report, &source);
+            return AppError::from(errors[0].clone()).exit_code();
+        }
+    };
+
+    if check {
+        if formatted == source {
+            return 0;
+        }
+        let name = file.map_or("stdin".into(), |file| file.to_string_lossy());
+        eprintln!("{name} is not formatted");
+        return 1;
+    }
+
+    match file {
+        Some(file) if formatted != source => match fs::write(file, formatted) {
+            Ok(()) => 0,
+            Err(error) => {
+                eprintln!("could not write {}: {error}", file.to_string_lossy());
+                AppError::from(error).exit_code()
+            }
+        },
+        Some(_) => 0,
+        None => {
+            print!("{formatted}");
+            0
+        }
+    }
+}
+
+fn run_script(
+    source: String,
+    prelude: Prelude,
+    debug: DebugOutput,
+    reporter: Reporter,
+) -> Result<(), AppError> {
+    let mut interpreter = debug.interpreter(prelude);
+    run(source, &mut interpreter, false, debug, reporter)
+}
+
+/// Runs `file` with a fresh interpreter, then again whenever it changes, until Ctrl-C.
+/// Errors are reported, but do not stop watching.
+fn run_watch(
+    file: OsString,
+    prelude: Prelude,
+    debug: DebugOutput,
+    reporter: Reporter,
+) -> Result<(), AppError> {
+    let (sender, mut events) = mpsc::channel();
+    let _watcher = watch::watch_file(Path::new(&file), sender.clone()).map_err(|error| {
+        let path = file.to_string_lossy();
+        io::Error::other(format!("could not watch {path}: {error}"))
+    })?;
+
+    // Ctrl-C stops a running loop first, then the watcher once the run has ended.
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupt.clone();
+    let handler = move || {
+        handler_flag.store(true, Ordering::Relaxed);
+        let _ = sender.send(WatchEvent::Stop);
+    };
+    if let Err(error) = ctrlc::set_handler(handler) {
+        eprintln!("Ctrl-C will not interrupt running code: {error}");
+    }
+
+    watch::watch(&mut events, watch::DEBOUNCE, || {
+        // Clear the screen and move the cursor to the top left corner.
+        print!("\x1b[2J\x1b[H");
+        let result = read_script(&file)
+            .map_err(AppError::from)
+            .and_then(|source| {
+                let mut interpreter = debug
+                    .interpreter(prelude.clone())
+                    .with_interrupt(interrupt.clone());
+                run(source, &mut interpreter, false, debug, reporter)
+            });
+        if let Err(error) = result {
+            reporter.report_app_error(error);
+        }
+        let _ = io::stdout().flush();
+    });
+
+    // Leave the shell prompt on a line of its own after the `^C`.
+    println!();
+    Ok(())
+}
+
+/// Runs the REPL until the user quits. Errors in the input are reported, but do not end
+/// the REPL. Ctrl-C discards the line being edited and stops a running loop, Ctrl-D quits.
+fn run_repl(
+    prelude: Prelude,
+    debug: DebugOutput,
+    reporter: Reporter,
+    editing_mode: EditingMode,
+) -> Result<(), AppError> {
+    let depth = Arc::new(AtomicUsize::new(0));
+    // Without color, a suggestion could not be told apart from typed text.
+    let dumb_terminal = env::var_os("TERM").is_some_and(|term| term == "dumb");
+    let config = ReplConfig {
+        editing_mode,
+        theme: reporter.theme(),
+        hints: reporter.color && !dumb_terminal,
+    };
+    let mut line_editor = create_repl(config, depth.clone());
+    let mut prompt = ReplPrompt { line: 0, depth };
+
+    // While a line is edited, the terminal is in raw mode and Ctrl-C arrives as
+    // `Signal::CtrlC`. While it runs, Ctrl-C raises SIGINT, which sets this flag instead.
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupt.clone();
+    if let Err(error) = ctrlc::set_handler(move || handler_flag.store(true, Ordering::Relaxed)) {
+        eprintln!("Ctrl-C will not interrupt running code: {error}");
+    }
+
+    let mut interpreter = debug.interpreter(prelude).with_interrupt(interrupt.clone());
+
+    loop {
+        // Alt-Enter adds lines without validating, so a depth left by the last input must
+        // not show up in this one.
+        prompt.depth.store(0, Ordering::Relaxed);
+        let sig = line_editor.read_line(&prompt);
+        match sig {
+            Ok(Signal::Success(buffer)) => {
+                // A blank line does not count as a line, so the prompt stays the same.
+                let Some(input) = repl::submitted_input(&buffer) else {
+                    continue;
+                };
+                match repl::run_command(&input, &mut interpreter) {
+                    Some(ReplAction::Quit) => {
+                        println!("\nGood Bye!");
+                        break;
+                    }
+                    Some(ReplAction::Continue) => {}
+                    None => {
+                        // A Ctrl-C that arrived after the last loop stopped must not stop
+                        // this run.
+                        interrupt.store(false, Ordering::Relaxed);
+                        // Named like the prompt, for errors raised in functions defined
+                        // here while a later input runs.
+                        interpreter.enter_source(format!("lox:{}", prompt.line), input.clone());
+                        // `exit()` leaves the REPL, other errors have been reported.
+                        match run(input, &mut interpreter, true, debug, reporter) {
+                            Err(error) if error.is_exit() => return Err(error),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Ok(Signal::CtrlC) => continue,
+            Ok(Signal::CtrlD) => {
+                println!("\nGood Bye!");
+                break;
+            }
+            _ => todo!(),
+        }
+        prompt.line += 1;
+    }
+
+    Ok(())
+}
+
+/// Runs `source`. With `echo`, the non-nil value of every statement is printed, as the
+/// REPL does. Errors are reported with their source as they happen: the scan and parse
+/// errors and warnings together in one report before running, a runtime error after it. The
+/// first error, which stopped the run, is also returned.
+fn run(
+    source: String,
+    interpreter: &mut Interpreter,
+    echo: bool,
+    debug: DebugOutput,
+    reporter: Reporter,
+) -> Result<(), AppError> {
+    let scanner = Scanner::new(source.clone());
+    let (tokens, scan_errors) = scanner.scan_with_errors();
+
+    if debug.tokens {
+        print_tokens(&source, &tokens);
+    }
+    if debug.stops_before_running() && !debug.ast {
+        return Ok(());
+    }
+
+    let mut parsed = parser::parse_with_recovery(&tokens);
+    // An unterminated string swallows the rest of the source, so the parse errors after its
+    // start only repeat it.
+    if let Some(start) = scan_errors.iter().find_map(|error| match error {
+        LoxError::ScanError(ScanError::UnterminatedString { position, .. }) => {
+            Some(position.absolute)
+        }
+        _ => None,
+    }) {
+        parsed.errors.retain(|error| {
+            error
+                .labels()
+                .and_then(|mut labels| labels.next())
+                .is_some_and(|label| label.offset() < start)
+        });
+    }
+
+    let errors = [scan_errors, parsed.errors].concat();
+    if let Some(first) = errors.first() {
+        // The statements that could be parsed are resolved as well, so their warnings are
+        // reported together with the errors.
+        let mut report = Report::default();
+        for error in &errors {
+            report.push(error.clone(), &source);
+        }
+        for warning in resolver::resolve(&parsed.statements) {
+            report.push(warning, &source);
+        }
+        reporter.print_report(report, &source);
+        return Err(first.clone().into());
+    }
+    let statements = parsed.statements;
+
+    if debug.ast {
+        for statement in &statements {
+            println!("{}", statement.pretty());
+        }
+    }
+    if debug.stops_before_running() {
+        return Ok(());
+    }
+
+    let mut warnings = Report::default();
+    for warning in resolver::resolve(&statements) {
+        warnings.push(warning, &source);
+    }
+    reporter.print_report(warnings, &source);
+
+    let result = if echo {
+        interpreter.evaluate_line(&statements)
+    } else {
+        interpreter.evaluate(&statements)
+    };
+
+    // The profile covers everything run so far, in the REPL all lines up to this one.
+    if let Some(profile) = interpreter.profile() {
+        eprint!("{}", profile.report());
+    }
+
+    match result {
+        Ok(values) => {
+            if echo {
+                values
+                    .iter()
+                    .filter(|value| !value.is_nil())
+                    .for_each(|value| println!("{value:?}"));
+            }
+            Ok(())
+        }
+        Err(error) => {
+            match interpreter.error_source() {
+                Some((name, defining_source)) => {
+                    reporter.report(&error, Some(name), defining_source)
+                }
+                None => reporter.report(&error, None, &source),
+            }
+            Err(error.into())
+        }
+    }
+}
+
+/// `lox check script...` reports the errors and warnings in the scripts without running
+/// them. Returns the exit code, which is 0 only if no script has errors.
+fn run_check(files: &[OsString], reporter: Reporter) -> i32 {
+    if files.is_empty() {
+        println!("Usage: lox check script...");
+        return 64;
+    }
+
+    let mut exit_code = 0;
+    for file in files {
+        let source = match read_script(file) {
+            Ok(source) => source,
+            Err(error) => {
+                exit_code = reporter.report_app_error(error.into());
+                continue;
+            }
+        };
+
+        let name = file.to_string_lossy();
+        let result = check::check(&source);
+        if exit_code == 0 && !result.is_ok() {
+            exit_code = AppError::from(result.errors[0].clone()).exit_code();
+        }
+
+        let mut report = Report::default();
+        for error in &result.errors {
+            report.push(error.clone(), &source);
+        }
+        for warning in result.warnings {
+            report.push(warning, &source);
+        }
+        reporter.report_in_file(report, &name, &source);
+    }
+
+    exit_code
+}
+
+/// `lox tokenize [script | -]` prints the tokens and scan errors of a script, or of stdin,
+/// as JSON lines. Returns the exit code.
+fn run_tokenize(args: &[OsString], reporter: Reporter) -> i32 {
+    let source = match args {
+        [] => read_stdin().map_err(AppError::from),
+        [file] if file == "-" => read_stdin().map_err(AppError::from),
+        [file] => read_script(file).map_err(AppError::from),
+        _ => {
+            println!("Usage: lox tokenize [script | -]");
+            return 64;
+        }
+    };
+
+    match source {
+        Ok(source) => {
+            print!("{}", tokenize::tokenize(&source));
+            0
+        }
+        Err(error) => reporter.report_app_error(error),
+    }
+}
+
+/// `lox --explain CODE` prints what the error with the code, e.g. `E0012`, means. Returns the
+/// exit code.
+fn run_explain(args: &[OsString]) -> i32 {
+    let [code] = args else {
+        println!("Usage: lox --explain CODE");
+        return 64;
+    };
+
+    let code = code.to_string_lossy().to_uppercase();
+    match error::explain(&code) {
+        Some(description) => {
+            println!("{code}: {description}");
+            0
+        }
+        None => {
+            eprintln!("unknown error code {code}");
+            1
+        }
+    }
+}
+
+/// Prints one token per line as `index  type  start..end  lexeme`, with the columns
+/// aligned. Line breaks in lexemes are escaped so every token stays on one line.
+fn print_tokens(source: &str, tokens: &[Token]) {
+    for (index, token) in tokens.iter().enumerate() {
+        let debug = format!("{:?}", token.token_type);
+        let name = debug.split('(').next().unwrap_or_default();
+        let (start, end) = (token.position.absolute, token.position.end_position());
+        let lexeme = source.get(start..end).unwrap_or_default();
+
+        println!(
+            "{index:>4}  {name:<12}  {:<10}  {}",
+            format!("{start}..{end}"),
+            lexeme.replace('\n', "\\n").replace('\r', "\\r")
+        );
+    }
+}
+
+fn create_repl(config: ReplConfig, depth: Arc<AtomicUsize>) -> Reedline {
+    // With bracketed paste, a multi-line paste becomes one input instead of one per line.
+    let mut line_editor = Reedline::create()
+        .use_bracketed_paste(true)
+        .with_validator(Box::new(ReplValidator::new(depth)));
+    if config.hints {
+        line_editor = line_editor.with_hinter(Box::new(config.theme.hinter()));
+    }
+    let line_editor = line_editor.with_highlighter(Box::new(ReplHighlighter::new(config.theme)));
+
+    match config.editing_mode {
+        EditingMode::Emacs => line_editor.with_edit_mode(Box::new(Emacs::new(emacs_keybindings()))),
+        EditingMode::Vi => line_editor.with_edit_mode(Box::new(Vi::new(
+            vi_insert_keybindings(),
+            default_vi_normal_keybindings(),
+        ))),
+    }
+}
+
+fn emacs_keybindings() -> Keybindings {
+    let mut keybindings = default_emacs_keybindings();
+    add_newline_binding(&mut keybindings);
+    keybindings
+}
+
+/// The keybindings of vi's insert mode. Normal mode has no way to type text, so it keeps
+/// the defaults.
+fn vi_insert_keybindings() -> Keybindings {
+    let mut keybindings = default_vi_insert_keybindings();
+    add_newline_binding(&mut keybindings);
+    keybindings
+}
+
+/// Alt-Enter inserts a newline without submitting the buffer.
+fn add_newline_binding(keybindings: &mut Keybindings) {
+    keybindings.add_binding(
+        KeyModifiers::ALT,
+        KeyCode::Enter,
+        ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+    );
+}
+
+/// The end of the prompt. In vi mode it shows whether the editor is in normal or insert
+/// mode.
+fn prompt_indicator(edit_mode: PromptEditMode) -> &'static str {
+    match edit_mode {
+        PromptEditMode::Vi(PromptViMode::Normal) => " [N]> ",
+        PromptEditMode::Vi(PromptViMode::Insert) => " [I]> ",
+        _ => "> ",
+    }
+}
+
+struct ReplPrompt {
+    line: usize,
+    /// The delimiter depth of the buffer, kept up to date by the `ReplValidator`.
+    depth: Arc<AtomicUsize>,
+}
+
+impl Prompt for ReplPrompt {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("lox:{}", self.line))
+    }
+
+    fn render_prompt_right(&self) -> Cow<'_, str> {
+        Cow::Owned("".to_string())
+    }
+
+    fn render_prompt_indicator(&self, edit_mode: PromptEditMode) -> Cow<'_, str> {
+        Cow::Borrowed(prompt_indicator(edit_mode))
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<'_, str> {
+        let depth = self.depth.load(Ordering::Relaxed);
+        Cow::Owned(repl::continuation_prompt(self.line, depth))
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<'_, str> {
+        let prefix = match history_search.status {
+            PromptHistorySearchStatus::Passing => "",
+            PromptHistorySearchStatus::Failing => "failing ",
+        };
+        // NOTE: magic strings, given there is logic on how these compose I am not sure if it
+        // is worth extracting in to static constant
+        Cow::Owned(format!(
+            "({}reverse-search: {}) ",
+            prefix, history_search.term
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_parse_error(reporter: Reporter) -> (String, String) {
+        let source = "print (1;";
+        let error = parser::parse(&Scanner::new(source.to_string()).scan())
+            .err()
+            .unwrap();
+        (
+            error.to_string(),
+            reporter.render(error, source.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_missing_script_suggests_a_similar_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("hello.lox"), "print 1;").unwrap();
+        fs::write(dir.path().join("other.lox"), "print 2;").unwrap();
+
+        let error = read_script(&dir.path().join("helo.lox").into_os_string()).unwrap_err();
+        let expected = format!("did you mean `{}`?", dir.path().join("hello.lox").display());
+        assert!(
+            matches!(&error, ScriptError::NotFound { help: Some(help), .. } if *help == expected),
+            "{error:?}"
+        );
+        assert_eq!(AppError::from(error).exit_code(), 66);
+
+        let error = read_script(&dir.path().join("missing.lox").into_os_string()).unwrap_err();
+        assert!(matches!(error, ScriptError::NotFound { help: None, .. }));
+    }
+
+    #[test]
+    fn test_script_that_is_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let error = read_script(&dir.path().as_os_str().to_owned()).unwrap_err();
+        assert!(
+            matches!(error, ScriptError::IsADirectory { .. }),
+            "{error:?}"
+        );
+        assert_eq!(AppError::from(error).exit_code(), 66);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_script_that_may_not_be_read() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.lox");
+        fs::write(&path, "print 1;").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+        // Permissions don't apply to root.
+        if fs::read(&path).is_ok() {
+            return;
+        }
+
+        let error = read_script(&path.into_os_string()).unwrap_err();
+        assert!(
+            matches!(error, ScriptError::PermissionDenied { .. }),
+            "{error:?}"
+        );
+        assert_eq!(AppError::from(error).exit_code(), 66);
+    }
+
+    #[test]
+    fn test_script_errors_render_path_and_help() {
+        let error = ScriptError::IsADirectory {
+            path: "scripts".to_string(),
+            error: io::Error::from(io::ErrorKind::IsADirectory),
+        };
+        let rendered = Reporter { color: false }.render(error, String::new());
+
+        assert!(
+            rendered.contains("could not read scripts: is a directory"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("help: `lox` runs a single script"),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("main.lox", "main.lox"), 0);
+        assert_eq!(edit_distance("mian.lox", "main.lox"), 2);
+        assert_eq!(edit_distance("man.lox", "main.lox"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("äb", "ab"), 1);
+    }
+
+    #[test]
+    fn test_alt_enter_inserts_a_newline_in_every_mode() {
+        let newline = Some(ReedlineEvent::Edit(vec![EditCommand::InsertNewline]));
+        for keybindings in [emacs_keybindings(), vi_insert_keybindings()] {
+            assert_eq!(
+                keybindings.find_binding(KeyModifiers::ALT, KeyCode::Enter),
+                newline
+            );
+        }
+    }
+
+    #[test]
+    fn test_editing_mode_setting() {
+        assert_eq!(EditingMode::from_setting(false, None), EditingMode::Emacs);
+        assert_eq!(EditingMode::from_setting(true, None), EditingMode::Vi);
+        assert_eq!(
+            EditingMode::from_setting(false, Some("vi")),
+            EditingMode::Vi
+        );
+        assert_eq!(
+            EditingMode::from_setting(false, Some("VI")),
+            EditingMode::Vi
+        );
+        assert_eq!(
+            EditingMode::from_setting(false, Some("emacs")),
+            EditingMode::Emacs
+        );
+        assert_eq!(
+            EditingMode::from_setting(true, Some("emacs")),
+            EditingMode::Vi
+        );
+    }
+
+    #[test]
+    fn test_prompt_indicator_shows_the_vi_mode() {
+        assert_eq!(prompt_indicator(PromptEditMode::Emacs), "> ");
+        assert_eq!(
+            prompt_indicator(PromptEditMode::Vi(PromptViMode::Normal)),
+            " [N]> "
+        );
+        assert_eq!(
+            prompt_indicator(PromptEditMode::Vi(PromptViMode::Insert)),
+            " [I]> "
+        );
+    }
+
+    #[test]
+    fn test_colored_reports() {
+        let (message, rendered) = render_parse_error(Reporter { color: true });
+        assert!(rendered.contains(&message), "{rendered}");
+        assert!(rendered.contains('\x1b'), "{rendered}");
+    }
+
+    #[test]
+    fn test_plain_reports() {
+        let (message, rendered) = render_parse_error(Reporter { color: false });
+        assert!(rendered.contains(&message), "{rendered}");
+        assert!(!rendered.contains('\x1b'), "{rendered}");
+        assert!(rendered.is_ascii(), "{rendered}");
+    }
+}
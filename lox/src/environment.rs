@@ -1,16 +1,38 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
 use std::rc::Rc;
 
 use crate::value::Value;
 
+thread_local! {
+    static LIVE_ENVIRONMENTS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// How many parents the `Debug` output of an [`Environment`] shows before it abbreviates the
+/// rest of the chain as `Some(...)`.
+const DEBUG_PARENTS: usize = 3;
+
 pub struct Environment {
     parent: Option<Rc<RefCell<Environment>>>,
     variables: HashMap<String, Option<Value>>,
 }
 
+/// A variable of a scope chain, see [`Environment::entries`].
+#[derive(Debug, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    /// `None` if the variable is declared but not initialized.
+    pub value: Option<Value>,
+    /// How many scopes above the one asked the variable is defined, 0 for that scope.
+    pub depth: usize,
+    /// Whether a scope closer to the one asked defines a variable with the same name.
+    pub shadowed: bool,
+}
+
 impl Environment {
     pub(crate) fn empty() -> Environment {
+        LIVE_ENVIRONMENTS.with(|count| count.set(count.get() + 1));
         Environment {
             parent: None,
             variables: HashMap::new(),
@@ -18,12 +40,90 @@ impl Environment {
     }
 
     pub(crate) fn wrap(parent: Rc<RefCell<Environment>>) -> Environment {
+        LIVE_ENVIRONMENTS.with(|count| count.set(count.get() + 1));
         Environment {
             parent: Some(parent),
             variables: HashMap::new(),
         }
     }
 
+    /// The number of environments currently alive on this thread.
+    pub fn live_count() -> usize {
+        LIVE_ENVIRONMENTS.with(|count| count.get())
+    }
+
+    /// The number of variables defined in this scope, not counting parent scopes.
+    pub fn len(&self) -> usize {
+        self.variables.len()
+    }
+
+    /// Whether this scope defines no variables. Parent scopes may still define some.
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// The number of variables defined in this scope and all its parents. A shadowed
+    /// variable counts once for every scope that defines it.
+    pub fn total_len(&self) -> usize {
+        let parent_len = self
+            .parent
+            .as_ref()
+            .map_or(0, |parent| parent.borrow().total_len());
+        self.len() + parent_len
+    }
+
+    /// The names of the variables defined in this scope, in no particular order.
+    pub fn variable_names(&self) -> Vec<&str> {
+        self.variables.keys().map(String::as_str).collect()
+    }
+
+    /// The variables of this scope and all its parents, this scope's first and each scope's
+    /// sorted by name. A parent that is being modified right now, which can only happen
+    /// while a statement runs, is left out together with its parents instead of panicking.
+    pub fn entries(&self) -> Vec<Variable> {
+        let mut entries = Vec::new();
+        self.collect_entries(0, &mut HashSet::new(), &mut entries);
+        entries
+    }
+
+    fn collect_entries(
+        &self,
+        depth: usize,
+        seen: &mut HashSet<String>,
+        entries: &mut Vec<Variable>,
+    ) {
+        for (name, value) in self.sorted_variables() {
+            entries.push(Variable {
+                name: name.clone(),
+                value: value.clone(),
+                depth,
+                shadowed: !seen.insert(name.clone()),
+            });
+        }
+
+        if let Some(Ok(parent)) = self.parent.as_ref().map(|parent| parent.try_borrow()) {
+            parent.collect_entries(depth + 1, seen, entries);
+        }
+    }
+
+    /// The variables of this scope sorted by name, for formatting.
+    fn sorted_variables(&self) -> Vec<(&String, &Option<Value>)> {
+        let mut variables = self.variables.iter().collect::<Vec<_>>();
+        variables.sort_by_key(|(name, _)| name.as_str());
+        variables
+    }
+
+    /// The initialized variables defined in this scope, not counting parent scopes.
+    pub(crate) fn initialized_variables(&self) -> HashMap<String, Value> {
+        self.variables
+            .iter()
+            .filter_map(|(key, value)| value.clone().map(|value| (key.clone(), value)))
+            .collect()
+    }
+
+    /// Defines `key` in this scope. `None` declares the variable without initializing it.
+    /// Values are stored as given, so an array or map registered here shares its data with
+    /// every other clone of it (see [`Value`]).
     pub fn register(&mut self, key: String, value: Option<Value>) {
         self.variables.insert(key, value);
     }
@@ -50,4 +150,338 @@ impl Environment {
             }
         }
     }
+
+    /// Looks `key` up in the scope `depth` levels above this one, without looking at any
+    /// other scope. Returns `None` if there are fewer than `depth` parents.
+    pub fn get_at(&self, depth: usize, key: &str) -> Option<Option<Value>> {
+        match depth {
+            0 => self.variables.get(key).cloned(),
+            _ => self.parent.as_ref()?.borrow().get_at(depth - 1, key),
+        }
+    }
+
+    /// Assigns `key` in the scope `depth` levels above this one. Returns `false` if `key`
+    /// is not defined in that scope or there are fewer than `depth` parents.
+    pub fn assign_at(&mut self, depth: usize, key: &str, value: Value) -> bool {
+        match depth {
+            0 => match self.variables.get_mut(key) {
+                Some(variable) => {
+                    *variable = Some(value);
+                    true
+                }
+                None => false,
+            },
+            _ => match &self.parent {
+                Some(p) => p.borrow_mut().assign_at(depth - 1, key, value),
+                None => false,
+            },
+        }
+    }
+}
+
+/// Shows the variables of each scope sorted by name, with their values as `print` shows
+/// them, e.g. `Environment { variables: {x: Some(1), y: None}, parent: None }`. `None` is a
+/// declared but uninitialized variable. Parents more than [`DEBUG_PARENTS`] levels up, and
+/// parents that are being modified right now, are shown as `Some(...)`.
+impl Debug for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        DebugScope {
+            environment: self,
+            parents: DEBUG_PARENTS,
+        }
+        .fmt(f)
+    }
+}
+
+struct DebugScope<'a> {
+    environment: &'a Environment,
+    /// How many more parents to show.
+    parents: usize,
+}
+
+impl Debug for DebugScope<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let variables = self.environment.sorted_variables();
+        let values = variables.iter().map(|(name, value)| {
+            let value = value.as_ref().map(|value| Plain(value.to_string()));
+            (Plain(name.to_string()), value)
+        });
+
+        let mut scope = f.debug_struct("Environment");
+        scope.field("variables", &DebugMap(values.collect()));
+
+        let parent = self.environment.parent.as_ref();
+        match parent.map(|parent| (self.parents, parent.try_borrow())) {
+            None => scope.field("parent", &None::<()>),
+            Some((parents, Ok(parent))) if parents > 0 => scope.field(
+                "parent",
+                &Some(DebugScope {
+                    environment: &parent,
+                    parents: parents - 1,
+                }),
+            ),
+            Some(_) => scope.field("parent", &Some(Plain("...".to_string()))),
+        };
+        scope.finish()
+    }
+}
+
+/// Text that is shown as it is by `Debug`, without quotes.
+struct Plain(String);
+
+impl Debug for Plain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+struct DebugMap(Vec<(Plain, Option<Plain>)>);
+
+impl Debug for DebugMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(key, value)| (key, value)))
+            .finish()
+    }
+}
+
+/// Shows the scope chain on one line, this scope first, e.g. `{x = 1, y} -> {z = 2}`. A
+/// variable without a value is declared but uninitialized. A parent that is being modified
+/// right now ends the chain with `-> ...`.
+impl Display for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let variables = self
+            .sorted_variables()
+            .into_iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{name} = {value}"),
+                None => name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{{{}}}", variables.join(", "))?;
+
+        match self.parent.as_ref().map(|parent| parent.try_borrow()) {
+            None => Ok(()),
+            Some(Ok(parent)) => write!(f, " -> {parent}"),
+            Some(Err(_)) => f.write_str(" -> ..."),
+        }
+    }
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        LIVE_ENVIRONMENTS.with(|count| count.set(count.get() - 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chain of three scopes that each define `x`, the innermost one returned first.
+    fn scopes() -> (
+        Environment,
+        Rc<RefCell<Environment>>,
+        Rc<RefCell<Environment>>,
+    ) {
+        let grandparent = Rc::new(RefCell::new(Environment::empty()));
+        grandparent
+            .borrow_mut()
+            .register("x".to_string(), Some(Value::Number(2.0)));
+        grandparent
+            .borrow_mut()
+            .register("only_global".to_string(), None);
+
+        let parent = Rc::new(RefCell::new(Environment::wrap(grandparent.clone())));
+        parent
+            .borrow_mut()
+            .register("x".to_string(), Some(Value::Number(1.0)));
+
+        let mut local = Environment::wrap(parent.clone());
+        local.register("x".to_string(), Some(Value::Number(0.0)));
+
+        (local, parent, grandparent)
+    }
+
+    #[test]
+    fn test_get_at() {
+        let (local, _, _) = scopes();
+
+        assert_eq!(local.get_at(0, "x"), Some(Some(Value::Number(0.0))));
+        assert_eq!(local.get_at(1, "x"), Some(Some(Value::Number(1.0))));
+        assert_eq!(local.get_at(2, "x"), Some(Some(Value::Number(2.0))));
+        assert_eq!(local.get_at(2, "only_global"), Some(None));
+    }
+
+    #[test]
+    fn test_get_at_does_not_search_other_scopes() {
+        let (local, _, _) = scopes();
+
+        assert_eq!(local.get_at(0, "only_global"), None);
+        assert_eq!(local.get_at(3, "x"), None);
+    }
+
+    #[test]
+    fn test_assign_at() {
+        let (mut local, parent, grandparent) = scopes();
+
+        assert!(local.assign_at(1, "x", Value::Number(10.0)));
+        assert_eq!(
+            parent.borrow().get_at(0, "x"),
+            Some(Some(Value::Number(10.0)))
+        );
+        assert_eq!(local.get_at(0, "x"), Some(Some(Value::Number(0.0))));
+        assert_eq!(
+            grandparent.borrow().get_at(0, "x"),
+            Some(Some(Value::Number(2.0)))
+        );
+
+        assert!(local.assign_at(2, "only_global", Value::Nil));
+        assert!(!local.assign_at(0, "only_global", Value::Nil));
+        assert!(!local.assign_at(3, "x", Value::Nil));
+    }
+
+    #[test]
+    fn test_entries_mark_shadowed_variables() {
+        let (local, _, _) = scopes();
+        let entries = local
+            .entries()
+            .into_iter()
+            .map(|entry| (entry.name, entry.value, entry.depth, entry.shadowed))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("x".to_string(), Some(Value::Number(0.0)), 0, false),
+                ("x".to_string(), Some(Value::Number(1.0)), 1, true),
+                ("only_global".to_string(), None, 2, false),
+                ("x".to_string(), Some(Value::Number(2.0)), 2, true),
+            ]
+        );
+        assert!(Environment::empty().entries().is_empty());
+    }
+
+    #[test]
+    fn test_entries_skip_scopes_being_modified() {
+        let (local, parent, _) = scopes();
+        let _modifying = parent.borrow_mut();
+
+        let entries = local.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].depth, 0);
+    }
+
+    #[test]
+    fn test_empty_environment() {
+        let environment = Environment::empty();
+
+        assert_eq!(environment.len(), 0);
+        assert_eq!(environment.total_len(), 0);
+        assert!(environment.is_empty());
+        assert!(environment.variable_names().is_empty());
+    }
+
+    #[test]
+    fn test_len_counts_this_scope() {
+        let mut environment = Environment::empty();
+        environment.register("a".to_string(), None);
+
+        assert_eq!(environment.len(), 1);
+        assert!(!environment.is_empty());
+        assert_eq!(environment.variable_names(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_wrapped_environment_counts_its_own_scope() {
+        let (local, parent, grandparent) = scopes();
+
+        assert_eq!(local.len(), 1);
+        assert_eq!(parent.borrow().len(), 1);
+        assert_eq!(grandparent.borrow().len(), 2);
+        assert_eq!(local.total_len(), 4);
+        assert_eq!(parent.borrow().total_len(), 3);
+
+        let grandparent = grandparent.borrow();
+        let mut names = grandparent.variable_names();
+        names.sort();
+        assert_eq!(names, vec!["only_global", "x"]);
+
+        let wrapped = Environment::wrap(parent);
+        assert!(wrapped.is_empty());
+        assert_eq!(wrapped.total_len(), 3);
+    }
+
+    #[test]
+    fn test_debug_two_levels() {
+        let parent = Rc::new(RefCell::new(Environment::empty()));
+        parent
+            .borrow_mut()
+            .register("z".to_string(), Some(Value::Str("zed".into())));
+        let mut local = Environment::wrap(parent);
+        local.register("y".to_string(), None);
+        local.register("x".to_string(), Some(Value::Number(1.0)));
+
+        assert_eq!(
+            format!("{local:?}"),
+            "Environment { variables: {x: Some(1), y: None}, \
+            parent: Some(Environment { variables: {z: Some(zed)}, parent: None }) }"
+        );
+        assert_eq!(
+            format!("{local:#?}"),
+            concat!(
+                "Environment {\n",
+                "    variables: {\n",
+                "        x: Some(\n",
+                "            1,\n",
+                "        ),\n",
+                "        y: None,\n",
+                "    },\n",
+                "    parent: Some(\n",
+                "        Environment {\n",
+                "            variables: {\n",
+                "                z: Some(\n",
+                "                    zed,\n",
+                "                ),\n",
+                "            },\n",
+                "            parent: None,\n",
+                "        },\n",
+                "    ),\n",
+                "}",
+            )
+        );
+    }
+
+    #[test]
+    fn test_debug_abbreviates_deep_parents() {
+        let mut scope = Rc::new(RefCell::new(Environment::empty()));
+        for _ in 0..4 {
+            scope = Rc::new(RefCell::new(Environment::wrap(scope)));
+        }
+
+        let debug = format!("{:?}", scope.borrow());
+        assert_eq!(debug.matches("Environment {").count(), 4);
+        assert!(debug.ends_with("parent: Some(...) }) }) }) }"), "{debug}");
+
+        let (local, parent, _) = scopes();
+        let _modifying = parent.borrow_mut();
+        assert_eq!(
+            format!("{local:?}"),
+            "Environment { variables: {x: Some(0)}, parent: Some(...) }"
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let (local, parent, _) = scopes();
+
+        assert_eq!(
+            local.to_string(),
+            "{x = 0} -> {x = 1} -> {only_global, x = 2}"
+        );
+        assert_eq!(Environment::empty().to_string(), "{}");
+
+        let _modifying = parent.borrow_mut();
+        assert_eq!(local.to_string(), "{x = 0} -> ...");
+    }
 }
@@ -1,11 +1,14 @@
-use miette::Diagnostic;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode};
 use thiserror::Error;
 
-use crate::position::Position;
+use crate::position::{LineIndex, Position};
 use crate::token::{Token, TokenType};
 use crate::value::ValueNode;
 
-#[derive(Diagnostic, Error, Debug)]
+#[derive(Diagnostic, Error, Debug, Clone)]
 pub enum LoxError {
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -13,49 +16,406 @@ pub enum LoxError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     ParseError(ParseError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ScanError(ScanError),
 }
 
+/// An error that stops the `lox` binary.
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Script(#[from] ScriptError),
+    #[error(transparent)]
+    Lox(#[from] LoxError),
+}
+
+/// A script file that can't be read. A missing file, a file that may not be read and a
+/// directory are told apart, as each is fixed differently.
 #[derive(Diagnostic, Error, Debug)]
+pub enum ScriptError {
+    #[error("could not read {path}: {error}")]
+    NotFound {
+        path: String,
+        error: io::Error,
+        /// Names a file with a similar name, if there is one.
+        #[help]
+        help: Option<String>,
+    },
+    #[error("could not read {path}: {error}")]
+    #[diagnostic(help("check the permissions of the file"))]
+    PermissionDenied { path: String, error: io::Error },
+    #[error("could not read {path}: {error}")]
+    #[diagnostic(help("`lox` runs a single script, pass a file instead of a directory"))]
+    IsADirectory { path: String, error: io::Error },
+    #[error("could not read {path}: {error}")]
+    Unreadable { path: String, error: io::Error },
+}
+
+impl AppError {
+    /// The exit code for the error, following sysexits.h.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Io(_) | AppError::Script(ScriptError::Unreadable { .. }) => 74,
+            AppError::Script(_) => 66,
+            AppError::Lox(LoxError::ScanError(_) | LoxError::ParseError(_)) => 65,
+            AppError::Lox(LoxError::RuntimeError(RuntimeError::UserPanic { .. })) => 1,
+            AppError::Lox(LoxError::RuntimeError(RuntimeError::Exit { code, .. })) => *code,
+            AppError::Lox(LoxError::RuntimeError(_)) => 70,
+        }
+    }
+
+    /// Whether the program stopped itself with `exit()` or `exitWithError()`.
+    pub fn is_exit(&self) -> bool {
+        matches!(
+            self,
+            AppError::Lox(LoxError::RuntimeError(RuntimeError::Exit { .. }))
+        )
+    }
+}
+
+/// The stable code of a kind of error, shown when the error is reported and explained by
+/// `lox --explain CODE`. Codes are never reused or renumbered, so tools can match on them.
+#[derive(Debug, PartialEq)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    /// What the error means, in one line.
+    pub description: &'static str,
+}
+
+/// The codes of all errors: scan errors from E0001, parse errors from E0010 and runtime errors
+/// from E0100.
+pub fn error_codes() -> &'static [ErrorCode] {
+    ERROR_CODES
+}
+
+/// The description of `code`, or `None` if no error has that code.
+pub fn explain(code: &str) -> Option<&'static str> {
+    ERROR_CODES
+        .iter()
+        .find(|error_code| error_code.code.eq_ignore_ascii_case(code))
+        .map(|error_code| error_code.description)
+}
+
+static ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: "E0001",
+        description: "a string is missing its closing quote",
+    },
+    ErrorCode {
+        code: "E0002",
+        description: "a character that is not part of Lox",
+    },
+    ErrorCode {
+        code: "E0003",
+        description: "a number literal with digits other than 0 to 9",
+    },
+    ErrorCode {
+        code: "E0010",
+        description: "a token that can't appear where it was found",
+    },
+    ErrorCode {
+        code: "E0011",
+        description: "a token other than the one the grammar requires",
+    },
+    ErrorCode {
+        code: "E0012",
+        description: "a `(`, `[` or `{` that is never closed",
+    },
+    ErrorCode {
+        code: "E0013",
+        description: "the source ends in the middle of a statement",
+    },
+    ErrorCode {
+        code: "E0014",
+        description: "a call with more than 255 arguments",
+    },
+    ErrorCode {
+        code: "E0015",
+        description: "an assignment to something other than a variable or element",
+    },
+    ErrorCode {
+        code: "E0016",
+        description: "a rest parameter that is not the last parameter",
+    },
+    ErrorCode {
+        code: "E0017",
+        description: "`break` or `continue` outside of a loop",
+    },
+    ErrorCode {
+        code: "E0018",
+        description: "`return` outside of a function",
+    },
+    ErrorCode {
+        code: "E0100",
+        description: "an operation on a value of the wrong type",
+    },
+    ErrorCode {
+        code: "E0101",
+        description: "a call of a value that is not a function",
+    },
+    ErrorCode {
+        code: "E0102",
+        description: "a read of a variable that was declared without a value",
+    },
+    ErrorCode {
+        code: "E0103",
+        description: "a name that is not defined",
+    },
+    ErrorCode {
+        code: "E0104",
+        description: "a native that is disabled, like file access without `--allow-io`",
+    },
+    ErrorCode {
+        code: "E0105",
+        description: "a native function called with arguments it can't handle",
+    },
+    ErrorCode {
+        code: "E0106",
+        description: "a call with the wrong number of arguments",
+    },
+    ErrorCode {
+        code: "E0107",
+        description: "an operand that has the right type but an invalid value",
+    },
+    ErrorCode {
+        code: "E0108",
+        description: "a member that a namespace or value does not have",
+    },
+    ErrorCode {
+        code: "E0109",
+        description: "a failed `assert` statement or assertion native",
+    },
+    ErrorCode {
+        code: "E0110",
+        description: "an operation on nil",
+    },
+    ErrorCode {
+        code: "E0111",
+        description: "a destructuring pattern with more names than the array has elements",
+    },
+    ErrorCode {
+        code: "E0112",
+        description: "an array index past the end of the array",
+    },
+    ErrorCode {
+        code: "E0113",
+        description: "the program called `panic()`",
+    },
+    ErrorCode {
+        code: "E0114",
+        description: "the program stopped itself with `exit()` or `exitWithError()`",
+    },
+    ErrorCode {
+        code: "E0115",
+        description: "the program was interrupted with Ctrl-C",
+    },
+    ErrorCode {
+        code: "E0116",
+        description: "an import under a name that is already defined",
+    },
+];
+
+#[derive(Diagnostic, Error, Debug, Clone)]
+pub enum ScanError {
+    #[error("Unterminated String")]
+    #[diagnostic(code(E0001))]
+    UnterminatedString {
+        #[label("string starts here")]
+        position: Position,
+        #[label("expected closing `\"` before end of file")]
+        end: Position,
+        #[help]
+        help: Option<&'static str>,
+    },
+    #[error("Unrecognized Character '{character:}' (U+{:04X})", *.character as u32)]
+    #[diagnostic(code(E0002))]
+    UnrecognizedCharacter {
+        character: char,
+        #[label("unrecognized character")]
+        position: Position,
+        #[help]
+        help: Option<&'static str>,
+    },
+    #[error("Invalid Number '{literal:}'")]
+    #[diagnostic(
+        code(E0003),
+        help("numbers can only be written with the digits 0 to 9")
+    )]
+    InvalidNumber {
+        literal: String,
+        #[label("not a number")]
+        position: Position,
+    },
+}
+
+impl ScanError {
+    /// `end` is the end of the input. `escaped_quote` tells whether the source before the
+    /// opening quote contains `\"`: that quote likely ended an earlier string early, so the
+    /// string's intended closing quote opened this one.
+    pub(crate) fn unterminated_string(
+        position: Position,
+        end: Position,
+        escaped_quote: bool,
+    ) -> LoxError {
+        let help = escaped_quote.then_some(
+            "Lox strings have no escape sequences, so `\\\"` ends a string instead of putting a quote into it",
+        );
+        LoxError::ScanError(ScanError::UnterminatedString {
+            position,
+            end,
+            help,
+        })
+    }
+
+    /// `position` covers just `character`, whatever its length in bytes. Characters that
+    /// pasted text often brings in place of Lox's get help naming the intended one.
+    pub(crate) fn unrecognized_character(character: char, position: Position) -> LoxError {
+        let help = match character {
+            '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{2018}' | '\u{2019}' => {
+                Some("this is a typographic quote, strings are written with `\"`")
+            }
+            '\u{2013}' | '\u{2014}' | '\u{2212}' => {
+                Some("this is a dash, subtraction and negation are written with `-`")
+            }
+            '\'' => Some("strings are written with `\"`"),
+            _ => None,
+        };
+        LoxError::ScanError(ScanError::UnrecognizedCharacter {
+            character,
+            position,
+            help,
+        })
+    }
+
+    pub(crate) fn invalid_number(literal: String, position: Position) -> LoxError {
+        LoxError::ScanError(ScanError::InvalidNumber { literal, position })
+    }
+}
+
+#[derive(Diagnostic, Error, Debug, Clone)]
 pub enum ParseError {
     #[error("Illegal Token")]
+    #[diagnostic(code(E0010))]
     IllegalToken {
         found: String,
         #[label("found `{found:}`")]
         position: Position,
+        #[help]
+        help: Option<ParseHelp>,
     },
     #[error("Unexpected Token")]
+    #[diagnostic(code(E0011))]
     UnexpectedToken {
         found: String,
         expected: String,
         #[label("found `{found:}` expected `{expected:}`")]
         position: Position,
+        #[help]
+        help: Option<ParseHelp>,
     },
     #[error("unclosed delimiter")]
+    #[diagnostic(code(E0012))]
     UnclosedDelimiter {
+        /// The delimiter that would close it, e.g. `)`.
+        closing: String,
         #[label("unclosed delimiter")]
         start_position: Position,
         #[label = "expected closing delimiter"]
         end_position: Position,
+        #[help]
+        help: Option<ParseHelp>,
     },
     #[error("unexpected end of token stream")]
-    UnexpectedEndOfTokenStream,
+    #[diagnostic(code(E0013))]
+    UnexpectedEndOfTokenStream {
+        /// What the parser was looking for, e.g. `an expression`.
+        expected: String,
+        #[label("expected {expected:}")]
+        position: Position,
+        #[help]
+        help: Option<ParseHelp>,
+    },
+    #[error("too many arguments")]
+    #[diagnostic(code(E0014))]
+    TooManyArguments {
+        count: usize,
+        #[label("can't have more than 255 arguments, found argument number {count:}")]
+        position: Position,
+        #[help]
+        help: Option<ParseHelp>,
+    },
     #[error("invalid assignment target")]
+    #[diagnostic(code(E0015))]
     InvalidAssignmentTarget {
         #[label("invalid assignment target")]
         position: Position,
+        #[help]
+        help: Option<ParseHelp>,
+    },
+    #[error("misplaced rest parameter")]
+    #[diagnostic(code(E0016))]
+    MisplacedRestParameter {
+        #[label("the rest parameter must be the last parameter")]
+        position: Position,
+        #[help]
+        help: Option<ParseHelp>,
+    },
+    #[error("`{keyword:}` outside of a loop")]
+    #[diagnostic(code(E0017))]
+    OutsideLoop {
+        keyword: String,
+        #[label("`{keyword:}` can only be used inside a loop")]
+        position: Position,
+        #[help]
+        help: Option<ParseHelp>,
+    },
+    #[error("`return` outside of a function")]
+    #[diagnostic(code(E0018))]
+    OutsideFunction {
+        #[label("`return` can only be used inside a function")]
+        position: Position,
+        #[help]
+        help: Option<ParseHelp>,
     },
 }
 
 impl ParseError {
+    /// Notes that the error happened while parsing `context`, e.g. `"while loop"`. Errors
+    /// keep the innermost context, which names the construct closest to the failure.
+    pub fn in_context(mut self, context: &str) -> LoxError {
+        let help = match &mut self {
+            ParseError::IllegalToken { help, .. }
+            | ParseError::UnexpectedToken { help, .. }
+            | ParseError::UnclosedDelimiter { help, .. }
+            | ParseError::UnexpectedEndOfTokenStream { help, .. }
+            | ParseError::TooManyArguments { help, .. }
+            | ParseError::InvalidAssignmentTarget { help, .. }
+            | ParseError::MisplacedRestParameter { help, .. }
+            | ParseError::OutsideLoop { help, .. }
+            | ParseError::OutsideFunction { help, .. } => {
+                help.get_or_insert_with(ParseHelp::default)
+            }
+        };
+        if help.context.is_none() {
+            help.context = Some(context.to_string());
+        }
+        LoxError::ParseError(self)
+    }
+
     pub fn illegal_token(found: Token) -> LoxError {
         LoxError::ParseError(ParseError::IllegalToken {
             found: found.token_type.to_string(),
             position: found.position,
+            help: None,
         })
     }
 
     pub fn unexpected_token(found: Token, expected: String) -> LoxError {
         LoxError::ParseError(ParseError::UnexpectedToken {
+            help: ParseHelp::for_unexpected_token(&found.token_type, &expected),
             found: found.token_type.to_string(),
             expected,
             position: found.position,
@@ -68,63 +428,267 @@ impl ParseError {
         position: Position,
     ) -> LoxError {
         LoxError::ParseError(ParseError::UnexpectedToken {
+            help: ParseHelp::for_unexpected_token(&found, &expected.to_string()),
             found: found.to_string(),
             expected: expected.to_string(),
             position,
         })
     }
 
-    pub fn unexpected_end_of_stream() -> LoxError {
-        LoxError::ParseError(ParseError::UnexpectedEndOfTokenStream)
+    /// The input ended at `position` while the parser was looking for `expected`.
+    pub fn unexpected_end_of_stream(expected: &str, position: Position) -> LoxError {
+        LoxError::ParseError(ParseError::UnexpectedEndOfTokenStream {
+            expected: expected.to_string(),
+            position,
+            help: None,
+        })
     }
 
-    pub fn unclosed_delimiter(start_position: &Position, end_position: &Position) -> LoxError {
+    pub fn unclosed_delimiter(
+        closing: &TokenType,
+        start_position: &Position,
+        end_position: &Position,
+    ) -> LoxError {
         LoxError::ParseError(ParseError::UnclosedDelimiter {
+            help: ParseHelp::hint(format!("add a matching `{closing}` before this point")),
+            closing: closing.to_string(),
             start_position: start_position.clone(),
             end_position: end_position.clone(),
         })
     }
 
-    pub(crate) fn invalid_assignment_target(position: &Position) -> LoxError {
+    pub(crate) fn too_many_arguments(count: usize, position: &Position) -> LoxError {
+        LoxError::ParseError(ParseError::TooManyArguments {
+            count,
+            position: position.clone(),
+            help: None,
+        })
+    }
+
+    /// An assignment to something that can't be assigned. `in_condition` tells whether it
+    /// is part of a condition, where it is probably a comparison written with `=`.
+    pub(crate) fn invalid_assignment_target(position: &Position, in_condition: bool) -> LoxError {
+        let hint = match in_condition {
+            true => "only variables and elements like `a[i]` can be assigned; did you mean `==`?",
+            false => "only variables and elements like `a[i]` can be assigned",
+        };
+
         LoxError::ParseError(ParseError::InvalidAssignmentTarget {
             position: position.clone(),
+            help: ParseHelp::hint(hint.to_string()),
+        })
+    }
+
+    pub(crate) fn misplaced_rest_parameter(position: &Position) -> LoxError {
+        LoxError::ParseError(ParseError::MisplacedRestParameter {
+            position: position.clone(),
+            help: None,
+        })
+    }
+
+    pub(crate) fn outside_loop(keyword: &Token) -> LoxError {
+        LoxError::ParseError(ParseError::OutsideLoop {
+            keyword: keyword.token_type.to_string(),
+            position: keyword.position.clone(),
+            help: None,
+        })
+    }
+
+    pub(crate) fn outside_function(keyword: &Token) -> LoxError {
+        LoxError::ParseError(ParseError::OutsideFunction {
+            position: keyword.position.clone(),
+            help: None,
         })
     }
 }
 
-#[derive(Diagnostic, Error, Debug)]
+/// The help of a parse error: a hint how to fix it and the construct that was being parsed
+/// when it happened. Either can be missing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseHelp {
+    pub hint: Option<String>,
+    /// What was being parsed, e.g. `"while loop"`.
+    pub context: Option<String>,
+}
+
+impl ParseHelp {
+    fn hint(hint: String) -> Option<ParseHelp> {
+        Some(ParseHelp {
+            hint: Some(hint),
+            context: None,
+        })
+    }
+
+    /// A hint for the common mistakes behind finding `found` where `expected` should be: a
+    /// missing semicolon and a keyword used as a name.
+    fn for_unexpected_token(found: &TokenType, expected: &str) -> Option<ParseHelp> {
+        match (found.keyword(), expected) {
+            (Some(keyword), "Identifier") => ParseHelp::hint(format!(
+                "`{keyword}` is a keyword and can't be used as a name"
+            )),
+            (_, ";") => ParseHelp::hint(
+                "did you forget a `;` at the end of the previous statement?".to_string(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ParseHelp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.hint, &self.context) {
+            (Some(hint), Some(context)) => write!(f, "{hint} (while parsing {context})"),
+            (Some(hint), None) => write!(f, "{hint}"),
+            (None, Some(context)) => write!(f, "while parsing {context}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[derive(Diagnostic, Error, Debug, Clone)]
 #[error("RuntimeError")]
 pub enum RuntimeError {
     #[error("TypeError")]
+    #[diagnostic(code(E0100))]
     TypeError {
         found: String,
         expected: String,
         #[label("no implicit conversion of type {found:} into {expected:}")]
         position: Position,
     },
+    #[error("NotCallable")]
+    #[diagnostic(code(E0101))]
+    NotCallable {
+        type_name: String,
+        #[label("{type_name:} is not callable")]
+        position: Position,
+    },
     #[error("UninitializedVariable")]
+    #[diagnostic(code(E0102))]
     UninitializedVariable {
         variable: String,
         #[label("Variable {variable:} has not been initialized")]
         position: Position,
     },
     #[error("UnknownIdentifier")]
+    #[diagnostic(code(E0103))]
     UnknownIdentifier {
         variable: String,
         #[label("Unknown variable {variable:}")]
         position: Position,
     },
+    #[error("Disabled")]
+    #[diagnostic(code(E0104))]
+    Disabled {
+        reason: String,
+        #[label("{reason:}")]
+        position: Position,
+    },
+    #[error("invalid call to {function:}")]
+    #[diagnostic(code(E0105))]
+    NativeError {
+        function: String,
+        reason: String,
+        #[label("{reason:}")]
+        position: Position,
+        #[help]
+        help: Option<String>,
+    },
+    #[error("ArityMismatch")]
+    #[diagnostic(code(E0106))]
+    ArityMismatch {
+        expected: String,
+        found: usize,
+        #[label("expected {expected:} arguments but got {found:}")]
+        position: Position,
+    },
+    #[error("InvalidArgument")]
+    #[diagnostic(code(E0107))]
+    InvalidArgument {
+        reason: String,
+        #[label("{reason:}")]
+        position: Position,
+    },
+    #[error("UnknownMember")]
+    #[diagnostic(code(E0108))]
+    UnknownMember {
+        namespace: String,
+        member: String,
+        #[label("{namespace:} has no member {member:}")]
+        position: Position,
+    },
+    #[error("{message:}")]
+    #[diagnostic(code(E0109))]
+    AssertionFailed {
+        message: String,
+        #[label("this assertion failed")]
+        position: Position,
+    },
+    #[error("{message:}")]
+    #[diagnostic(code(E0110))]
+    NilOperation {
+        message: String,
+        #[label("this value is nil")]
+        position: Position,
+    },
+    #[error("MissingElement")]
+    #[diagnostic(code(E0111))]
+    MissingElement {
+        index: usize,
+        length: usize,
+        #[label("no element at index {index:}, the array has {length:} elements")]
+        position: Position,
+    },
+    #[error("IndexOutOfBounds")]
+    #[diagnostic(code(E0112))]
+    IndexOutOfBounds {
+        index: usize,
+        length: usize,
+        #[label("index {index:} is out of bounds for {length:} elements")]
+        position: Position,
+    },
+    #[error("Panic: {message:}")]
+    #[diagnostic(code(E0113))]
+    UserPanic {
+        message: String,
+        #[label("panicked here")]
+        position: Position,
+    },
+    /// Not an error: the program asked to stop with `exit(code)` or `exitWithError(message)`.
+    #[error("Exit({code:})")]
+    #[diagnostic(code(E0114))]
+    Exit {
+        code: i32,
+        /// Printed to stderr before exiting.
+        message: Option<String>,
+    },
+    #[error("Interrupted")]
+    #[diagnostic(code(E0115))]
+    Interrupted {
+        #[label("interrupted while running this loop")]
+        position: Position,
+    },
+    #[error("DuplicateNamespace")]
+    #[diagnostic(code(E0116), help("a global named {name:} is already defined"))]
+    DuplicateNamespace { name: String },
 }
 
 impl RuntimeError {
     pub(crate) fn type_error(found: &ValueNode, expected: String) -> LoxError {
         LoxError::RuntimeError(RuntimeError::TypeError {
-            found: format!("{:?}", found.value),
+            found: found.value.type_name().to_string(),
             expected,
             position: found.position.clone(),
         })
     }
 
+    pub(crate) fn not_callable(callee: &ValueNode) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::NotCallable {
+            type_name: callee.value.type_name().to_string(),
+            position: callee.position.clone(),
+        })
+    }
+
     pub(crate) fn uninitialized_variable(variable: String, position: Position) -> LoxError {
         LoxError::RuntimeError(RuntimeError::UninitializedVariable { variable, position })
     }
@@ -132,4 +696,847 @@ impl RuntimeError {
     pub(crate) fn unknown_identifier(variable: String, position: Position) -> LoxError {
         LoxError::RuntimeError(RuntimeError::UnknownIdentifier { variable, position })
     }
+
+    pub(crate) fn disabled(reason: String, position: Position) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::Disabled { reason, position })
+    }
+
+    pub(crate) fn native_error(
+        function: String,
+        reason: String,
+        position: Position,
+        help: Option<String>,
+    ) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::NativeError {
+            function,
+            reason,
+            position,
+            help,
+        })
+    }
+
+    pub(crate) fn arity_mismatch(expected: String, found: usize, position: Position) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::ArityMismatch {
+            expected,
+            found,
+            position,
+        })
+    }
+
+    pub(crate) fn invalid_argument(argument: &ValueNode, reason: String) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::InvalidArgument {
+            reason,
+            position: argument.position.clone(),
+        })
+    }
+
+    pub(crate) fn unknown_member(
+        namespace: String,
+        member: String,
+        position: Position,
+    ) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::UnknownMember {
+            namespace,
+            member,
+            position,
+        })
+    }
+
+    pub(crate) fn assertion_failed(message: Option<String>, position: Position) -> LoxError {
+        let message = match message {
+            Some(message) => format!("assertion failed: {message}"),
+            None => "assertion failed".to_string(),
+        };
+
+        LoxError::RuntimeError(RuntimeError::AssertionFailed { message, position })
+    }
+
+    pub(crate) fn nil_operation(message: String, nil: &ValueNode) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::NilOperation {
+            message,
+            position: nil.position.clone(),
+        })
+    }
+
+    pub(crate) fn missing_element(index: usize, length: usize, position: Position) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::MissingElement {
+            index,
+            length,
+            position,
+        })
+    }
+
+    pub(crate) fn index_out_of_bounds(index: usize, length: usize, position: Position) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::IndexOutOfBounds {
+            index,
+            length,
+            position,
+        })
+    }
+
+    pub(crate) fn user_panic(message: String, position: Position) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::UserPanic { message, position })
+    }
+
+    pub(crate) fn exit(code: i32, message: Option<String>) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::Exit { code, message })
+    }
+
+    pub(crate) fn interrupted(position: Position) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::Interrupted { position })
+    }
+
+    pub(crate) fn duplicate_namespace(name: String) -> LoxError {
+        LoxError::RuntimeError(RuntimeError::DuplicateNamespace { name })
+    }
+}
+
+#[derive(Diagnostic, Error, Debug)]
+pub enum Warning {
+    #[error("infinite loop")]
+    #[diagnostic(severity(Warning))]
+    InfiniteLoop {
+        #[label("this loop contains no `break` or `return` and never ends")]
+        position: Position,
+    },
+    #[error("assignment in condition")]
+    #[diagnostic(
+        severity(Warning),
+        help("if the assignment is intended, wrap it in another pair of parentheses")
+    )]
+    AssignmentInCondition {
+        #[label("this assigns; did you mean `==`?")]
+        position: Position,
+    },
+}
+
+/// A diagnostic whose message ends with the line and column of its first label, so output
+/// without a source snippet still says where the problem is, e.g. `Unexpected Token at 7:13`.
+/// Diagnostics without a label are shown as they are.
+#[derive(Debug)]
+pub(crate) struct Located<D> {
+    diagnostic: D,
+    location: Option<(usize, usize)>,
+}
+
+impl<D: Diagnostic> Located<D> {
+    /// Locates `diagnostic` in `source`, the source its positions point into.
+    pub(crate) fn new(diagnostic: D, source: &str) -> Located<D> {
+        let location = diagnostic
+            .labels()
+            .and_then(|mut labels| labels.next())
+            .map(|label| LineIndex::from_source(source).line_col_of(label.offset()));
+        Located {
+            diagnostic,
+            location,
+        }
+    }
+}
+
+impl<D: Display> Display for Located<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{} at {line}:{column}", self.diagnostic),
+            None => write!(f, "{}", self.diagnostic),
+        }
+    }
+}
+
+impl<D: Diagnostic> std::error::Error for Located<D> {}
+
+impl<D: Diagnostic> Diagnostic for Located<D> {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.diagnostic.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.diagnostic.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.diagnostic.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.diagnostic.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.diagnostic.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.diagnostic.labels()
+    }
+}
+
+/// Every error and warning found in one source, rendered as a single diagnostic: the labels
+/// of all of them share one source excerpt, and below it each is listed in source order with
+/// its location, code and help. Its message counts them, e.g. `3 errors, 2 warnings`. A
+/// report of just one diagnostic is shown like that diagnostic.
+#[derive(Debug, Default)]
+pub(crate) struct Report {
+    entries: Vec<Entry>,
+}
+
+/// A diagnostic of a [`Report`], with its message [`Located`] and its labels kept for the
+/// report's excerpt.
+#[derive(Debug)]
+struct Entry {
+    message: String,
+    code: Option<String>,
+    severity: Option<Severity>,
+    help: Option<String>,
+    labels: Vec<LabeledSpan>,
+}
+
+impl Report {
+    /// Adds `diagnostic`, whose positions point into `source`.
+    pub(crate) fn push(&mut self, diagnostic: impl Diagnostic, source: &str) {
+        let labels = diagnostic
+            .labels()
+            .map(|labels| labels.collect::<Vec<_>>())
+            .unwrap_or_default();
+        let entry = Entry {
+            code: diagnostic.code().map(|code| code.to_string()),
+            severity: diagnostic.severity(),
+            help: diagnostic.help().map(|help| help.to_string()),
+            message: Located::new(diagnostic, source).to_string(),
+            labels,
+        };
+
+        let index = self
+            .entries
+            .partition_point(|other| other.offset() <= entry.offset());
+        self.entries.insert(index, entry);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn single(&self) -> Option<&Entry> {
+        match self.entries.as_slice() {
+            [entry] => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.severity.unwrap_or(Severity::Error) == severity)
+            .count()
+    }
+}
+
+impl Entry {
+    /// Where the entry is sorted to, after the entries with labels if it has none.
+    fn offset(&self) -> usize {
+        self.labels
+            .first()
+            .map_or(usize::MAX, |label| label.offset())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(entry) = self.single() {
+            return write!(f, "{entry}");
+        }
+
+        let counts = [
+            (self.count(Severity::Error), "error"),
+            (self.count(Severity::Warning), "warning"),
+        ];
+        let summary = counts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, noun)| match count {
+                1 => format!("1 {noun}"),
+                _ => format!("{count} {noun}s"),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", summary.join(", "))
+    }
+}
+
+impl std::error::Error for Report {}
+
+impl Diagnostic for Report {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.single()?.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.count(Severity::Error) {
+            0 if !self.is_empty() => Some(Severity::Warning),
+            _ => Some(Severity::Error),
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.single()?.help()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let labels = self.entries.iter().flat_map(|entry| entry.labels.clone());
+        Some(Box::new(labels))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        if self.single().is_some() {
+            return None;
+        }
+
+        let entries = self.entries.iter().map(|entry| entry as &dyn Diagnostic);
+        Some(Box::new(entries))
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Entry {}
+
+/// The labels are left out, the [`Report`] shows them in its excerpt.
+impl Diagnostic for Entry {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.code
+            .as_ref()
+            .map(|code| Box::new(code) as Box<dyn Display>)
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn Display>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{GraphicalReportHandler, GraphicalTheme};
+
+    use crate::evaluation::Interpreter;
+    use crate::parser::parse;
+    use crate::scanner::Scanner;
+
+    use super::*;
+
+    fn located(error: LoxError, source: &str) -> String {
+        Located::new(error, source).to_string()
+    }
+
+    fn scan_error(source: &str) -> String {
+        let (_, errors) = Scanner::new(source.to_string()).scan_with_errors();
+        located(errors.into_iter().next().unwrap(), source)
+    }
+
+    fn parse_error(source: &str) -> String {
+        let tokens = Scanner::new(source.to_string()).scan();
+        located(parse(&tokens).err().unwrap(), source)
+    }
+
+    fn runtime_error(source: &str) -> String {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let statements = parse(&tokens).unwrap();
+        located(
+            Interpreter::new().evaluate(&statements).unwrap_err(),
+            source,
+        )
+    }
+
+    #[test]
+    fn test_scan_errors_are_located() {
+        assert_eq!(scan_error("var a;\n  \"abc"), "Unterminated String at 2:3");
+        assert_eq!(
+            scan_error("1;\n2 # 3;"),
+            "Unrecognized Character '#' (U+0023) at 2:3"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_are_located() {
+        for (source, expected) in [
+            ("var a;\nprint (1;", "Unexpected Token at 2:9"),
+            ("var a;\n\n  (1;", "Unexpected Token at 3:5"),
+            ("print 1;\n  1 = 2;", "invalid assignment target at 2:3"),
+            ("fun f(...a, b) {}", "misplaced rest parameter at 1:7"),
+            ("var a;\nbreak;", "`break` outside of a loop at 2:1"),
+            ("var a;\nreturn a;", "`return` outside of a function at 2:1"),
+        ] {
+            assert_eq!(parse_error(source), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn test_runtime_errors_are_located() {
+        for (source, expected) in [
+            ("var a = 1;\nprint -\"a\";", "TypeError at 2:8"),
+            ("1;\n  undefined;", "UnknownIdentifier at 2:3"),
+            ("var a;\nprint a;", "UninitializedVariable at 2:7"),
+            ("var a = 1;\na();", "NotCallable at 2:1"),
+            ("fun f(x) {}\nf();", "ArityMismatch at 2:2"),
+            ("var xs = [1];\nxs[3];", "InvalidArgument at 2:4"),
+            ("1;\nassert false;", "assertion failed at 2:8"),
+            ("1;\n\npanic(\"no\");", "Panic: no at 3:6"),
+            ("\nord(1);", "invalid call to ord at 2:5"),
+        ] {
+            assert_eq!(runtime_error(source), expected, "{source}");
+        }
+    }
+
+    /// The report of the parse error in `source` as plain text.
+    fn render_parse_error(source: &str) -> String {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let error = parse(&tokens).err().unwrap();
+        let report = miette::Report::new(error).with_source_code(source.to_string());
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::none())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+        rendered
+    }
+
+    fn render_scan_error(source: &str) -> String {
+        let (_, errors) = Scanner::new(source.to_string()).scan_with_errors();
+        let error = errors.into_iter().next().unwrap();
+        let report = miette::Report::new(error).with_source_code(source.to_string());
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::none())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+        rendered
+    }
+
+    #[test]
+    fn test_unterminated_string_on_the_last_line() {
+        let rendered = render_scan_error("print 1;\nprint \"abc");
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "E0001\n",
+                "\n",
+                "  x Unterminated String\n",
+                "   ,-[1:1]\n",
+                " 1 | print 1;\n",
+                " 2 | print \"abc\n",
+                "   :       |  |\n",
+                "   :       |  `-- expected closing `\"` before end of file\n",
+                "   :       `-- string starts here\n",
+                "   `----\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string_opened_mid_file() {
+        let rendered = render_scan_error("var a = \"abc;\nprint a;\n");
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "E0001\n",
+                "\n",
+                "  x Unterminated String\n",
+                "   ,-[1:1]\n",
+                " 1 | var a = \"abc;\n",
+                "   :         |\n",
+                "   :         `-- string starts here\n",
+                " 2 | print a;\n",
+                "   :         ^\n",
+                "   :         `-- expected closing `\"` before end of file\n",
+                "   `----\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string_after_an_escaped_quote() {
+        let help = "help: Lox strings have no escape sequences, so `\\\"` ends a string";
+
+        assert!(render_scan_error("print \"a \\\"b\";\nprint 1;").contains(help));
+        assert!(!render_scan_error("print \"a;\nprint 1;").contains(help));
+    }
+
+    #[test]
+    fn test_unrecognized_character_shows_its_code_point() {
+        assert_eq!(
+            render_scan_error("print 1 @ 2;"),
+            concat!(
+                "E0002\n",
+                "\n",
+                "  x Unrecognized Character '@' (U+0040)\n",
+                "   ,----\n",
+                " 1 | print 1 @ 2;\n",
+                "   :         |\n",
+                "   :         `-- unrecognized character\n",
+                "   `----\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_unrecognized_typographic_characters_have_help() {
+        let rendered = render_scan_error("print \u{201c}hi\u{201d};");
+        assert!(rendered.contains("Unrecognized Character '\u{201c}' (U+201C)"));
+        assert!(
+            rendered.contains("help: this is a typographic quote, strings are written with `\"`")
+        );
+
+        let rendered = render_scan_error("print 2 \u{2014} 1;");
+        assert!(rendered
+            .contains("help: this is a dash, subtraction and negation are written with `-`"));
+
+        assert!(!render_scan_error("print 1 \u{a7} 2;").contains("help:"));
+    }
+
+    #[test]
+    fn test_unrecognized_character_spans_all_its_bytes() {
+        let (_, errors) =
+            Scanner::new("var a = \u{1f600}; print a;".to_string()).scan_with_errors();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [LoxError::ScanError(ScanError::UnrecognizedCharacter { character: '\u{1f600}', position, .. })]
+                if *position == Position::new(8, 4)
+        ));
+        assert!(render_scan_error("var a = \u{1f600};").contains("(U+1F600)"));
+    }
+
+    #[test]
+    fn test_parse_errors_render_their_help() {
+        for (source, help) in [
+            ("print (1 + 2", "help: add a matching `)` before this point"),
+            (
+                "fun f() { print 1;",
+                "help: add a matching `}` before this point",
+            ),
+            (
+                "var a = 1; 1 = a;",
+                "help: only variables and elements like `a[i]` can be assigned\n",
+            ),
+            (
+                "var a = 1; if (a + 1 = 2) print a;",
+                "help: only variables and elements like `a[i]` can be assigned; did you mean `==`?",
+            ),
+            (
+                "print 1\nprint 2;",
+                "help: did you forget a `;` at the end of the previous statement?",
+            ),
+            (
+                "var print = 1;",
+                "help: `print` is a keyword and can't be used as a name",
+            ),
+            (
+                "fun f(while) {}",
+                "help: `while` is a keyword and can't be used as a name (while parsing function \
+                declaration)",
+            ),
+        ] {
+            let rendered = render_parse_error(source);
+            assert!(rendered.contains(help), "{source}\n{rendered}");
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_without_a_hint_render_their_context() {
+        let rendered = render_parse_error("while (true) print ;");
+        assert!(
+            rendered.contains("help: while parsing while loop"),
+            "{rendered}"
+        );
+
+        let rendered = render_parse_error("print ;");
+        assert!(!rendered.contains("help:"), "{rendered}");
+    }
+
+    /// One error of every variant.
+    fn examples() -> Vec<LoxError> {
+        let position = Position::new(0, 1);
+        let value = ValueNode::new(crate::value::Value::Nil, &position);
+        let token = Token {
+            token_type: TokenType::Semicolon,
+            position: position.clone(),
+        };
+
+        vec![
+            ScanError::unterminated_string(position.clone(), position.clone(), false),
+            ScanError::unrecognized_character('#', position.clone()),
+            ScanError::invalid_number("1²".to_string(), position.clone()),
+            ParseError::illegal_token(token.clone()),
+            ParseError::unexpected_token(token.clone(), ")".to_string()),
+            ParseError::unclosed_delimiter(&TokenType::RightParent, &position, &position),
+            ParseError::unexpected_end_of_stream("an expression", position.clone()),
+            ParseError::too_many_arguments(256, &position),
+            ParseError::invalid_assignment_target(&position, false),
+            ParseError::misplaced_rest_parameter(&position),
+            ParseError::outside_loop(&token),
+            ParseError::outside_function(&token),
+            RuntimeError::type_error(&value, "Number".to_string()),
+            RuntimeError::not_callable(&value),
+            RuntimeError::uninitialized_variable("a".to_string(), position.clone()),
+            RuntimeError::unknown_identifier("a".to_string(), position.clone()),
+            RuntimeError::disabled("disabled".to_string(), position.clone()),
+            RuntimeError::native_error("f".to_string(), "no".to_string(), position.clone(), None),
+            RuntimeError::arity_mismatch("1".to_string(), 2, position.clone()),
+            RuntimeError::invalid_argument(&value, "no".to_string()),
+            RuntimeError::unknown_member("a".to_string(), "b".to_string(), position.clone()),
+            RuntimeError::assertion_failed(None, position.clone()),
+            RuntimeError::nil_operation("no".to_string(), &value),
+            RuntimeError::missing_element(1, 1, position.clone()),
+            RuntimeError::index_out_of_bounds(1, 1, position.clone()),
+            RuntimeError::user_panic("no".to_string(), position.clone()),
+            RuntimeError::exit(0, None),
+            RuntimeError::interrupted(position.clone()),
+            RuntimeError::duplicate_namespace("a".to_string()),
+        ]
+    }
+
+    /// The name of the variant of `error`. The match has no wildcard, so adding a variant
+    /// fails to compile until it is added here, and then `test_every_error_has_a_code`
+    /// fails until it has an example with a code.
+    fn variant_name(error: &LoxError) -> &'static str {
+        match error {
+            LoxError::ScanError(error) => match error {
+                ScanError::UnterminatedString { .. } => "UnterminatedString",
+                ScanError::UnrecognizedCharacter { .. } => "UnrecognizedCharacter",
+                ScanError::InvalidNumber { .. } => "InvalidNumber",
+            },
+            LoxError::ParseError(error) => match error {
+                ParseError::IllegalToken { .. } => "IllegalToken",
+                ParseError::UnexpectedToken { .. } => "UnexpectedToken",
+                ParseError::UnclosedDelimiter { .. } => "UnclosedDelimiter",
+                ParseError::UnexpectedEndOfTokenStream { .. } => "UnexpectedEndOfTokenStream",
+                ParseError::TooManyArguments { .. } => "TooManyArguments",
+                ParseError::InvalidAssignmentTarget { .. } => "InvalidAssignmentTarget",
+                ParseError::MisplacedRestParameter { .. } => "MisplacedRestParameter",
+                ParseError::OutsideLoop { .. } => "OutsideLoop",
+                ParseError::OutsideFunction { .. } => "OutsideFunction",
+            },
+            LoxError::RuntimeError(error) => match error {
+                RuntimeError::TypeError { .. } => "TypeError",
+                RuntimeError::NotCallable { .. } => "NotCallable",
+                RuntimeError::UninitializedVariable { .. } => "UninitializedVariable",
+                RuntimeError::UnknownIdentifier { .. } => "UnknownIdentifier",
+                RuntimeError::Disabled { .. } => "Disabled",
+                RuntimeError::NativeError { .. } => "NativeError",
+                RuntimeError::ArityMismatch { .. } => "ArityMismatch",
+                RuntimeError::InvalidArgument { .. } => "InvalidArgument",
+                RuntimeError::UnknownMember { .. } => "UnknownMember",
+                RuntimeError::AssertionFailed { .. } => "AssertionFailed",
+                RuntimeError::NilOperation { .. } => "NilOperation",
+                RuntimeError::MissingElement { .. } => "MissingElement",
+                RuntimeError::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+                RuntimeError::UserPanic { .. } => "UserPanic",
+                RuntimeError::Exit { .. } => "Exit",
+                RuntimeError::Interrupted { .. } => "Interrupted",
+                RuntimeError::DuplicateNamespace { .. } => "DuplicateNamespace",
+            },
+        }
+    }
+
+    #[test]
+    fn test_every_error_has_a_code() {
+        let codes = examples()
+            .iter()
+            .map(|error| {
+                let code = error.code().map(|code| code.to_string());
+                (variant_name(error), code.unwrap_or_default())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            codes,
+            vec![
+                ("UnterminatedString", "E0001"),
+                ("UnrecognizedCharacter", "E0002"),
+                ("InvalidNumber", "E0003"),
+                ("IllegalToken", "E0010"),
+                ("UnexpectedToken", "E0011"),
+                ("UnclosedDelimiter", "E0012"),
+                ("UnexpectedEndOfTokenStream", "E0013"),
+                ("TooManyArguments", "E0014"),
+                ("InvalidAssignmentTarget", "E0015"),
+                ("MisplacedRestParameter", "E0016"),
+                ("OutsideLoop", "E0017"),
+                ("OutsideFunction", "E0018"),
+                ("TypeError", "E0100"),
+                ("NotCallable", "E0101"),
+                ("UninitializedVariable", "E0102"),
+                ("UnknownIdentifier", "E0103"),
+                ("Disabled", "E0104"),
+                ("NativeError", "E0105"),
+                ("ArityMismatch", "E0106"),
+                ("InvalidArgument", "E0107"),
+                ("UnknownMember", "E0108"),
+                ("AssertionFailed", "E0109"),
+                ("NilOperation", "E0110"),
+                ("MissingElement", "E0111"),
+                ("IndexOutOfBounds", "E0112"),
+                ("UserPanic", "E0113"),
+                ("Exit", "E0114"),
+                ("Interrupted", "E0115"),
+                ("DuplicateNamespace", "E0116"),
+            ]
+            .into_iter()
+            .map(|(name, code)| (name, code.to_string()))
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_error_codes_list_every_code_once() {
+        let listed = error_codes()
+            .iter()
+            .map(|error_code| error_code.code.to_string())
+            .collect::<Vec<_>>();
+        let used = examples()
+            .iter()
+            .map(|error| error.code().unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(listed, used);
+        assert!(error_codes()
+            .iter()
+            .all(|error_code| !error_code.description.is_empty()));
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explain("E0012"),
+            Some("a `(`, `[` or `{` that is never closed")
+        );
+        assert_eq!(explain("e0012"), explain("E0012"));
+        assert_eq!(explain("E9999"), None);
+    }
+
+    #[test]
+    fn test_codes_are_rendered() {
+        let rendered = render_parse_error("print (1 + 2");
+        assert!(rendered.contains("E0012"), "{rendered}");
+    }
+
+    #[test]
+    fn test_errors_without_labels_are_unchanged() {
+        let error = RuntimeError::duplicate_namespace("a".to_string());
+        assert_eq!(located(error, "print"), "DuplicateNamespace");
+    }
+
+    #[test]
+    fn test_unexpected_end_of_input_points_at_the_end() {
+        for (source, expected) in [
+            ("while (true) {\n  print", "expected an expression"),
+            ("f(1,", "expected an expression"),
+            ("var", "expected a name"),
+            ("var a =", "expected an expression"),
+            ("for (", "expected the initializer of the `for` loop or `;`"),
+            ("for (;", "expected the condition of the `for` loop or `;`"),
+        ] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            let error = parse(&tokens).err().unwrap();
+            assert!(
+                matches!(
+                    error,
+                    LoxError::ParseError(ParseError::UnexpectedEndOfTokenStream { .. })
+                ),
+                "{source}"
+            );
+
+            let label = error.labels().unwrap().next().unwrap();
+            assert_eq!(label.offset(), source.len(), "{source}");
+            assert_eq!(label.label(), Some(expected), "{source}");
+        }
+    }
+
+    /// The report of every parse error and warning in `source` as plain text.
+    fn render_report(source: &str) -> (String, String) {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let parsed = crate::parser::parse_with_recovery(&tokens);
+
+        let mut report = Report::default();
+        for warning in crate::resolver::resolve(&parsed.statements) {
+            report.push(warning, source);
+        }
+        for error in parsed.errors {
+            report.push(error, source);
+        }
+
+        let summary = report.to_string();
+        let report = miette::Report::new(report).with_source_code(source.to_string());
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::none())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+        (summary, rendered)
+    }
+
+    #[test]
+    fn test_report_shows_every_label_in_one_excerpt() {
+        let (summary, rendered) = render_report("var a = ;\nprint 1;\nprint (3;\n");
+        assert_eq!(summary, "2 errors");
+        assert_eq!(
+            rendered,
+            [
+                "  x 2 errors",
+                "   ,-[1:1]",
+                " 1 | var a = ;",
+                "   :         |",
+                "   :         `-- found `;`",
+                " 2 | print 1;",
+                " 3 | print (3;",
+                "   :         |",
+                "   :         `-- found `;` expected `)`",
+                "   `----",
+                "",
+                "Error: E0010",
+                "",
+                "  x Illegal Token at 1:9",
+                "Error: E0011",
+                "",
+                "  x Unexpected Token at 3:9",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_report_counts_errors_and_warnings_in_source_order() {
+        let (summary, rendered) = render_report("print (1;\nloop { print 2; }\nvar a = ;");
+        assert_eq!(summary, "2 errors, 1 warning");
+
+        let unexpected = rendered.find("Unexpected Token at 1:9").unwrap();
+        let infinite_loop = rendered.find("infinite loop at 2:1");
+        let illegal = rendered.find("Illegal Token at 3:9").unwrap();
+        assert!(
+            infinite_loop.is_some_and(|at| unexpected < at && at < illegal),
+            "{rendered}"
+        );
+
+        let (summary, _) = render_report("loop {}\nloop {}");
+        assert_eq!(summary, "2 warnings");
+    }
+
+    #[test]
+    fn test_report_of_one_diagnostic_shows_it_alone() {
+        let (summary, rendered) = render_report("print (1;");
+        assert_eq!(summary, "Unexpected Token at 1:9");
+        assert_eq!(
+            rendered,
+            render_parse_error("print (1;").replace("Token", "Token at 1:9")
+        );
+    }
 }
@@ -1,107 +1,589 @@
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Write;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use regex::Regex;
 
 use crate::callable::FunctionContainer;
-use crate::environment::Environment;
+use crate::environment::{Environment, Variable};
 use crate::error::LoxError;
 use crate::error::RuntimeError;
 use crate::evaluation::ReturnOrError::{Error, Return};
-use crate::evaluation::Value::{Boolean, Function, Nil};
-use crate::expression::{BinaryOp, Expression, ExpressionNode, LogicalOp, UnaryOp};
+use crate::evaluation::Value::{Function, Nil};
+use crate::expression::{
+    BinaryOp, Expression, ExpressionNode, LiteralType, LogicalOp, PatternElement, UnaryOp,
+};
+use crate::parser::parse;
+use crate::position::Position;
+use crate::profile::{FunctionKey, Profile};
+use crate::scanner::Scanner;
 use crate::statement::Statement;
-use crate::value::{Value, ValueNode};
+use crate::stdlib::{Clock, Prelude, SystemClock};
+use crate::value::{Namespace, Value, ValueNode};
 
-pub(crate) enum ReturnOrError {
+pub enum ReturnOrError {
     Error(LoxError),
     Return(Value),
+    Break,
+    Continue,
+}
+
+pub type EvaluationResult<T> = Result<T, ReturnOrError>;
+
+pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    prelude: Prelude,
+    output: Box<dyn Write>,
+    call_depth: usize,
+    peak_call_depth: usize,
+    patterns: HashMap<String, Regex>,
+    /// The values of string literals, so every evaluation of a literal shares one string.
+    strings: HashSet<Rc<str>>,
+    clock: Box<dyn Clock>,
+    interrupt: Arc<AtomicBool>,
+    profile: Option<Profile>,
+    trace: Option<Box<dyn Write>>,
+    /// The name and text of every source entered with `enter_source`, in order.
+    sources: Vec<(String, String)>,
+    source: SourceId,
+    /// The source the function the last error was raised in was defined in.
+    error_source: Option<SourceId>,
+}
+
+/// One of the sources entered with [`Interpreter::enter_source`], counted from 1. The
+/// default stands for code that was evaluated before any source was entered.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub(crate) struct SourceId(usize);
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter::with_output(Box::new(io::stdout()))
+    }
+
+    /// Creates an interpreter that writes everything the program prints to `output`.
+    pub fn with_output(output: Box<dyn Write>) -> Interpreter {
+        Interpreter::with_prelude(Prelude::new(), output)
+    }
+
+    /// Creates an interpreter whose globals are registered by `prelude`.
+    pub fn with_prelude(prelude: Prelude, output: Box<dyn Write>) -> Interpreter {
+        Interpreter {
+            globals: fresh_globals(&prelude),
+            prelude,
+            output,
+            call_depth: 0,
+            peak_call_depth: 0,
+            patterns: HashMap::new(),
+            strings: HashSet::new(),
+            clock: Box::new(SystemClock),
+            interrupt: Arc::new(AtomicBool::new(false)),
+            profile: None,
+            trace: None,
+            sources: Vec::new(),
+            source: SourceId::default(),
+            error_source: None,
+        }
+    }
+
+    /// Replaces the system clock that `clockMillis()` and `sleep()` use.
+    pub fn with_clock(mut self, clock: Box<dyn Clock>) -> Interpreter {
+        self.clock = clock;
+        self
+    }
+
+    pub(crate) fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// Lets running loops be stopped by setting `interrupt`, for example from a Ctrl-C
+    /// handler. The flag is cleared again when the loop stops with an `Interrupted` error.
+    pub fn with_interrupt(mut self, interrupt: Arc<AtomicBool>) -> Interpreter {
+        self.interrupt = interrupt;
+        self
+    }
+
+    /// Counts the calls of every function and measures their time, see `profile`.
+    pub fn with_profiling(mut self, enabled: bool) -> Interpreter {
+        self.profile = enabled.then(Profile::default);
+        self
+    }
+
+    /// The calls made so far, if profiling is enabled.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Writes a line to `trace` for every statement before it runs, see `set_trace`.
+    pub fn with_trace(mut self, trace: Box<dyn Write>) -> Interpreter {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Starts writing a line to `trace` for every statement before it runs, or stops if
+    /// `trace` is `None`. Each line shows the statement as source, without the statements
+    /// nested in it, indented by the call depth. Blocks are not traced themselves, only the
+    /// statements in them.
+    pub fn set_trace(&mut self, trace: Option<Box<dyn Write>>) {
+        self.trace = trace;
+    }
+
+    /// Whether statements are traced, see `set_trace`.
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    fn trace_statement(&mut self, stmt: &Statement) {
+        if matches!(stmt, Statement::Block(_)) {
+            return;
+        }
+        if let Some(trace) = &mut self.trace {
+            let indent = "  ".repeat(self.call_depth);
+            writeln!(trace, "{indent}{}", stmt.summary()).expect("failed to write trace");
+        }
+    }
+
+    /// Records the start of a call of the function `key`, if profiling is enabled. Every
+    /// call must be ended with `profile_exit`, also when it fails.
+    pub(crate) fn profile_enter(&mut self, key: FunctionKey, name: &str) {
+        if let Some(profile) = &mut self.profile {
+            profile.enter(key, name);
+        }
+    }
+
+    pub(crate) fn profile_exit(&mut self) {
+        if let Some(profile) = &mut self.profile {
+            profile.exit();
+        }
+    }
+
+    /// Fails with an `Interrupted` error at `position` if an interruption was requested.
+    fn check_interrupt(&self, position: &Position) -> EvaluationResult<()> {
+        if self.interrupt.swap(false, Ordering::Relaxed) {
+            return Err(Error(RuntimeError::interrupted(position.clone())));
+        }
+
+        Ok(())
+    }
+
+    /// Makes `text`, named `name`, the source of the statements evaluated from now on. The
+    /// functions they define remember it, so that an error raised in one of them while a
+    /// later source runs can be shown in the source it points into, see `error_source`.
+    pub fn enter_source(&mut self, name: impl Into<String>, text: impl Into<String>) {
+        self.sources.push((name.into(), text.into()));
+        self.source = SourceId(self.sources.len());
+    }
+
+    pub(crate) fn source(&self) -> SourceId {
+        self.source
+    }
+
+    /// Records that the error being returned was raised in a function defined in `source`,
+    /// unless a function it called already did.
+    pub(crate) fn raised_in(&mut self, source: SourceId) {
+        self.error_source.get_or_insert(source);
+    }
+
+    /// The name and text of the source that the positions of the error returned by the last
+    /// evaluation point into, if that is not the current source. This is the case when the
+    /// error was raised in a function defined in an earlier source.
+    pub fn error_source(&self) -> Option<(&str, &str)> {
+        let source = self.error_source.filter(|source| *source != self.source)?;
+        let (name, text) = self.sources.get(source.0.checked_sub(1)?)?;
+        Some((name, text))
+    }
+
+    /// The global variables, sorted by name, including the natives of the prelude.
+    pub fn globals(&self) -> Vec<Variable> {
+        self.globals.borrow().entries()
+    }
+
+    pub(crate) fn prelude(&self) -> &Prelude {
+        &self.prelude
+    }
+
+    /// Defines the global `name`, replacing a global of the same name.
+    pub(crate) fn define_global(&mut self, name: &str, value: Value) {
+        self.globals
+            .borrow_mut()
+            .register(name.to_string(), Some(value));
+    }
+
+    /// Drops every global the program defined by replacing the global scope with a fresh
+    /// one holding only the prelude.
+    pub fn reset(&mut self) {
+        self.globals = fresh_globals(&self.prelude);
+    }
+
+    /// Writes `text` followed by a newline to the output.
+    pub(crate) fn print(&mut self, text: &str) {
+        writeln!(self.output, "{text}").expect("failed to write output");
+    }
+
+    /// Writes `text` to the output without a newline and flushes it, so partial lines show
+    /// up immediately.
+    pub(crate) fn print_raw(&mut self, text: &str) {
+        write!(self.output, "{text}").expect("failed to write output");
+        self.output.flush().expect("failed to flush output");
+    }
+
+    /// Evaluates `statements` in the global scope and returns one value per statement: the
+    /// value of expression statements and of the branch an `if` takes, nil for everything
+    /// else.
+    pub fn evaluate(&mut self, statements: &Vec<Statement>) -> Result<Vec<Value>, LoxError> {
+        self.error_source = None;
+        evaluate(statements, self.globals.clone(), self)
+    }
+
+    /// Evaluates `statements` like `evaluate`, as a line typed into the REPL: the value of
+    /// the last expression statement that is not nil is bound to the global `_`, so the next
+    /// line can build on it.
+    pub fn evaluate_line(&mut self, statements: &Vec<Statement>) -> Result<Vec<Value>, LoxError> {
+        let values = self.evaluate(statements)?;
+
+        let last_result = statements
+            .iter()
+            .zip(&values)
+            .rev()
+            .find(|(statement, value)| {
+                matches!(statement, Statement::Expression(_)) && !value.is_nil()
+            });
+        if let Some((_, value)) = last_result {
+            self.globals
+                .borrow_mut()
+                .register("_".to_string(), Some(value.clone()));
+        }
+
+        Ok(values)
+    }
+
+    /// Evaluates `source` as a module in its own scope and binds its top-level functions and
+    /// variables to the global `name`, so they can be used as `name.member`. Fails if a
+    /// global called `name` already exists.
+    pub fn import_lox(&mut self, name: &str, source: &str) -> Result<(), LoxError> {
+        if self.globals.borrow().get(&name.to_string()).is_some() {
+            return Err(RuntimeError::duplicate_namespace(name.to_string()));
+        }
+
+        let (tokens, errors) = Scanner::new(source.to_string()).scan_with_errors();
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error);
+        }
+        let statements = parse(&tokens)?;
+
+        let module_env = Rc::new(RefCell::new(Environment::wrap(self.globals.clone())));
+        evaluate(&statements, module_env.clone(), self)?;
+
+        let namespace = Namespace {
+            name: name.to_string(),
+            members: module_env.borrow().initialized_variables(),
+        };
+        self.globals
+            .borrow_mut()
+            .register(name.to_string(), Some(Value::Namespace(Rc::new(namespace))));
+
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            environments: Environment::live_count(),
+            globals: self.globals.borrow().len(),
+            call_depth: self.call_depth,
+            peak_call_depth: self.peak_call_depth,
+            interned_strings: self.strings.len(),
+        }
+    }
+
+    /// Returns the shared copy of the string `s`, creating it on first use.
+    fn intern(&mut self, s: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+
+        let interned: Rc<str> = s.into();
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// Compiles the regular expression `pattern`, reusing an earlier compilation of the same
+    /// pattern string.
+    pub(crate) fn compile_pattern(&mut self, pattern: &str) -> Result<Regex, regex::Error> {
+        if let Some(regex) = self.patterns.get(pattern) {
+            return Ok(regex.clone());
+        }
+
+        let regex = Regex::new(pattern)?;
+        self.patterns.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    pub(crate) fn enter_call(&mut self) {
+        self.call_depth += 1;
+        self.peak_call_depth = self.peak_call_depth.max(self.call_depth);
+    }
+
+    pub(crate) fn exit_call(&mut self) {
+        self.call_depth -= 1;
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+/// Evaluates `source` with a new interpreter and returns the value of its last statement, or
+/// `nil` if it has none.
+#[cfg(test)]
+pub(crate) fn evaluate_source(source: &str) -> Result<Value, LoxError> {
+    let tokens = Scanner::new(source.to_string()).scan();
+    let statements = parse(&tokens)?;
+    let mut values = Interpreter::new().evaluate(&statements)?;
+    Ok(values.pop().unwrap_or(Value::Nil))
+}
+
+fn fresh_globals(prelude: &Prelude) -> Rc<RefCell<Environment>> {
+    let mut globals = Environment::empty();
+    prelude.register_globals(&mut globals);
+    Rc::new(RefCell::new(globals))
+}
+
+/// A snapshot of the interpreter's instrumentation counters.
+pub struct Stats {
+    pub environments: usize,
+    pub globals: usize,
+    pub call_depth: usize,
+    pub peak_call_depth: usize,
+    pub interned_strings: usize,
+}
+
+impl Stats {
+    pub(crate) fn entries(&self) -> [(&'static str, usize); 5] {
+        [
+            ("environments", self.environments),
+            ("globals", self.globals),
+            ("callDepth", self.call_depth),
+            ("peakCallDepth", self.peak_call_depth),
+            ("internedStrings", self.interned_strings),
+        ]
+    }
 }
 
-pub(crate) type EvaluationResult<T> = Result<T, ReturnOrError>;
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .entries()
+            .iter()
+            .map(|(name, count)| format!("{name}: {count}"))
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
 
 pub(crate) fn evaluate(
     statements: &Vec<Statement>,
     env: Rc<RefCell<Environment>>,
-) -> Result<Value, LoxError> {
-    let mut result: Result<Value, LoxError> = Ok(Nil);
+    interpreter: &mut Interpreter,
+) -> Result<Vec<Value>, LoxError> {
+    let mut values = Vec::with_capacity(statements.len());
 
     for stmt in statements {
-        result = match evaluate_statement(stmt, env.clone()) {
-            Ok(v) => Ok(v),
-            Err(Error(e)) => Err(e),
-            _ => panic!(),
+        match evaluate_statement(stmt, env.clone(), interpreter) {
+            Ok(v) => values.push(v),
+            Err(Error(e)) => return Err(e),
+            Err(_) => unreachable!("the parser rejects `return`, `break` and `continue` here"),
         }
     }
 
-    result
+    Ok(values)
 }
 
+/// Evaluates a statement that produces a value: an expression statement produces the value
+/// of its expression and an `if` the value of the branch taken. Every other statement is
+/// executed and produces nil.
 pub(crate) fn evaluate_statement(
     stmt: &Statement,
     env: Rc<RefCell<Environment>>,
+    interpreter: &mut Interpreter,
 ) -> EvaluationResult<Value> {
+    match stmt {
+        Statement::Expression(expr) => {
+            interpreter.trace_statement(stmt);
+            Ok(evaluate_expression(expr, env, interpreter)?.value)
+        }
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            interpreter.trace_statement(stmt);
+            let condition = evaluate_expression(condition, env.clone(), interpreter)?;
+
+            if condition.is_truthy() {
+                evaluate_statement(then_branch, env, interpreter)
+            } else if let Some(else_branch) = else_branch {
+                evaluate_statement(else_branch, env, interpreter)
+            } else {
+                Ok(Nil)
+            }
+        }
+        _ => {
+            execute_statement(stmt, env, interpreter)?;
+            Ok(Nil)
+        }
+    }
+}
+
+/// Executes a statement for its effects.
+pub(crate) fn execute_statement(
+    stmt: &Statement,
+    env: Rc<RefCell<Environment>>,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<()> {
+    interpreter.trace_statement(stmt);
+
     match stmt {
         Statement::Print(expr) => {
-            let inner_value = evaluate_expression(expr, env)?;
-            println!("{inner_value}");
-            Ok(inner_value.value)
+            let inner_value = evaluate_expression(expr, env, interpreter)?;
+            interpreter.print(&inner_value.to_string());
+            Ok(())
+        }
+        Statement::Expression(expr) => {
+            evaluate_expression(expr, env, interpreter)?;
+            Ok(())
         }
-        Statement::Expression(expr) => Ok(evaluate_expression(expr, env)?.value),
         Statement::Var { name, initializer } => {
             let initializer = match initializer {
-                Some(expr) => Some(evaluate_expression(expr, env.clone())?.value),
+                Some(expr) => Some(evaluate_expression(expr, env.clone(), interpreter)?.value),
                 _ => None,
             };
 
             env.borrow_mut().register(name.to_string(), initializer);
 
-            Ok(Nil)
+            Ok(())
+        }
+        Statement::VarPattern {
+            pattern,
+            initializer,
+        } => {
+            let value = evaluate_expression(initializer, env.clone(), interpreter)?;
+
+            for (name, _, value) in destructure(&value, pattern)? {
+                env.borrow_mut().register(name.to_string(), Some(value));
+            }
+
+            Ok(())
         }
         Statement::Block(statements) => {
             let block_env = Rc::new(RefCell::new(Environment::wrap(env)));
 
             for stmt in statements {
-                evaluate_statement(stmt, block_env.clone())?;
+                execute_statement(stmt, block_env.clone(), interpreter)?;
             }
 
-            Ok(Nil)
+            Ok(())
         }
         Statement::If {
             condition,
             then_branch,
             else_branch,
         } => {
-            let condition = evaluate_expression(condition, env.clone())?;
+            let condition = evaluate_expression(condition, env.clone(), interpreter)?;
 
-            if condition.as_boolean()? {
-                evaluate_statement(then_branch, env)
-            } else {
-                match else_branch {
-                    Some(else_branch) => evaluate_statement(else_branch, env),
-                    _ => Ok(Nil),
+            if condition.is_truthy() {
+                execute_statement(then_branch, env, interpreter)?;
+            } else if let Some(else_branch) = else_branch {
+                execute_statement(else_branch, env, interpreter)?;
+            }
+
+            Ok(())
+        }
+        Statement::While {
+            condition,
+            body,
+            increment,
+            ..
+        } => {
+            loop {
+                // No expression can `break` today. Should evaluating the condition ever
+                // unwind with a `Break`, it ends this loop and not a loop around it.
+                match evaluate_expression(condition, env.clone(), interpreter) {
+                    Ok(value) if value.is_truthy() => {}
+                    Ok(_) | Err(ReturnOrError::Break) => break,
+                    Err(e) => return Err(e),
+                }
+                interpreter.check_interrupt(&condition.position)?;
+
+                match execute_statement(body, env.clone(), interpreter) {
+                    Err(ReturnOrError::Break) => break,
+                    Ok(_) | Err(ReturnOrError::Continue) => {}
+                    Err(e) => return Err(e),
+                }
+
+                if let Some(increment) = increment {
+                    evaluate_expression(increment, env.clone(), interpreter)?;
                 }
             }
+
+            Ok(())
         }
-        Statement::While { condition, body } => {
-            while evaluate_expression(condition, env.clone())?.as_boolean()? {
-                evaluate_statement(body, env.clone())?;
+        Statement::Loop { body, position } => {
+            loop {
+                interpreter.check_interrupt(position)?;
+
+                match execute_statement(body, env.clone(), interpreter) {
+                    Err(ReturnOrError::Break) => break,
+                    Ok(_) | Err(ReturnOrError::Continue) => {}
+                    Err(e) => return Err(e),
+                }
             }
 
-            Ok(Nil)
+            Ok(())
         }
+        Statement::Break => Err(ReturnOrError::Break),
+        Statement::Continue => Err(ReturnOrError::Continue),
         Statement::Function {
             name,
             parameters,
             body,
         } => {
-            let container = FunctionContainer::new(name, parameters, body.clone(), env.clone());
+            let container = FunctionContainer::new(
+                name,
+                parameters,
+                body.clone(),
+                env.clone(),
+                interpreter.source(),
+            );
             env.borrow_mut()
                 .register(name.to_string(), Some(Function(Rc::new(container))));
 
-            Ok(Nil)
+            Ok(())
+        }
+        Statement::Assert { condition, message } => {
+            if evaluate_expression(condition, env.clone(), interpreter)?.is_truthy() {
+                return Ok(());
+            }
+
+            let message = match message {
+                Some(message) => Some(evaluate_expression(message, env, interpreter)?.to_string()),
+                None => None,
+            };
+
+            Err(Error(RuntimeError::assertion_failed(
+                message,
+                condition.position.clone(),
+            )))
         }
         Statement::Return(return_expression) => {
             let value = match return_expression {
-                Some(e) => evaluate_expression(e, env)?.value,
+                Some(e) => evaluate_expression(e, env, interpreter)?.value,
                 _ => Nil,
             };
 
@@ -113,15 +595,20 @@ pub(crate) fn evaluate_statement(
 fn evaluate_expression(
     expr: &ExpressionNode,
     env: Rc<RefCell<Environment>>,
+    interpreter: &mut Interpreter,
 ) -> EvaluationResult<ValueNode> {
     match &expr.expression {
+        Expression::Literal(LiteralType::StringLit(value)) => Ok(ValueNode::new(
+            Value::Str(interpreter.intern(value)),
+            &expr.position,
+        )),
         Expression::Literal(lit) => {
             let value_node: ValueNode = ValueNode::from_literal(lit, &expr.position);
             Ok(value_node)
         }
-        Expression::Grouping(inner) => evaluate_expression(inner, env),
+        Expression::Grouping(inner) => evaluate_expression(inner, env, interpreter),
         Expression::Unary { inner, op, .. } => {
-            let inner_value = evaluate_expression(inner, env)?;
+            let inner_value = evaluate_expression(inner, env, interpreter)?;
             let value = match op {
                 UnaryOp::Negative => inner_value.negative(),
                 UnaryOp::Not => inner_value.not(),
@@ -131,8 +618,8 @@ fn evaluate_expression(
         Expression::Binary {
             left, right, op, ..
         } => {
-            let left_value = evaluate_expression(left, env.clone())?;
-            let right_value = evaluate_expression(right, env)?;
+            let left_value = evaluate_expression(left, env.clone(), interpreter)?;
+            let right_value = evaluate_expression(right, env, interpreter)?;
 
             let value = match op {
                 BinaryOp::Equals => left_value.equals(&right_value),
@@ -149,22 +636,22 @@ fn evaluate_expression(
             Ok(ValueNode::new(value?, &expr.position))
         }
         Expression::Logical { left, right, op } => {
-            let left_value = evaluate_expression(left, env.clone())?;
+            let left_value = evaluate_expression(left, env.clone(), interpreter)?;
 
             match op {
                 LogicalOp::And => {
-                    if !left_value.as_boolean()? {
-                        return Ok(ValueNode::new(Boolean(false), &expr.position));
+                    if !left_value.is_truthy() {
+                        return Ok(ValueNode::new(left_value.value, &expr.position));
                     }
                 }
                 LogicalOp::Or => {
-                    if left_value.as_boolean()? {
-                        return Ok(ValueNode::new(Boolean(true), &expr.position));
+                    if left_value.is_truthy() {
+                        return Ok(ValueNode::new(left_value.value, &expr.position));
                     }
                 }
             }
 
-            let right_value = evaluate_expression(right, env)?;
+            let right_value = evaluate_expression(right, env, interpreter)?;
             Ok(ValueNode::new(right_value.value, &expr.position))
         }
         Expression::Variable(name) => match env.borrow().get(name) {
@@ -173,13 +660,19 @@ fn evaluate_expression(
                 name.to_string(),
                 expr.position.clone(),
             ))),
-            None => Err(Error(RuntimeError::unknown_identifier(
-                name.to_string(),
-                expr.position.clone(),
-            ))),
+            None => match interpreter.prelude.disabled_reason(name) {
+                Some(reason) => Err(Error(RuntimeError::disabled(
+                    reason.to_string(),
+                    expr.position.clone(),
+                ))),
+                None => Err(Error(RuntimeError::unknown_identifier(
+                    name.to_string(),
+                    expr.position.clone(),
+                ))),
+            },
         },
-        Expression::Assignment { name, value } => {
-            let value = evaluate_expression(value, env.clone())?;
+        Expression::Assignment { name, value, .. } => {
+            let value = evaluate_expression(value, env.clone(), interpreter)?;
             match env.borrow_mut().assign(name, value.value) {
                 true => Ok(ValueNode::new(Nil, &expr.position)),
                 false => Err(Error(RuntimeError::unknown_identifier(
@@ -188,22 +681,874 @@ fn evaluate_expression(
                 ))),
             }
         }
+        Expression::PatternAssignment { pattern, value } => {
+            let value = evaluate_expression(value, env.clone(), interpreter)?;
+
+            for (name, position, value) in destructure(&value, pattern)? {
+                if !env.borrow_mut().assign(name, value) {
+                    return Err(Error(RuntimeError::unknown_identifier(
+                        name.to_string(),
+                        position.clone(),
+                    )));
+                }
+            }
+
+            Ok(ValueNode::new(Nil, &expr.position))
+        }
         Expression::Lambda { arguments, body } => {
-            let container = FunctionContainer::new("", arguments, body.clone(), env.clone());
+            let container = FunctionContainer::new(
+                "",
+                arguments,
+                body.clone(),
+                env.clone(),
+                interpreter.source(),
+            );
             let function = Function(Rc::new(container));
             Ok(ValueNode::new(function, &expr.position))
         }
         Expression::Call { callee, arguments } => {
-            let callee_expr = evaluate_expression(callee, env.clone())?;
+            let callee_expr = evaluate_expression(callee, env.clone(), interpreter)?;
 
-            let argument_values = arguments
-                .iter()
-                .map(|arg| evaluate_expression(arg, env.clone()))
-                .collect::<EvaluationResult<Vec<ValueNode>>>()?;
+            let argument_values = evaluate_arguments(arguments, env, interpreter)?;
 
-            let value = callee_expr.call(argument_values)?;
+            if !callee_expr.value.is_callable() {
+                return Err(Error(RuntimeError::not_callable(&callee_expr)));
+            }
+            let value = callee_expr.call(argument_values, &expr.position, interpreter)?;
 
             Ok(ValueNode::new(value, &expr.position))
         }
+        Expression::Array(elements) => {
+            let values = elements
+                .iter()
+                .map(|element| Ok(evaluate_expression(element, env.clone(), interpreter)?.value))
+                .collect::<EvaluationResult<Vec<Value>>>()?;
+
+            Ok(ValueNode::new(
+                Value::Array(Rc::new(RefCell::new(values))),
+                &expr.position,
+            ))
+        }
+        Expression::Index { object, index } => {
+            let object = evaluate_expression(object, env.clone(), interpreter)?;
+            let index = evaluate_expression(index, env, interpreter)?;
+
+            Ok(ValueNode::new(object.get_index(&index)?, &expr.position))
+        }
+        Expression::Get { object, name } => {
+            let object = evaluate_expression(object, env, interpreter)?;
+
+            Ok(ValueNode::new(
+                object.get_member(name, &expr.position)?,
+                &expr.position,
+            ))
+        }
+        Expression::IndexAssignment {
+            object,
+            index,
+            value,
+        } => {
+            let object = evaluate_expression(object, env.clone(), interpreter)?;
+            let index = evaluate_expression(index, env.clone(), interpreter)?;
+            let value = evaluate_expression(value, env, interpreter)?;
+
+            object.set_index(&index, value.value)?;
+            Ok(ValueNode::new(Nil, &expr.position))
+        }
+        Expression::Spread(_) => unreachable!("spread arguments are expanded by the call"),
+    }
+}
+
+/// Evaluates the arguments of a call, expanding each `...array` argument into the elements
+/// of the array. The elements are positioned at the spread argument.
+fn evaluate_arguments(
+    arguments: &[ExpressionNode],
+    env: Rc<RefCell<Environment>>,
+    interpreter: &mut Interpreter,
+) -> EvaluationResult<Vec<ValueNode>> {
+    let mut values = Vec::with_capacity(arguments.len());
+
+    for argument in arguments {
+        match &argument.expression {
+            Expression::Spread(inner) => {
+                let array = evaluate_expression(inner, env.clone(), interpreter)?;
+                let array = ValueNode::new(array.value, &argument.position).as_array()?;
+                let elements = array.borrow();
+
+                values.extend(
+                    elements
+                        .iter()
+                        .map(|element| ValueNode::new(element.clone(), &argument.position)),
+                );
+            }
+            _ => values.push(evaluate_expression(argument, env.clone(), interpreter)?),
+        }
+    }
+
+    Ok(values)
+}
+
+/// Pairs the names of `pattern` with the elements of the array `value`, skipping `_`
+/// placeholders. Extra elements are ignored.
+fn destructure<'a>(
+    value: &ValueNode,
+    pattern: &'a [PatternElement],
+) -> EvaluationResult<Vec<(&'a String, &'a Position, Value)>> {
+    let array = value.as_array()?;
+    let array = array.borrow();
+
+    if let Some(element) = pattern.get(array.len()) {
+        return Err(Error(RuntimeError::missing_element(
+            array.len(),
+            array.len(),
+            element.position.clone(),
+        )));
+    }
+
+    Ok(pattern
+        .iter()
+        .zip(array.iter())
+        .filter_map(|(element, value)| {
+            let name = element.name.as_ref()?;
+            Some((name, &element.position, value.clone()))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::Diagnostic;
+
+    use crate::callable::{NativeContext, NativeFunctionContainer};
+    use crate::error::RuntimeError;
+    use crate::value::Value::Boolean;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn trace(source: &str) -> Vec<String> {
+        let buffer = SharedBuffer::default();
+        let mut interpreter =
+            Interpreter::with_output(Box::new(io::sink())).with_trace(Box::new(buffer.clone()));
+        let tokens = Scanner::new(source.to_string()).scan();
+        interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+
+        let trace = String::from_utf8(buffer.0.take()).unwrap();
+        trace.lines().map(str::to_string).collect()
+    }
+
+    fn printed(source: &str) -> String {
+        let buffer = SharedBuffer::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        let tokens = Scanner::new(source.to_string()).scan();
+        interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+
+        String::from_utf8(buffer.0.take()).unwrap()
+    }
+
+    #[test]
+    fn test_print_numbers_beyond_i64_as_floats() {
+        assert_eq!(
+            printed("print 9223372036854775808;"),
+            "9223372036854776000\n"
+        );
+        assert_eq!(
+            printed("print 9223372036854775807;"),
+            "9223372036854776000\n"
+        );
+    }
+
+    #[test]
+    fn test_trace_lists_statements_in_order() {
+        assert_eq!(
+            trace("var i = 1;\ni = i + 1;\nprint i;"),
+            vec!["var i = 1;", "i = i + 1;", "print i;"]
+        );
+    }
+
+    #[test]
+    fn test_trace_indents_calls_and_shows_for_loops_once() {
+        let source = "
+            fun double(n) { return n * 2; }
+            for (var i = 0; i < 2; i = i + 1) { double(i); }";
+        assert_eq!(
+            trace(source),
+            vec![
+                "fun double(n)",
+                "var i = 0;",
+                "for (; i < 2; i = i + 1)",
+                "double(i);",
+                "  return n * 2;",
+                "double(i);",
+                "  return n * 2;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_can_be_switched_off() {
+        let buffer = SharedBuffer::default();
+        let mut interpreter = Interpreter::with_output(Box::new(io::sink()));
+        assert!(!interpreter.is_tracing());
+
+        interpreter.set_trace(Some(Box::new(buffer.clone())));
+        assert!(interpreter.is_tracing());
+        interpreter.set_trace(None);
+        let tokens = Scanner::new("print 1;".to_string()).scan();
+        interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+
+        assert!(buffer.0.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_returns_one_value_per_statement() {
+        let tokens =
+            Scanner::new("var a = 1; a + 1; { a; } if (true) 3; \"x\";".to_string()).scan();
+        let values = Interpreter::new()
+            .evaluate(&parse(&tokens).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                Nil,
+                Value::Number(2.0),
+                Nil,
+                Value::Number(3.0),
+                Value::Str("x".into())
+            ]
+        );
+        assert!(Interpreter::new().evaluate(&Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_line_binds_the_last_result() {
+        let mut interpreter = Interpreter::new();
+        let mut line = |source: &str| {
+            let tokens = Scanner::new(source.to_string()).scan();
+            interpreter.evaluate_line(&parse(&tokens).unwrap()).unwrap()
+        };
+
+        line("1 + 2;");
+        line("var a = _ * 10; print a; nil;");
+        assert_eq!(line("_;"), vec![Value::Number(3.0)]);
+        line("_ * 10;");
+        assert_eq!(line("a == _;"), vec![Boolean(true)]);
+        assert_eq!(line("_;"), vec![Boolean(true)]);
+    }
+
+    #[test]
+    fn test_last_result_is_a_normal_variable() {
+        let mut interpreter = Interpreter::new();
+        let mut line = |source: &str| {
+            let tokens = Scanner::new(source.to_string()).scan();
+            interpreter.evaluate_line(&parse(&tokens).unwrap()).unwrap()
+        };
+
+        line("\"a\";");
+        line("_ = _ + \"b\"; { var _ = 1; }");
+        assert_eq!(line("_;"), vec![Value::Str("ab".into())]);
+    }
+
+    #[test]
+    fn test_evaluate_does_not_bind_the_last_result() {
+        assert!(matches!(
+            evaluate_source("1 + 2; _;"),
+            Err(LoxError::RuntimeError(
+                RuntimeError::UnknownIdentifier { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_for_with_empty_clauses() {
+        let source = "var n = 0; for (;;) { n = n + 1; if (n == 3) break; } n;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(3.0));
+
+        let source = "var n = 0; for (;false;) { n = n + 1; } n;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(0.0));
+
+        let source = "var n = 0; for (var i = 0; i < 3; i = i + 1) { n = n + i; } n;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_interrupt_stops_loops() {
+        for source in ["while (true) {}", "loop {}", "for (;;) {}"] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            let statements = parse(&tokens).unwrap();
+            let interrupt = Arc::new(AtomicBool::new(true));
+            let mut interpreter = Interpreter::new().with_interrupt(interrupt.clone());
+
+            assert!(
+                matches!(
+                    interpreter.evaluate(&statements),
+                    Err(LoxError::RuntimeError(RuntimeError::Interrupted { .. }))
+                ),
+                "{source}"
+            );
+            assert!(!interrupt.load(Ordering::Relaxed), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_environment_count_goes_down_after_block_exits() {
+        let tokens = Scanner::new("{ var a = 1; { var b = a; } }".to_string()).scan();
+        let statements = parse(&tokens).unwrap();
+        let mut interpreter = Interpreter::new();
+
+        let before = interpreter.stats().environments;
+        interpreter.evaluate(&statements).unwrap();
+        assert_eq!(interpreter.stats().environments, before);
+    }
+
+    #[test]
+    fn test_stats_track_call_depth() {
+        let source = "fun f(n) { if (n > 0) { f(n - 1); } } f(3); stats()[\"peakCallDepth\"];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(4.0));
+
+        let source = "fun f() { return stats()[\"callDepth\"]; } f();";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(1.0));
+    }
+
+    #[test]
+    fn test_stats_count_interned_strings() {
+        let mut interpreter = Interpreter::new();
+        let tokens =
+            Scanner::new("for (var i = 0; i < 3; i = i + 1) { \"a\"; \"b\"; }".into()).scan();
+        interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+        assert_eq!(interpreter.stats().interned_strings, 2);
+
+        let source = "var a = \"x\"; var b = \"x\"; var c = \"y\"; stats()[\"internedStrings\"];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(2.0));
+    }
+
+    #[test]
+    fn test_stats_count_globals() {
+        let mut interpreter = Interpreter::new();
+        let globals = interpreter.stats().globals;
+
+        let tokens = Scanner::new("var a = 1; var b = 2;".to_string()).scan();
+        interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+        assert_eq!(interpreter.stats().globals, globals + 2);
+    }
+
+    #[test]
+    fn test_zero_empty_string_and_maps_are_truthy() {
+        for condition in ["0", "\"\"", "Map()", "\"false\""] {
+            let source = format!("var r = false; if ({condition}) r = true; r;");
+            assert_eq!(
+                evaluate_source(&source).unwrap(),
+                Boolean(true),
+                "{condition}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nil_and_false_are_falsy() {
+        for condition in ["nil", "false"] {
+            let source = format!("var r = false; if ({condition}) r = true; r;");
+            assert_eq!(
+                evaluate_source(&source).unwrap(),
+                Boolean(false),
+                "{condition}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_logical_operators_and_not_use_truthiness() {
+        assert_eq!(
+            evaluate_source("0 and \"\";").unwrap(),
+            Value::Str("".into())
+        );
+        assert_eq!(evaluate_source("nil or 0;").unwrap(), Value::Number(0.0));
+        assert_eq!(evaluate_source("!0;").unwrap(), Boolean(false));
+        assert_eq!(evaluate_source("!nil;").unwrap(), Boolean(true));
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit_to_the_left_operand() {
+        assert_eq!(evaluate_source("1 or 2;").unwrap(), Value::Number(1.0));
+        assert_eq!(evaluate_source("nil and 1;").unwrap(), Nil);
+        assert_eq!(evaluate_source("false and 1;").unwrap(), Boolean(false));
+        assert_eq!(
+            evaluate_source("\"a\" or 2;").unwrap(),
+            Value::Str("a".into())
+        );
+    }
+
+    #[test]
+    fn test_while_condition_uses_truthiness() {
+        let source = "var i = 3; var n = 0; while (i) { n = n + 1; if (n > 2) i = nil; } n;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_map_index_get_and_set() {
+        let source = "var m = Map(); m[\"a\"] = 1; m[true] = 2; m[\"a\"] + m[true];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_map_missing_key_is_nil() {
+        assert_eq!(evaluate_source("Map()[\"a\"];").unwrap(), Nil);
+    }
+
+    #[test]
+    fn test_map_integral_and_float_keys_are_the_same() {
+        let source = "var m = Map(); m[1] = \"one\"; m[1.0];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Str("one".into()));
+    }
+
+    #[test]
+    fn test_map_zero_and_negative_zero_are_the_same_key() {
+        let source = "var m = Map(); m[0] = \"zero\"; m[-0];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Str("zero".into()));
+    }
+
+    #[test]
+    fn test_map_rejects_nan_keys() {
+        assert!(matches!(
+            evaluate_source("var m = Map(); m[0/0] = 1;"),
+            Err(LoxError::RuntimeError(RuntimeError::InvalidArgument { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_map_rejects_unhashable_keys() {
+        let source = "var m = Map(); m[nil] = 1;";
+        match evaluate_source(source) {
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { position, .. })) => {
+                assert_eq!(position.absolute, 17);
+                assert_eq!(position.length, 3);
+            }
+            _ => panic!("expected a type error"),
+        }
+
+        assert!(matches!(
+            evaluate_source("var m = Map(); m[Map()];"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_indexing_non_map_is_a_type_error() {
+        assert!(matches!(
+            evaluate_source("var x = 1; x[0];"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+    }
+
+    fn evaluate_with(interpreter: &mut Interpreter, source: &str) -> Result<Value, LoxError> {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let mut values = interpreter.evaluate(&parse(&tokens)?)?;
+        Ok(values.pop().unwrap_or(Value::Nil))
+    }
+
+    #[test]
+    fn test_imported_modules_keep_their_own_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .import_lox(
+                "a",
+                "fun name() { return \"a\"; } fun greet() { return name(); }",
+            )
+            .unwrap();
+        interpreter
+            .import_lox("b", "fun name() { return \"b\"; } var answer = 42;")
+            .unwrap();
+
+        assert_eq!(
+            evaluate_with(&mut interpreter, "a.greet() + b.name();").unwrap(),
+            Value::Str("ab".into())
+        );
+        assert_eq!(
+            evaluate_with(&mut interpreter, "b.answer;").unwrap(),
+            Value::Number(42.0)
+        );
+    }
+
+    #[test]
+    fn test_errors_in_functions_point_into_the_source_defining_them() {
+        let mut interpreter = Interpreter::new();
+        let mut run_input = |line: usize, source: &str| {
+            interpreter.enter_source(format!("lox:{line}"), source);
+            let statements = parse(&Scanner::new(source.to_string()).scan()).unwrap();
+            let result = interpreter.evaluate(&statements);
+            let error_source = interpreter
+                .error_source()
+                .map(|(name, text)| (name.to_string(), text.to_string()));
+            (result, error_source)
+        };
+
+        let first = "var unused = 1;\nfun negate(x) {\n  return -x;\n}";
+        assert!(run_input(1, first).0.is_ok());
+
+        let (result, error_source) = run_input(2, "negate(\"a\");");
+        assert_eq!(error_source, Some(("lox:1".to_string(), first.to_string())));
+        let label = result.unwrap_err().labels().unwrap().next().unwrap();
+        assert_eq!(&first[label.offset()..][..label.len()], "x");
+
+        // Errors outside of earlier functions point into the input being run.
+        let (result, error_source) = run_input(3, "negate(1); -\"b\";");
+        assert!(result.is_err());
+        assert_eq!(error_source, None);
+        let (result, error_source) = run_input(4, "fun f() { return -\"c\"; } f();");
+        assert!(result.is_err());
+        assert_eq!(error_source, None);
+    }
+
+    /// Unwinds like a `break`, which no Lox expression can do yet.
+    fn unwind_break(_: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+        Err(ReturnOrError::Break)
+    }
+
+    #[test]
+    fn test_break_while_evaluating_a_condition_ends_that_loop() {
+        let mut interpreter = Interpreter::new();
+        let native = NativeFunctionContainer::new("unwindBreak", 0..=0, unwind_break);
+        interpreter.define_global("unwindBreak", Function(Rc::new(native)));
+
+        let source = "var outer = 0; var inner = 0;
+            while (outer < 2) {
+                outer = outer + 1;
+                while (unwindBreak()) { inner = inner + 1; }
+            }
+            [outer, inner];";
+        assert_eq!(
+            evaluate_with(&mut interpreter, source).unwrap().to_string(),
+            "[2, 0]"
+        );
+    }
+
+    #[test]
+    fn test_import_empty_module() {
+        let mut interpreter = Interpreter::new();
+        interpreter.import_lox("empty", "").unwrap();
+        interpreter
+            .import_lox("comment", "// nothing here")
+            .unwrap();
+
+        for name in ["empty", "comment"] {
+            match evaluate_with(&mut interpreter, &format!("{name};")).unwrap() {
+                Value::Namespace(namespace) => assert!(namespace.members.is_empty(), "{name}"),
+                value => panic!("expected a namespace, found {value}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_twice_with_same_name_fails() {
+        let mut interpreter = Interpreter::new();
+        interpreter.import_lox("a", "var x = 1;").unwrap();
+
+        assert!(matches!(
+            interpreter.import_lox("a", "var y = 2;"),
+            Err(LoxError::RuntimeError(
+                RuntimeError::DuplicateNamespace { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_namespace_member() {
+        let mut interpreter = Interpreter::new();
+        interpreter.import_lox("a", "var x = 1;").unwrap();
+
+        assert!(matches!(
+            evaluate_with(&mut interpreter, "a.y;"),
+            Err(LoxError::RuntimeError(RuntimeError::UnknownMember { .. }))
+        ));
+        assert!(matches!(
+            evaluate_source("var a = 1; a.x;"),
+            Err(LoxError::RuntimeError(RuntimeError::UnknownMember { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_arrays() {
+        assert_eq!(
+            evaluate_source("var a = [1, \"b\", [true]]; a[1] = 2; a;")
+                .unwrap()
+                .to_string(),
+            "[1, 2, [true]]"
+        );
+        assert!(matches!(
+            evaluate_source("[1, 2][2];"),
+            Err(LoxError::RuntimeError(RuntimeError::InvalidArgument { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_destructuring_exact_length() {
+        let source = "fun pair() { return [1, 2]; } var [a, b] = pair(); a * 10 + b;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(12.0));
+    }
+
+    #[test]
+    fn test_destructuring_too_short() {
+        match evaluate_source("var [a, b, c] = [1, 2];") {
+            Err(LoxError::RuntimeError(RuntimeError::MissingElement {
+                index, position, ..
+            })) => {
+                assert_eq!(index, 2);
+                assert_eq!(position.absolute, 11);
+            }
+            _ => panic!("expected a missing element error"),
+        }
+    }
+
+    #[test]
+    fn test_destructuring_too_long_and_placeholders() {
+        let source = "var xs = [1, 2, 3, 4]; var [head, _, third] = xs; head + third;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(4.0));
+
+        let source = "var xs = [1, 2, 3]; var [head, rest] = [xs[0], slice(xs, 1)]; rest;";
+        assert_eq!(evaluate_source(source).unwrap().to_string(), "[2, 3]");
+    }
+
+    #[test]
+    fn test_destructuring_in_for_loop_body() {
+        let source = "var pairs = [[1, 2], [3, 4]]; var sum = 0;
+            for (var i = 0; i < 2; i = i + 1) { var [a, b] = pairs[i]; sum = sum + a * b; }
+            sum;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(14.0));
+    }
+
+    #[test]
+    fn test_destructuring_assignment() {
+        let source = "var a = 1; var b = 2; [a, b] = [b, a]; a - b;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(1.0));
+
+        assert!(matches!(
+            evaluate_source("var [a] = 1;"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_loop_with_break() {
+        let source = "var i = 0; loop { i = i + 1; if (i > 4) { break; } } i;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(5.0));
+    }
+
+    #[test]
+    fn test_continue() {
+        let source = "var i = 0; var odd = 0; var skip = true;
+            loop { i = i + 1; if (i > 5) break; skip = !skip; if (skip) continue; odd = odd + 1; }
+            odd;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(3.0));
+
+        let source = "var sum = 0; for (var i = 0; i < 5; i = i + 1) { if (i < 3) continue; sum = sum + i; } sum;";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(7.0));
+    }
+
+    #[test]
+    fn test_arrays_are_shared_between_variables() {
+        let source = "var a = [1]; var b = a; b[0] = 2; a[0];";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(2.0));
+    }
+
+    #[test]
+    fn test_assert() {
+        let source =
+            "fun check(n) { assert n > 1; assert n < 3, \"too big\"; return n; } check(2);";
+        assert_eq!(evaluate_source(source).unwrap(), Value::Number(2.0));
+
+        match evaluate_source("var n = 5; assert n < 3, \"n is \" + \"too big\";") {
+            Err(LoxError::RuntimeError(RuntimeError::AssertionFailed { message, position })) => {
+                assert_eq!(message, "assertion failed: n is too big");
+                assert_eq!(position, Position::new(18, 5));
+            }
+            _ => panic!("expected an assertion failure"),
+        }
+
+        match evaluate_source("assert nil;") {
+            Err(LoxError::RuntimeError(RuntimeError::AssertionFailed { message, .. })) => {
+                assert_eq!(message, "assertion failed")
+            }
+            _ => panic!("expected an assertion failure"),
+        }
+    }
+
+    #[test]
+    fn test_assert_message_is_evaluated_lazily() {
+        assert_eq!(
+            evaluate_source("assert true, undefined; 1;").unwrap(),
+            Value::Number(1.0)
+        );
+    }
+
+    fn error_message(source: &str) -> String {
+        match evaluate_source(source) {
+            Err(error) => error.to_string(),
+            Ok(value) => panic!("expected an error, got {value:?}"),
+        }
+    }
+
+    #[test]
+    fn test_operations_on_nil() {
+        assert_eq!(error_message("nil + 1;"), "cannot add nil to number");
+        assert_eq!(error_message("1 + nil;"), "cannot add nil to number");
+        assert_eq!(error_message("\"a\" + nil;"), "cannot add nil to string");
+        assert_eq!(error_message("nil * 2;"), "cannot multiply nil by number");
+        assert_eq!(
+            error_message("nil.size();"),
+            "cannot access member size of nil"
+        );
+
+        match evaluate_source("var x = nil; 1 + x;") {
+            Err(LoxError::RuntimeError(RuntimeError::NilOperation { position, .. })) => {
+                assert_eq!(position, Position::new(17, 1))
+            }
+            _ => panic!("expected a nil operation error"),
+        }
+
+        assert_eq!(
+            evaluate_source("nil == nil;").unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(evaluate_source("nil != 1;").unwrap(), Value::Boolean(true));
+    }
+
+    #[test]
+    fn test_type_error_names_the_found_type() {
+        match evaluate_source("nil - 1;") {
+            Err(LoxError::RuntimeError(RuntimeError::TypeError {
+                found, expected, ..
+            })) => {
+                assert_eq!(found, "Nil");
+                assert_eq!(expected, "Number");
+            }
+            _ => panic!("expected a type error"),
+        }
+    }
+
+    #[test]
+    fn test_rest_parameter_collects_extra_arguments() {
+        let source = "fun count(first, ...rest) { return [first, rest]; }";
+        assert_eq!(
+            evaluate_source(&format!("{source} count(1);"))
+                .unwrap()
+                .to_string(),
+            "[1, []]"
+        );
+        assert_eq!(
+            evaluate_source(&format!("{source} count(1, 2, 3);"))
+                .unwrap()
+                .to_string(),
+            "[1, [2, 3]]"
+        );
+
+        match evaluate_source(&format!("{source} count();")) {
+            Err(LoxError::RuntimeError(RuntimeError::ArityMismatch {
+                expected, found, ..
+            })) => {
+                assert_eq!(expected, "at least 1");
+                assert_eq!(found, 0);
+            }
+            _ => panic!("expected an arity mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_arity_mismatch() {
+        let source = "fun add(a, b) { return a + b; }";
+
+        for (call, found, position) in [
+            ("add(1);", 1, Position::new(35, 3)),
+            ("add(1, 2, 3);", 3, Position::new(35, 9)),
+        ] {
+            match evaluate_source(&format!("{source} {call}")) {
+                Err(LoxError::RuntimeError(RuntimeError::ArityMismatch {
+                    expected,
+                    found: actual,
+                    position: actual_position,
+                })) => {
+                    assert_eq!(expected, "2");
+                    assert_eq!(actual, found);
+                    assert_eq!(actual_position, position);
+                }
+                result => panic!("expected an arity mismatch for {call}, got {result:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_spread_arguments() {
+        let source = "var sum = fun (...xs) { var s = 0; for (var i = 0; i < xs.size(); i = i + 1) s = s + xs[i]; return s; };";
+        assert_eq!(
+            evaluate_source(&format!("{source} sum(1, ...[2, 3], ...[], 4);")).unwrap(),
+            Value::Number(10.0)
+        );
+        assert_eq!(
+            evaluate_source(&format!("{source} sum(...[]);")).unwrap(),
+            Value::Number(0.0)
+        );
+        assert_eq!(
+            evaluate_source("fun pair(a, b) { return a - b; } pair(...[3, 1]);").unwrap(),
+            Value::Number(2.0)
+        );
+    }
+
+    #[test]
+    fn test_calling_a_non_function() {
+        match evaluate_source("nil(42);") {
+            Err(LoxError::RuntimeError(RuntimeError::NotCallable {
+                type_name,
+                position,
+            })) => {
+                assert_eq!(type_name, "Nil");
+                assert_eq!(position, Position::new(0, 3));
+            }
+            _ => panic!("expected a not callable error"),
+        }
+
+        assert!(matches!(
+            evaluate_source("var s = \"f\"; s();"),
+            Err(LoxError::RuntimeError(RuntimeError::NotCallable { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_spread_of_non_array() {
+        match evaluate_source("fun f(...xs) { return xs; } f(1, ...2);") {
+            Err(LoxError::RuntimeError(RuntimeError::TypeError {
+                expected, position, ..
+            })) => {
+                assert_eq!(expected, "Array");
+                assert_eq!(position, Position::new(33, 4));
+            }
+            _ => panic!("expected a type error"),
+        }
+    }
+
+    #[test]
+    fn test_compiled_patterns_are_cached() {
+        let mut interpreter = Interpreter::new();
+        let source = "var found = 0;
+            for (var i = 0; i < 3; i = i + 1) { if (matches(\"a1\", \"[0-9]\")) found = found + 1; }
+            findAll(\"b2\", \"[0-9]\"); found;";
+
+        assert_eq!(
+            evaluate_with(&mut interpreter, source).unwrap(),
+            Value::Number(3.0)
+        );
+        assert_eq!(interpreter.patterns.len(), 1);
+        assert!(interpreter.patterns.contains_key("[0-9]"));
     }
 }
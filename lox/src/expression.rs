@@ -2,6 +2,7 @@ use std::fmt;
 use std::fmt::Formatter;
 use std::rc::Rc;
 
+use crate::ast::display::to_s_expression;
 use crate::expression::BinaryOp::*;
 use crate::expression::Expression::*;
 use crate::expression::LiteralType::*;
@@ -53,27 +54,83 @@ pub enum Expression {
     Assignment {
         name: String,
         value: Box<ExpressionNode>,
+        /// The position of the `=`.
+        equals: Position,
     },
     Lambda {
-        arguments: Vec<String>,
+        arguments: Parameters,
         body: Rc<Statement>,
     },
     Call {
         callee: Box<ExpressionNode>,
         arguments: Vec<ExpressionNode>,
     },
+    Array(Vec<ExpressionNode>),
+    Index {
+        object: Box<ExpressionNode>,
+        index: Box<ExpressionNode>,
+    },
+    Get {
+        object: Box<ExpressionNode>,
+        name: String,
+    },
+    IndexAssignment {
+        object: Box<ExpressionNode>,
+        index: Box<ExpressionNode>,
+        value: Box<ExpressionNode>,
+    },
+    PatternAssignment {
+        pattern: Vec<PatternElement>,
+        value: Box<ExpressionNode>,
+    },
+    /// `...array` in an argument list, passing the elements of `array` as arguments.
+    Spread(Box<ExpressionNode>),
+}
+
+/// One name of a destructuring pattern like `[a, _, b]`. `_` placeholders have no name and
+/// skip their position.
+pub struct PatternElement {
+    pub name: Option<String>,
+    pub position: Position,
+}
+
+/// The parameters of a function. A rest parameter `...name` is always last and binds an
+/// array of the arguments left over after the other parameters.
+#[derive(Clone)]
+pub struct Parameters {
+    pub names: Vec<String>,
+    pub rest: Option<String>,
+}
+
+impl fmt::Display for Parameters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut names = self.names.clone();
+        names.extend(self.rest.iter().map(|rest| format!("...{rest}")));
+
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+/// The prefix of a node `level` levels deep in a pretty-printed tree.
+pub(crate) fn tree_prefix(level: u32) -> String {
+    if level == 0 {
+        "".to_string()
+    } else {
+        let mut prefix = "   ".repeat(level as usize);
+        prefix.push_str("|_ ");
+        prefix
+    }
 }
 
 impl ExpressionNode {
     pub fn pretty(&self) -> String {
+        self.pretty_at(0)
+    }
+
+    /// Same as `pretty`, for an expression nested `level` levels deep in a tree.
+    pub(crate) fn pretty_at(&self, level: u32) -> String {
         fn pretty(expr: &ExpressionNode, level: u32) -> String {
-            let mut prefix = if level == 0 {
-                "".to_string()
-            } else {
-                let mut prefix = "   ".repeat(level as usize);
-                prefix.push_str("|_ ");
-                prefix
-            };
+            let mut prefix = tree_prefix(level);
 
             let thing = match &expr.expression {
                 Unary { inner, op, .. } => {
@@ -127,7 +184,7 @@ impl ExpressionNode {
                         identifier, expr.position.absolute, expr.position.length
                     )
                 }
-                Assignment { name, value } => {
+                Assignment { name, value, .. } => {
                     format!(
                         "Assignment: {} ({}:{})\n{}",
                         name,
@@ -136,7 +193,12 @@ impl ExpressionNode {
                         pretty(value, level + 1),
                     )
                 }
-                _ => "unknown".to_string(),
+                _ => format!(
+                    "{} ({}:{})",
+                    to_s_expression(expr),
+                    expr.position.absolute,
+                    expr.position.length
+                ),
             };
 
             prefix.push_str(&thing);
@@ -144,10 +206,12 @@ impl ExpressionNode {
             prefix
         }
 
-        pretty(self, 0)
+        pretty(self, level)
     }
 }
 
+// The suffix keeps the variants apart from the token types of the same name, e.g. `Number`.
+#[allow(clippy::enum_variant_names)]
 pub enum LiteralType {
     NumberLit(f64),
     StringLit(String),
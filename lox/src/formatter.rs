@@ -0,0 +1,550 @@
+//! `lox fmt`: prints a program back from its syntax tree in a consistent style.
+//!
+//! The printer walks the statements together with the tokens they were parsed from and copies
+//! the text of every token from the source, so literals stay as they were written. Comments,
+//! which the scanner drops, are taken from the gaps between the tokens: a comment on a line of
+//! its own stays on its own line, any other comment moves to the end of the line its statement
+//! ends on. Runs of blank lines between statements become a single blank line.
+
+use crate::error::LoxError;
+use crate::expression::{Expression, ExpressionNode, Parameters};
+use crate::parser;
+use crate::position::Position;
+use crate::scanner::Scanner;
+use crate::statement::Statement;
+use crate::token::Token;
+use crate::token::TokenType::{self, For, LeftBrace};
+
+const INDENT: &str = "    ";
+
+/// Formats `source`, or returns the errors that prevent it from being parsed. Formatting
+/// the result again does not change it.
+pub fn format_source(source: &str) -> Result<String, Vec<LoxError>> {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_with_errors();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let parsed = parser::parse_with_recovery(&tokens);
+    if !parsed.errors.is_empty() {
+        return Err(parsed.errors);
+    }
+    let statements = parsed.statements;
+
+    let mut printer = Printer {
+        source,
+        comments: Scanner::comments(&tokens, source),
+        tokens: &tokens,
+        next_token: 0,
+        next_comment: 0,
+        last_end: 0,
+        indent: 0,
+        output: String::with_capacity(source.len()),
+    };
+    printer.statements(&statements);
+
+    Ok(printer.output)
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    tokens: &'a [Token],
+    comments: Vec<Position>,
+    /// The index of the next token to print.
+    next_token: usize,
+    /// The index of the next comment to print.
+    next_comment: usize,
+    /// The end of the last token or comment printed, in the source.
+    last_end: usize,
+    indent: usize,
+    output: String,
+}
+
+impl Printer<'_> {
+    fn peek(&self) -> Option<&TokenType> {
+        self.tokens.get(self.next_token).map(|t| &t.token_type)
+    }
+
+    /// Where the next token starts in the source, or the end of the source.
+    fn next_start(&self) -> usize {
+        self.tokens
+            .get(self.next_token)
+            .map_or(self.source.len(), |t| t.position.absolute)
+    }
+
+    /// Copies the next token from the source.
+    fn token(&mut self) {
+        let position = &self.tokens[self.next_token].position;
+        self.output
+            .push_str(&self.source[position.absolute..position.end_position()]);
+        self.last_end = self.last_end.max(position.end_position());
+        self.next_token += 1;
+    }
+
+    /// Copies the next token from the source with a space on both sides, as for operators.
+    fn spaced_token(&mut self) {
+        self.output.push(' ');
+        self.token();
+        self.output.push(' ');
+    }
+
+    /// The next comment, if it comes before the next token.
+    fn pending_comment(&self) -> Option<Position> {
+        self.comments
+            .get(self.next_comment)
+            .filter(|comment| comment.absolute < self.next_start())
+            .cloned()
+    }
+
+    fn comment(&mut self, comment: &Position) {
+        let text = &self.source[comment.absolute..comment.end_position()];
+        self.output.push_str(text.trim_end());
+        self.last_end = self.last_end.max(comment.end_position());
+        self.next_comment += 1;
+    }
+
+    /// Whether nothing but whitespace precedes `comment` on its line.
+    fn is_own_line(&self, comment: &Position) -> bool {
+        let before = self.source[..comment.absolute].trim_end_matches([' ', '\t']);
+        before.is_empty() || before.ends_with('\n')
+    }
+
+    /// Starts a line for a statement or comment at `offset` in the source. Unless it is the
+    /// first line of its block, a blank line before it in the source is kept.
+    fn start_line(&mut self, offset: usize, first: &mut bool) {
+        let blank_line = self
+            .source
+            .get(self.last_end..offset)
+            .is_some_and(|gap| gap.matches('\n').count() > 1);
+        if blank_line && !*first {
+            self.output.push('\n');
+        }
+        *first = false;
+
+        self.indentation();
+    }
+
+    fn indentation(&mut self) {
+        self.output.push_str(&INDENT.repeat(self.indent));
+    }
+
+    /// Prints the comments before the next token, each on its own line.
+    fn leading_comments(&mut self, first: &mut bool) {
+        while let Some(comment) = self.pending_comment() {
+            self.start_line(comment.absolute, first);
+            self.comment(&comment);
+            self.output.push('\n');
+        }
+    }
+
+    /// Prints a comment that follows the statement just printed on its line.
+    fn trailing_comment(&mut self) {
+        if let Some(comment) = self.pending_comment() {
+            if !self.is_own_line(&comment) {
+                self.output.push(' ');
+                self.comment(&comment);
+            }
+        }
+    }
+
+    /// Prints `statements` one per line, followed by the comments up to the next token.
+    fn statements(&mut self, statements: &[Statement]) {
+        let mut first = true;
+
+        for statement in statements {
+            self.leading_comments(&mut first);
+            self.start_line(self.next_start(), &mut first);
+            self.statement(statement);
+            self.trailing_comment();
+            self.output.push('\n');
+        }
+
+        self.leading_comments(&mut first);
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.token();
+        if statements.is_empty() && self.pending_comment().is_none() {
+            self.token();
+            return;
+        }
+
+        self.output.push('\n');
+        self.indent += 1;
+        self.statements(statements);
+        self.indent -= 1;
+        self.indentation();
+        self.token();
+    }
+
+    /// Prints the body of an `if`, a loop or a function: a block goes on the same line, any
+    /// other statement indented on the next one. Returns whether the body is a block.
+    fn body(&mut self, body: &Statement) -> bool {
+        match body {
+            Statement::Block(statements) if self.peek() == Some(&LeftBrace) => {
+                self.output.push(' ');
+                self.block(statements);
+                true
+            }
+            _ => {
+                self.output.push('\n');
+                self.indent += 1;
+                self.indentation();
+                self.statement(body);
+                self.indent -= 1;
+                false
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Print(expression) => {
+                self.token();
+                self.output.push(' ');
+                self.expression(expression);
+                self.token();
+            }
+            Statement::Expression(expression) => {
+                self.expression(expression);
+                self.token();
+            }
+            Statement::Var { initializer, .. } => {
+                self.token();
+                self.output.push(' ');
+                self.token();
+                if let Some(initializer) = initializer {
+                    self.spaced_token();
+                    self.expression(initializer);
+                }
+                self.token();
+            }
+            Statement::VarPattern {
+                pattern,
+                initializer,
+            } => {
+                self.token();
+                self.output.push(' ');
+                self.pattern(pattern.len());
+                self.spaced_token();
+                self.expression(initializer);
+                self.token();
+            }
+            // A `for` loop with an initializer is parsed into a block around the loop.
+            Statement::Block(statements) if self.peek() == Some(&For) => {
+                self.for_loop(Some(&statements[0]), &statements[1])
+            }
+            Statement::Block(statements) => self.block(statements),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.token();
+                self.output.push(' ');
+                self.condition(condition);
+                let then_is_block = self.body(then_branch);
+
+                if let Some(else_branch) = else_branch {
+                    if then_is_block {
+                        self.output.push(' ');
+                    } else {
+                        self.output.push('\n');
+                        self.indentation();
+                    }
+                    self.token();
+
+                    if let Statement::If { .. } = else_branch.as_ref() {
+                        self.output.push(' ');
+                        self.statement(else_branch);
+                    } else {
+                        self.body(else_branch);
+                    }
+                }
+            }
+            Statement::While {
+                desugared_for: true,
+                ..
+            } => self.for_loop(None, statement),
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.token();
+                self.output.push(' ');
+                self.condition(condition);
+                self.body(body);
+            }
+            Statement::Loop { body, .. } => {
+                self.token();
+                self.body(body);
+            }
+            Statement::Break | Statement::Continue => {
+                self.token();
+                self.token();
+            }
+            Statement::Function {
+                parameters, body, ..
+            } => {
+                self.token();
+                self.output.push(' ');
+                self.token();
+                self.parameters(parameters);
+                self.body(body);
+            }
+            Statement::Return(value) => {
+                self.token();
+                if let Some(value) = value {
+                    self.output.push(' ');
+                    self.expression(value);
+                }
+                self.token();
+            }
+            Statement::Assert { condition, message } => {
+                self.token();
+                self.output.push(' ');
+                self.expression(condition);
+                if let Some(message) = message {
+                    self.token();
+                    self.output.push(' ');
+                    self.expression(message);
+                }
+                self.token();
+            }
+        }
+    }
+
+    /// Prints a `for` loop from the `while` loop it was parsed into. The tokens tell which
+    /// of the clauses were written.
+    fn for_loop(&mut self, initializer: Option<&Statement>, desugared: &Statement) {
+        let Statement::While {
+            condition,
+            body,
+            increment,
+            ..
+        } = desugared
+        else {
+            unreachable!("a for loop is parsed into a while loop");
+        };
+
+        self.token();
+        self.output.push(' ');
+        self.token();
+
+        match initializer {
+            Some(initializer) => self.statement(initializer),
+            None => self.token(),
+        }
+
+        if self.peek() != Some(&TokenType::Semicolon) {
+            self.output.push(' ');
+            self.expression(condition);
+        }
+        self.token();
+
+        if let Some(increment) = increment {
+            self.output.push(' ');
+            self.expression(increment);
+        }
+        self.token();
+
+        self.body(body);
+    }
+
+    /// Prints a parenthesized condition.
+    fn condition(&mut self, condition: &ExpressionNode) {
+        self.token();
+        self.expression(condition);
+        self.token();
+    }
+
+    fn parameters(&mut self, parameters: &Parameters) {
+        self.token();
+        let count = parameters.names.len() + usize::from(parameters.rest.is_some());
+        for index in 0..count {
+            if index > 0 {
+                self.token();
+                self.output.push(' ');
+            }
+            if index == parameters.names.len() {
+                self.token();
+            }
+            self.token();
+        }
+        self.token();
+    }
+
+    /// Prints a destructuring pattern of `length` names.
+    fn pattern(&mut self, length: usize) {
+        self.token();
+        for index in 0..length {
+            if index > 0 {
+                self.token();
+                self.output.push(' ');
+            }
+            self.token();
+        }
+        self.token();
+    }
+
+    /// Prints comma separated `elements` between the delimiters around them.
+    fn list(&mut self, elements: &[ExpressionNode]) {
+        self.token();
+        for (index, element) in elements.iter().enumerate() {
+            if index > 0 {
+                self.token();
+                self.output.push(' ');
+            }
+            self.expression(element);
+        }
+        self.token();
+    }
+
+    fn expression(&mut self, expression: &ExpressionNode) {
+        match &expression.expression {
+            Expression::Literal(_) | Expression::Variable(_) => self.token(),
+            Expression::Unary { inner, .. } => {
+                self.token();
+                self.expression(inner);
+            }
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                self.expression(left);
+                self.spaced_token();
+                self.expression(right);
+            }
+            Expression::Grouping(inner) => {
+                self.token();
+                self.expression(inner);
+                self.token();
+            }
+            Expression::Assignment { value, .. } => {
+                self.token();
+                self.spaced_token();
+                self.expression(value);
+            }
+            Expression::IndexAssignment {
+                object,
+                index,
+                value,
+            } => {
+                self.expression(object);
+                self.token();
+                self.expression(index);
+                self.token();
+                self.spaced_token();
+                self.expression(value);
+            }
+            Expression::PatternAssignment { pattern, value } => {
+                self.pattern(pattern.len());
+                self.spaced_token();
+                self.expression(value);
+            }
+            Expression::Lambda { arguments, body } => {
+                self.token();
+                self.output.push(' ');
+                self.parameters(arguments);
+                self.body(body);
+            }
+            Expression::Call { callee, arguments } => {
+                self.expression(callee);
+                self.list(arguments);
+            }
+            Expression::Array(elements) => self.list(elements),
+            Expression::Index { object, index } => {
+                self.expression(object);
+                self.token();
+                self.expression(index);
+                self.token();
+            }
+            Expression::Get { object, .. } => {
+                self.expression(object);
+                self.token();
+                self.token();
+            }
+            Expression::Spread(inner) => {
+                self.token();
+                self.expression(inner);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::error::ParseError;
+
+    use super::*;
+
+    const MESSY_SPACING: &str = include_str!("../tests/fmt/messy_spacing.lox");
+    const MESSY_SPACING_FORMATTED: &str = include_str!("../tests/fmt/messy_spacing.formatted.lox");
+    const MESSY_BLOCKS: &str = include_str!("../tests/fmt/messy_blocks.lox");
+    const MESSY_BLOCKS_FORMATTED: &str = include_str!("../tests/fmt/messy_blocks.formatted.lox");
+    const COMMENTS: &str = include_str!("../tests/fmt/comments.lox");
+    const COMMENTS_FORMATTED: &str = include_str!("../tests/fmt/comments.formatted.lox");
+
+    #[test]
+    fn test_golden_files() {
+        for (messy, formatted) in [
+            (MESSY_SPACING, MESSY_SPACING_FORMATTED),
+            (MESSY_BLOCKS, MESSY_BLOCKS_FORMATTED),
+            (COMMENTS, COMMENTS_FORMATTED),
+        ] {
+            pretty_assertions::assert_eq!(format_source(messy).unwrap(), formatted);
+            pretty_assertions::assert_eq!(format_source(formatted).unwrap(), formatted);
+        }
+    }
+
+    #[test]
+    fn test_formatting_is_idempotent_on_the_fixtures() {
+        let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+        let mut formatted_fixtures = 0;
+
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if !matches!(path.extension(), Some(e) if e == "lox" || e == "crox") {
+                continue;
+            }
+
+            let source = fs::read_to_string(&path).unwrap();
+            // Some fixtures use syntax the parser does not support yet, like classes.
+            let Ok(once) = format_source(&source) else {
+                continue;
+            };
+
+            assert_eq!(format_source(&once).unwrap(), once, "{}", path.display());
+            formatted_fixtures += 1;
+        }
+
+        assert!(formatted_fixtures >= 5);
+    }
+
+    #[test]
+    fn test_well_formatted_source_is_unchanged() {
+        let source = include_str!("../tests/control_flow.lox").replace("}\n\n\n", "}\n\n");
+        assert_eq!(format_source(&source).unwrap(), source);
+    }
+
+    #[test]
+    fn test_empty_source() {
+        assert_eq!(format_source("").unwrap(), "");
+        assert_eq!(format_source("  \n\n").unwrap(), "");
+        assert_eq!(
+            format_source("\n  // only a comment  \n\n").unwrap(),
+            "// only a comment\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_are_returned() {
+        let errors = format_source("var a = ;\nprint a\n").unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                LoxError::ParseError(ParseError::IllegalToken { .. }),
+                LoxError::ParseError(ParseError::UnexpectedEndOfTokenStream { .. })
+            ]
+        ));
+        assert!(format_source("print \"unterminated;").is_err());
+    }
+}
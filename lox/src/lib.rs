@@ -0,0 +1,141 @@
+//! A tree-walking interpreter for Lox, the language from Crafting Interpreters.
+//!
+//! [`evaluate_program`] runs a program in one call. [`evaluate_program_with_env`] keeps the
+//! globals of an [`Interpreter`] from one program to the next, as the REPL does. Errors point
+//! into the source with byte offsets, [`LineIndex`] turns them into lines and columns.
+//! [`parse_statement`] and [`parse_expression`] parse a single construct on its own.
+
+pub use crate::environment::{Environment, Variable};
+pub use crate::error::{error_codes, ErrorCode, LoxError};
+pub use crate::evaluation::Interpreter;
+pub use crate::expression::ExpressionNode;
+pub use crate::parser::{parse_expression, parse_statement};
+pub use crate::scanner::{LineIndex, Scanner};
+pub use crate::statement::Statement;
+pub use crate::token::{Token, TokenType};
+pub use crate::value::Value;
+
+mod ast;
+mod builtins;
+mod callable;
+mod check;
+pub mod cli;
+mod environment;
+mod error;
+mod evaluation;
+mod expression;
+mod formatter;
+mod parser;
+mod position;
+mod profile;
+mod repl;
+mod resolver;
+pub mod scanner;
+mod session;
+mod statement;
+mod stdlib;
+mod token;
+mod tokenize;
+mod value;
+mod watch;
+
+/// Scans, parses and evaluates `source` with a fresh interpreter that prints to stdout.
+/// Returns one value per top-level statement, as [`Interpreter::evaluate`] does.
+pub fn evaluate_program(source: &str) -> Result<Vec<Value>, LoxError> {
+    evaluate_program_with_env(source, &mut Interpreter::new())
+}
+
+/// Evaluates `source` like [`evaluate_program`], in the global environment of
+/// `interpreter`. Globals defined by earlier programs stay visible.
+pub fn evaluate_program_with_env(
+    source: &str,
+    interpreter: &mut Interpreter,
+) -> Result<Vec<Value>, LoxError> {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_with_errors();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let statements = parser::parse(&tokens)?;
+
+    interpreter.evaluate(&statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_program() {
+        assert_eq!(
+            evaluate_program("var a = 1; a + 2;").unwrap(),
+            vec![Value::Nil, Value::Number(3.0)]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_program_without_statements() {
+        assert_eq!(evaluate_program("").unwrap(), vec![]);
+        assert_eq!(evaluate_program("// only a comment").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_evaluate_program_reports_errors() {
+        assert!(matches!(
+            evaluate_program("\"unterminated"),
+            Err(LoxError::ScanError(_))
+        ));
+        assert!(matches!(
+            evaluate_program("1 +;"),
+            Err(LoxError::ParseError(_))
+        ));
+        assert!(matches!(
+            evaluate_program("return 1;"),
+            Err(LoxError::ParseError(_))
+        ));
+        assert!(matches!(
+            evaluate_program("undefined;"),
+            Err(LoxError::RuntimeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_line_index_locates_errors() {
+        use miette::Diagnostic;
+
+        let source = "var a = 1;\nprint a + nil;";
+        let error = evaluate_program(source).unwrap_err();
+        let offset = error.labels().unwrap().next().unwrap().offset();
+
+        assert_eq!(LineIndex::from_source(source).line_col_of(offset), (2, 11));
+    }
+
+    #[test]
+    fn test_parse_single_constructs() {
+        let tokens = Scanner::new("var a = 1;".to_string()).scan();
+        let statement = parse_statement(&tokens).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.evaluate(&vec![statement]).unwrap();
+        assert_eq!(
+            evaluate_program_with_env("a;", &mut interpreter).unwrap(),
+            vec![Value::Number(1.0)]
+        );
+
+        let tokens = Scanner::new("1 + 2".to_string()).scan();
+        assert!(parse_expression(&tokens).is_ok());
+        let tokens = Scanner::new("1 + 2; 3".to_string()).scan();
+        assert!(matches!(
+            parse_expression(&tokens),
+            Err(LoxError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_program_with_env_keeps_globals() {
+        let mut interpreter = Interpreter::new();
+        evaluate_program_with_env("fun double(x) { return 2 * x; }", &mut interpreter).unwrap();
+        assert_eq!(
+            evaluate_program_with_env("double(21);", &mut interpreter).unwrap(),
+            vec![Value::Number(42.0)]
+        );
+    }
+}
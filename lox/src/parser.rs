@@ -5,10 +5,12 @@ use std::slice::Iter;
 
 use crate::error::{LoxError, ParseError};
 use crate::expression::Expression::{
-    Binary, Call, Grouping, Lambda, Literal, Logical, Unary, Variable,
+    Array, Binary, Call, Get, Grouping, Index, Lambda, Literal, Logical, Spread, Unary, Variable,
+};
+use crate::expression::LiteralType::TrueLit;
+use crate::expression::{
+    BinaryOp, Expression, ExpressionNode, LogicalOp, Parameters, PatternElement, UnaryOp,
 };
-use crate::expression::LiteralType::{FalseLit, NilLit, NumberLit, StringLit, TrueLit};
-use crate::expression::{BinaryOp, Expression, ExpressionNode, LogicalOp, UnaryOp};
 use crate::position::Position;
 use crate::statement::Statement;
 use crate::token::TokenType::*;
@@ -16,18 +18,30 @@ use crate::token::{Token, TokenType};
 
 pub type ParseResult<T> = Result<T, LoxError>;
 
+const MAX_ARGUMENTS: usize = 255;
+
 struct TokenIter<'a> {
     peekable: Peekable<Iter<'a, Token>>,
+    previous: Option<&'a Token>,
     size: usize,
+    loop_depth: usize,
+    /// Whether a `return` is allowed, that is whether a function body is being parsed.
+    in_function: bool,
+    /// Whether the expression being parsed is the condition of an `if` or a loop.
+    in_condition: bool,
 }
 
 impl<'a> TokenIter<'a> {
-    pub fn new(tokens: &[Token]) -> TokenIter {
+    pub fn new(tokens: &[Token]) -> TokenIter<'_> {
         let last_token = tokens.last().unwrap();
         let peekable = tokens.iter().peekable();
         TokenIter {
             peekable,
+            previous: None,
             size: last_token.position.absolute + last_token.position.length,
+            loop_depth: 0,
+            in_function: false,
+            in_condition: false,
         }
     }
 
@@ -36,15 +50,31 @@ impl<'a> TokenIter<'a> {
     }
 
     fn next(&mut self) -> Option<&Token> {
-        self.peekable.next()
+        self.previous = self.peekable.next();
+        self.previous
+    }
+
+    /// An empty position just after the last token, for errors about input that ends too
+    /// early. If nothing follows the last token it is at the very end of the source, where
+    /// a label is only shown if the source is rendered with a final line break.
+    fn end_of_input(&self) -> Position {
+        Position::new(self.size, 0)
     }
 
     pub fn next_if(&mut self, func: impl FnOnce(&&Token) -> bool) -> Option<&Token> {
-        self.peekable.next_if(func)
+        let token = self.peekable.next_if(func);
+        if token.is_some() {
+            self.previous = token;
+        }
+        token
     }
 }
 
 pub fn parse(tokens: &[Token]) -> ParseResult<Vec<Statement>> {
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
     let mut token_iter = TokenIter::new(tokens);
     let mut statements = Vec::new();
 
@@ -55,6 +85,93 @@ pub fn parse(tokens: &[Token]) -> ParseResult<Vec<Statement>> {
     Ok(statements)
 }
 
+/// The result of `parse_with_recovery`: the statements that could be parsed and all errors.
+pub struct ParseWithRecoveryResult {
+    pub statements: Vec<Statement>,
+    pub errors: Vec<LoxError>,
+}
+
+/// Parses all statements like `parse`, but instead of stopping at the first error, skips to
+/// the start of the next statement and continues, so that all errors can be reported at once.
+pub fn parse_with_recovery(tokens: &[Token]) -> ParseWithRecoveryResult {
+    let mut result = ParseWithRecoveryResult {
+        statements: Vec::new(),
+        errors: Vec::new(),
+    };
+    if tokens.is_empty() {
+        return result;
+    }
+
+    let mut token_iter = TokenIter::new(tokens);
+
+    while token_iter.peek().is_some() {
+        match declaration(&mut token_iter) {
+            Ok(statement) => result.statements.push(statement),
+            Err(error) => {
+                result.errors.push(error);
+                synchronize(&mut token_iter);
+            }
+        }
+    }
+
+    result
+}
+
+/// Parses `tokens` as exactly one declaration or statement, including its `;`.
+pub fn parse_statement(tokens: &[Token]) -> ParseResult<Statement> {
+    parse_single(tokens, declaration, "a statement")
+}
+
+/// Parses `tokens` as exactly one expression, without a trailing `;`.
+pub fn parse_expression(tokens: &[Token]) -> ParseResult<ExpressionNode> {
+    parse_single(tokens, expression, "an expression")
+}
+
+/// Runs `parser` on `tokens` and fails if it does not consume all of them. `expected`
+/// describes what it parses, for the error if there are no tokens.
+fn parse_single<T>(
+    tokens: &[Token],
+    parser: fn(&mut TokenIter) -> ParseResult<T>,
+    expected: &str,
+) -> ParseResult<T> {
+    if tokens.is_empty() {
+        return Err(ParseError::unexpected_end_of_stream(
+            expected,
+            Position::new(0, 0),
+        ));
+    }
+
+    let mut token_iter = TokenIter::new(tokens);
+    let parsed = parser(&mut token_iter)?;
+
+    match token_iter.next() {
+        Some(token) => Err(ParseError::illegal_token(token.clone())),
+        None => Ok(parsed),
+    }
+}
+
+/// Skips the tokens after a parse error up to the start of the next statement: past the
+/// next `;` or up to a keyword that starts a statement. Every parse error consumes the
+/// token it is reported for, so parsing makes progress even if nothing is skipped.
+fn synchronize(tokens: &mut TokenIter) {
+    if tokens.previous.is_some_and(|t| t.token_type == Semicolon) {
+        return;
+    }
+
+    while let Some(token) = tokens.peek() {
+        if matches!(
+            token.token_type,
+            Class | Fun | Var | For | If | While | Loop | Print | Return | Assert
+        ) {
+            return;
+        }
+
+        if tokens.next().is_some_and(|t| t.token_type == Semicolon) {
+            return;
+        }
+    }
+}
+
 fn declaration(tokens: &mut TokenIter) -> ParseResult<Statement> {
     match tokens.peek() {
         Some(Token {
@@ -76,13 +193,24 @@ fn declaration(tokens: &mut TokenIter) -> ParseResult<Statement> {
 }
 
 fn function(tokens: &mut TokenIter) -> ParseResult<Statement> {
+    error_context(function_declaration(tokens), "function declaration")
+}
+
+/// Parses a method in a class body, `name(parameters) { body }`. Unlike a function
+/// declaration it has no `fun` in front, so the class parser calls this directly.
+#[allow(dead_code)] // Until the parser has class declarations.
+fn method_declaration(tokens: &mut TokenIter) -> ParseResult<Statement> {
+    error_context(function_declaration(tokens), "method declaration")
+}
+
+fn function_declaration(tokens: &mut TokenIter) -> ParseResult<Statement> {
     let name = consume_identifier(tokens)?;
 
     let parameters = parse_function_arguments(tokens)?;
 
     let left_brace = consume(tokens, LeftBrace)?;
     let position = left_brace.position.clone();
-    let body = block(tokens, position)?;
+    let body = function_body(tokens, position)?;
 
     Ok(Statement::Function {
         name,
@@ -91,15 +219,32 @@ fn function(tokens: &mut TokenIter) -> ParseResult<Statement> {
     })
 }
 
-fn parse_function_arguments(tokens: &mut TokenIter) -> ParseResult<Vec<String>> {
+fn parse_function_arguments(tokens: &mut TokenIter) -> ParseResult<Parameters> {
     consume(tokens, LeftParent)?;
 
-    let mut parameters: Vec<String> = vec![];
+    let mut parameters = Parameters {
+        names: vec![],
+        rest: None,
+    };
     if tokens.next_if(|t| t.token_type == RightParent).is_none() {
         loop {
-            parameters.push(consume_identifier(tokens)?);
+            if let Some(t) = tokens.next_if(|t| t.token_type == DotDotDot) {
+                let mut position = t.position.clone();
+                if let Some(name) = tokens.peek() {
+                    position.union(&name.position);
+                }
+                parameters.rest = Some(consume_identifier(tokens)?);
 
-            if tokens.peek().map_or(false, |t| t.token_type == RightParent) {
+                if tokens.peek().is_some_and(|t| t.token_type == Comma) {
+                    return Err(ParseError::misplaced_rest_parameter(&position));
+                }
+                consume(tokens, RightParent)?;
+                break;
+            }
+
+            parameters.names.push(consume_identifier(tokens)?);
+
+            if tokens.peek().is_some_and(|t| t.token_type == RightParent) {
                 tokens.next();
                 break;
             }
@@ -112,6 +257,11 @@ fn parse_function_arguments(tokens: &mut TokenIter) -> ParseResult<Vec<String>>
 }
 
 fn var(tokens: &mut TokenIter) -> ParseResult<Statement> {
+    if let Some(t) = tokens.next_if(|t| t.token_type == LeftBracket) {
+        let opening_position = t.position.clone();
+        return var_pattern(tokens, &opening_position);
+    }
+
     let identifier = consume_identifier(tokens)?;
 
     let initializer = match tokens.next_if(|t| t.token_type == Equal) {
@@ -127,6 +277,41 @@ fn var(tokens: &mut TokenIter) -> ParseResult<Statement> {
     })
 }
 
+fn var_pattern(tokens: &mut TokenIter, opening_position: &Position) -> ParseResult<Statement> {
+    let mut pattern = vec![];
+
+    if tokens.next_if(|t| t.token_type == RightBracket).is_none() {
+        loop {
+            let position = tokens.peek().map(|t| t.position.clone());
+            let name = consume_identifier(tokens)?;
+            pattern.push(pattern_element(name, position.unwrap()));
+
+            if tokens.next_if(|t| t.token_type == Comma).is_none() {
+                consume_closing_delimiter(tokens, RightBracket, opening_position)?;
+                break;
+            }
+        }
+    }
+
+    consume(tokens, Equal)?;
+    let initializer = expression(tokens)?;
+    consume(tokens, Semicolon)?;
+
+    Ok(Statement::VarPattern {
+        pattern,
+        initializer,
+    })
+}
+
+fn pattern_element(name: String, position: Position) -> PatternElement {
+    let name = match name.as_str() {
+        "_" => None,
+        _ => Some(name),
+    };
+
+    PatternElement { name, position }
+}
+
 fn statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
     match tokens.peek() {
         Some(Token {
@@ -145,14 +330,26 @@ fn statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
                 let _ = tokens.next();
                 for_statement(tokens)
             }
+            Loop => {
+                let position = tokens.next().unwrap().position.clone();
+                loop_statement(tokens, position)
+            }
+            Break | Continue => {
+                let keyword = tokens.next().unwrap().clone();
+                loop_control_statement(tokens, &keyword)
+            }
             Return => {
-                let _ = tokens.next();
-                return_statement(tokens)
+                let keyword = tokens.next().unwrap().clone();
+                return_statement(tokens, &keyword)
             }
             Print => {
                 let _ = tokens.next();
                 print_statement(tokens)
             }
+            Assert => {
+                let _ = tokens.next();
+                assert_statement(tokens)
+            }
             LeftBrace => {
                 let position = tokens.next().unwrap().position.clone();
                 block(tokens, position)
@@ -163,12 +360,34 @@ fn statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
     }
 }
 
+/// Parses a parenthesized condition as used by `if` and `while`.
+fn condition(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
+    let opening_position = consume(tokens, LeftParent)?.position.clone();
+    let condition = condition_expression(tokens, expression)?;
+    consume_closing_delimiter(tokens, RightParent, &opening_position)?;
+
+    Ok(condition)
+}
+
+/// Parses a condition with `parser`. In a condition, an invalid assignment target is
+/// probably a comparison written with `=`.
+fn condition_expression<T>(
+    tokens: &mut TokenIter,
+    parser: fn(&mut TokenIter) -> ParseResult<T>,
+) -> ParseResult<T> {
+    let in_condition = std::mem::replace(&mut tokens.in_condition, true);
+    let condition = parser(tokens);
+    tokens.in_condition = in_condition;
+
+    condition
+}
+
 fn if_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
-    let condition = expression(tokens)?;
+    let condition = error_context(condition(tokens), "if condition")?;
 
-    let then_branch = statement(tokens)?;
+    let then_branch = error_context(statement(tokens), "if statement")?;
     let else_branch = match tokens.next_if(|t| t.token_type == Else) {
-        Some(_) => Some(statement(tokens)?),
+        Some(_) => Some(error_context(statement(tokens), "else branch")?),
         None => None,
     };
 
@@ -180,16 +399,72 @@ fn if_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
 }
 
 fn while_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
-    let condition = expression(tokens)?;
-    let body = statement(tokens)?;
+    let condition = error_context(condition(tokens), "while condition")?;
+    let body = error_context(loop_body(tokens, statement), "while loop")?;
 
     Ok(Statement::While {
         condition,
         body: Box::new(body),
+        increment: None,
+        desugared_for: false,
     })
 }
 
+fn loop_statement(tokens: &mut TokenIter, position: Position) -> ParseResult<Statement> {
+    let body = loop_body(tokens, |tokens| {
+        let left_brace = consume(tokens, LeftBrace)?;
+        let position = left_brace.position.clone();
+        block(tokens, position)
+    })?;
+
+    Ok(Statement::Loop {
+        body: Box::new(body),
+        position,
+    })
+}
+
+/// Parses the body of a loop, in which `break` and `continue` are allowed.
+fn loop_body(
+    tokens: &mut TokenIter,
+    parser: fn(&mut TokenIter) -> ParseResult<Statement>,
+) -> ParseResult<Statement> {
+    tokens.loop_depth += 1;
+    let body = parser(tokens);
+    tokens.loop_depth -= 1;
+
+    body
+}
+
+/// Parses the body of a function, in which `break` and `continue` may not refer to loops
+/// surrounding the function, and which is not part of a condition the function is in.
+fn function_body(tokens: &mut TokenIter, opening_brace_pos: Position) -> ParseResult<Statement> {
+    let loop_depth = std::mem::take(&mut tokens.loop_depth);
+    let in_function = std::mem::replace(&mut tokens.in_function, true);
+    let in_condition = std::mem::take(&mut tokens.in_condition);
+    let body = block(tokens, opening_brace_pos);
+    tokens.loop_depth = loop_depth;
+    tokens.in_function = in_function;
+    tokens.in_condition = in_condition;
+
+    body
+}
+
+fn loop_control_statement(tokens: &mut TokenIter, keyword: &Token) -> ParseResult<Statement> {
+    if tokens.loop_depth == 0 {
+        return Err(ParseError::outside_loop(keyword));
+    }
+
+    consume(tokens, Semicolon)?;
+
+    match keyword.token_type {
+        Break => Ok(Statement::Break),
+        _ => Ok(Statement::Continue),
+    }
+}
+
 fn for_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
+    let opening_position = consume(tokens, LeftParent)?.position.clone();
+
     let initializer = match tokens.peek() {
         Some(Token {
             token_type: Var,
@@ -207,7 +482,10 @@ fn for_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
         }
         Some(_) => Some(expression_statement(tokens)?),
         None => {
-            return Err(ParseError::unexpected_end_of_stream());
+            return Err(ParseError::unexpected_end_of_stream(
+                "the initializer of the `for` loop or `;`",
+                tokens.end_of_input(),
+            ));
         }
     };
 
@@ -225,22 +503,35 @@ fn for_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
                 consume(tokens, Semicolon)?;
                 expr
             }
-            None => Err(ParseError::unexpected_end_of_stream()),
+            None => Err(ParseError::unexpected_end_of_stream(
+                "the condition of the `for` loop or `;`",
+                tokens.end_of_input(),
+            )),
         }
     }
 
-    let condition = parse(tokens)?;
-    let increment = parse(tokens)?;
-
-    let mut body = statement(tokens)?;
+    // An absent condition is always true, placed at the semicolon that ends it.
+    let condition_position = tokens.peek().map(|token| token.position.clone());
+    let condition = condition_expression(tokens, parse)?;
+    let increment = match tokens.next_if(|t| t.token_type == RightParent) {
+        Some(_) => None,
+        None => {
+            let increment = expression(tokens)?;
+            consume_closing_delimiter(tokens, RightParent, &opening_position)?;
+            Some(increment)
+        }
+    };
 
-    if let Some(increment) = increment {
-        body = Statement::Block(vec![body, Statement::Expression(increment)]);
-    }
+    let body = loop_body(tokens, statement)?;
 
-    body = Statement::While {
-        condition: condition.unwrap_or(ExpressionNode::new(Literal(TrueLit), &Position::new(0, 1))),
+    let mut body = Statement::While {
+        condition: condition.unwrap_or_else(|| {
+            let position = condition_position.expect("the condition was parsed");
+            ExpressionNode::new(Literal(TrueLit), &position)
+        }),
         body: Box::new(body),
+        increment,
+        desugared_for: true,
     };
 
     if let Some(initializer) = initializer {
@@ -250,15 +541,29 @@ fn for_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
     Ok(body)
 }
 
-fn return_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
+fn assert_statement(tokens: &mut TokenIter) -> ParseResult<Statement> {
+    let condition = expression(tokens)?;
+
+    let message = match tokens.next_if(|t| t.token_type == Comma) {
+        Some(_) => Some(expression(tokens)?),
+        None => None,
+    };
+
+    consume(tokens, Semicolon)?;
+
+    Ok(Statement::Assert { condition, message })
+}
+
+fn return_statement(tokens: &mut TokenIter, keyword: &Token) -> ParseResult<Statement> {
+    if !tokens.in_function {
+        return Err(ParseError::outside_function(keyword));
+    }
+
     let return_expression = match tokens.peek() {
         Some(Token {
             token_type: Semicolon,
             position: _,
-        }) => {
-            tokens.next();
-            None
-        }
+        }) => None,
         _ => Some(expression(tokens)?),
     };
 
@@ -286,7 +591,11 @@ fn block(tokens: &mut TokenIter, opening_brace_pos: Position) -> ParseResult<Sta
             }
             Some(_) => {}
             None => {
-                return Err(ParseError::unexpected_end_of_stream());
+                return Err(ParseError::unclosed_delimiter(
+                    &RightBrace,
+                    &opening_brace_pos,
+                    &tokens.end_of_input(),
+                ));
             }
         }
 
@@ -315,7 +624,7 @@ fn lambda(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
             let arguments = parse_function_arguments(tokens)?;
             let left_brace = consume(tokens, LeftBrace)?;
             let position = left_brace.position.clone();
-            let body = block(tokens, position)?;
+            let body = function_body(tokens, position)?;
 
             let expr = Lambda {
                 arguments,
@@ -332,21 +641,54 @@ fn assignment(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
     let expr = or(tokens)?;
 
     match tokens.next_if(|n| n.token_type == Equal) {
-        Some(_) => {
+        Some(equals) => {
+            let equals = equals.position.clone();
             let value = expression(tokens)?;
+            let length = value.position.end_position() - expr.position.absolute;
+            let position = Position::new(expr.position.absolute, length);
+
             match expr.expression {
                 Variable(name) => {
-                    let length = value.position.end_position() - expr.position.absolute;
                     let assignment = Expression::Assignment {
                         name,
                         value: Box::new(value),
+                        equals,
+                    };
+
+                    Ok(ExpressionNode::new(assignment, &position))
+                }
+                Index { object, index } => {
+                    let assignment = Expression::IndexAssignment {
+                        object,
+                        index,
+                        value: Box::new(value),
                     };
 
-                    let position = Position::new(expr.position.absolute, length);
+                    Ok(ExpressionNode::new(assignment, &position))
+                }
+                Array(elements) => {
+                    let pattern = elements
+                        .into_iter()
+                        .map(|element| match element.expression {
+                            Variable(name) => Ok(pattern_element(name, element.position)),
+                            _ => Err(ParseError::invalid_assignment_target(
+                                &element.position,
+                                tokens.in_condition,
+                            )),
+                        })
+                        .collect::<ParseResult<Vec<_>>>()?;
+
+                    let assignment = Expression::PatternAssignment {
+                        pattern,
+                        value: Box::new(value),
+                    };
 
                     Ok(ExpressionNode::new(assignment, &position))
                 }
-                _ => Err(ParseError::invalid_assignment_target(&expr.position)),
+                _ => Err(ParseError::invalid_assignment_target(
+                    &expr.position,
+                    tokens.in_condition,
+                )),
             }
         }
         None => Ok(expr),
@@ -385,9 +727,10 @@ fn unary(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
             ..
         }) => {
             let op: UnaryOp = token_type.try_into().unwrap();
-            let position = position.clone();
+            let mut position = position.clone();
 
             let inner = unary(tokens)?;
+            position.union(&inner.position);
             let expression = Unary {
                 inner: Box::new(inner),
                 op,
@@ -401,48 +744,100 @@ fn unary(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
 fn call(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
     let mut expr = primary(tokens)?;
 
-    while let Some(t) = tokens.next_if(|t| t.token_type == LeftParent) {
-        let mut position = t.position.clone();
-
-        let mut arguments: Vec<ExpressionNode> = vec![];
+    loop {
+        if let Some(t) = tokens.next_if(|t| t.token_type == LeftParent) {
+            let mut position = t.position.clone();
+
+            let mut arguments: Vec<ExpressionNode> = vec![];
+
+            if tokens.next_if(|t| t.token_type == RightParent).is_none() {
+                loop {
+                    let argument = argument(tokens)?;
+                    if arguments.len() >= MAX_ARGUMENTS {
+                        return Err(ParseError::too_many_arguments(
+                            arguments.len() + 1,
+                            &argument.position,
+                        ));
+                    }
+                    position.union(&argument.position);
+                    arguments.push(argument);
 
-        if tokens.next_if(|t| t.token_type == RightParent).is_none() {
-            loop {
-                let argument = expression(tokens)?;
-                position.union(&argument.position);
-                arguments.push(argument);
+                    if tokens.peek().is_some_and(|t| t.token_type == RightParent) {
+                        position.union(&tokens.next().unwrap().position);
+                        break;
+                    }
 
-                if tokens.peek().map_or(false, |t| t.token_type == RightParent) {
-                    position.union(&tokens.next().unwrap().position);
-                    break;
+                    position.union(&consume(tokens, Comma)?.position);
                 }
-
-                position.union(&consume(tokens, Comma)?.position);
             }
-        }
 
-        expr = ExpressionNode::raw(
-            Call {
-                callee: Box::new(expr),
-                arguments,
-            },
-            position,
-        );
+            expr = ExpressionNode::raw(
+                Call {
+                    callee: Box::new(expr),
+                    arguments,
+                },
+                position,
+            );
+        } else if let Some(t) = tokens.next_if(|t| t.token_type == LeftBracket) {
+            let opening_position = t.position.clone();
+            let index = expression(tokens)?;
+            let closing = consume_closing_delimiter(tokens, RightBracket, &opening_position)?;
+
+            let mut position = expr.position.clone();
+            position.union(&closing.position);
+
+            expr = ExpressionNode::raw(
+                Index {
+                    object: Box::new(expr),
+                    index: Box::new(index),
+                },
+                position,
+            );
+        } else if tokens.next_if(|t| t.token_type == Dot).is_some() {
+            let name_position = tokens.peek().map(|t| t.position.clone());
+            let name = consume_member_name(tokens)?;
+
+            let mut position = expr.position.clone();
+            position.union(&name_position.unwrap());
+
+            expr = ExpressionNode::raw(
+                Get {
+                    object: Box::new(expr),
+                    name,
+                },
+                position,
+            );
+        } else {
+            break;
+        }
     }
 
     Ok(expr)
 }
 
+/// Parses an argument of a call, which may spread an array into several arguments.
+fn argument(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
+    match tokens.next_if(|t| t.token_type == DotDotDot) {
+        Some(t) => {
+            let mut position = t.position.clone();
+            let inner = expression(tokens)?;
+            position.union(&inner.position);
+
+            Ok(ExpressionNode::raw(Spread(Box::new(inner)), position))
+        }
+        None => expression(tokens),
+    }
+}
+
 fn primary(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
     match tokens.next() {
         Some(token) => {
             let mut position = token.position.clone();
+            if let Some(literal) = token.token_type.literal_value() {
+                return Ok(ExpressionNode::new(Literal(literal), &position));
+            }
+
             let expression = match &token.token_type {
-                False => Ok(Literal(FalseLit)),
-                True => Ok(Literal(TrueLit)),
-                Nil => Ok(Literal(NilLit)),
-                Number(value) => Ok(Literal(NumberLit(*value))),
-                StringToken(value) => Ok(Literal(StringLit(value.clone()))),
                 LeftParent => {
                     let inner = expression(tokens)?;
                     let end_position = consume_closing_delimiter(tokens, RightParent, &position)?
@@ -457,11 +852,36 @@ fn primary(tokens: &mut TokenIter) -> ParseResult<ExpressionNode> {
                     Ok(Grouping(Box::new(inner)))
                 }
                 Identifier(identifier) => Ok(Variable(identifier.to_string())),
+                LeftBracket => {
+                    let mut elements = vec![];
+
+                    let closing = match tokens.next_if(|t| t.token_type == RightBracket) {
+                        Some(closing) => closing.position.clone(),
+                        None => {
+                            loop {
+                                elements.push(expression(tokens)?);
+
+                                if tokens.next_if(|t| t.token_type == Comma).is_none() {
+                                    break;
+                                }
+                            }
+                            consume_closing_delimiter(tokens, RightBracket, &position)?
+                                .position
+                                .clone()
+                        }
+                    };
+                    position.union(&closing);
+
+                    Ok(Array(elements))
+                }
                 _ => Err(ParseError::illegal_token((*token).clone())),
             };
             Ok(ExpressionNode::new(expression?, &position))
         }
-        None => Err(ParseError::unexpected_end_of_stream()),
+        None => Err(ParseError::unexpected_end_of_stream(
+            "an expression",
+            tokens.end_of_input(),
+        )),
     }
 }
 
@@ -518,18 +938,12 @@ where
                 let left = Box::new(expression_node);
                 let right = Box::new(inner_parser(tokens)?);
 
-                let start_pos = left.position.absolute;
-                let length = right.position.absolute + right.position.length - start_pos;
+                let mut position = left.position.clone();
+                position.union(&right.position);
 
                 let expression = expression_creator(left, right, op);
 
-                expression_node = ExpressionNode::new(
-                    expression,
-                    &Position {
-                        absolute: start_pos,
-                        length,
-                    },
-                )
+                expression_node = ExpressionNode::raw(expression, position)
             }
             None => break,
         }
@@ -538,12 +952,21 @@ where
     Ok(expression_node)
 }
 
+/// Notes in a parse error from `result` that it happened while parsing `context`.
+fn error_context<T>(result: ParseResult<T>, context: &str) -> ParseResult<T> {
+    result.map_err(|error| match error {
+        LoxError::ParseError(error) => error.in_context(context),
+        error => error,
+    })
+}
+
 fn consume_identifier(tokens: &mut TokenIter) -> Result<String, LoxError> {
     let matcher = |token: &TokenType| matches!(token, Identifier(_));
     let expected = "Identifier".to_string();
 
+    let end_of_input = tokens.end_of_input();
     let identifier = _consume(tokens, matcher, expected, || {
-        ParseError::unexpected_end_of_stream()
+        ParseError::unexpected_end_of_stream("a name", end_of_input.clone())
     })?;
 
     match &identifier.token_type {
@@ -552,16 +975,27 @@ fn consume_identifier(tokens: &mut TokenIter) -> Result<String, LoxError> {
     }
 }
 
+/// Consumes the name after a `.`, which may be a keyword, as in `true.and(false)`.
+fn consume_member_name(tokens: &mut TokenIter) -> Result<String, LoxError> {
+    match tokens.peek().and_then(|token| token.token_type.keyword()) {
+        Some(keyword) => {
+            tokens.next();
+            Ok(keyword.to_string())
+        }
+        None => consume_identifier(tokens),
+    }
+}
+
 fn consume_closing_delimiter<'a>(
     tokens: &'a mut TokenIter,
     expected: TokenType,
     opening_delimiter_position: &Position,
 ) -> Result<&'a Token, LoxError> {
+    let end_of_input = tokens.end_of_input();
+    let eof_error =
+        || ParseError::unclosed_delimiter(&expected, opening_delimiter_position, &end_of_input);
     let matcher = |t: &TokenType| *t == expected;
     let expected = expected.to_string();
-    let eof_pos = tokens.size;
-    let eof_error =
-        || ParseError::unclosed_delimiter(opening_delimiter_position, &Position::new(eof_pos, 1));
 
     _consume(tokens, matcher, expected, eof_error)
 }
@@ -569,8 +1003,9 @@ fn consume_closing_delimiter<'a>(
 fn consume<'a>(tokens: &'a mut TokenIter, token_type: TokenType) -> Result<&'a Token, LoxError> {
     let matcher = |t: &TokenType| *t == token_type;
     let expected = token_type.to_string();
-    let eof_pos = tokens.size;
-    let eof_error = || ParseError::unexpected_token_raw(Eof, Semicolon, Position::new(eof_pos, 1));
+    let end_of_input = tokens.end_of_input();
+    let eof_error =
+        || ParseError::unexpected_end_of_stream(&format!("`{token_type}`"), end_of_input.clone());
 
     _consume(tokens, matcher, expected, eof_error)
 }
@@ -591,3 +1026,753 @@ where
         None => Err(eof_error()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use miette::Diagnostic;
+
+    use crate::expression::LiteralType::{FalseLit, NumberLit, StringLit};
+    use crate::scanner::Scanner;
+
+    use super::*;
+
+    fn scan(source: &str) -> Vec<Token> {
+        Scanner::new(source.to_string()).scan()
+    }
+
+    fn statement(source: &str) -> Statement {
+        parse_statement(&scan(source)).unwrap()
+    }
+
+    /// Parses `source` as the body of a function and returns its only statement.
+    fn function_body_statement(source: &str) -> Statement {
+        let body = match statement(&format!("fun f() {{ {source} }}")) {
+            Statement::Function { body, .. } => body,
+            _ => panic!("expected a function"),
+        };
+        match Rc::try_unwrap(body) {
+            Ok(Statement::Block(mut statements)) if statements.len() == 1 => statements.remove(0),
+            _ => panic!("expected a body with one statement"),
+        }
+    }
+
+    fn expression(source: &str) -> ExpressionNode {
+        parse_expression(&scan(source)).unwrap()
+    }
+
+    fn number(node: &ExpressionNode) -> f64 {
+        match node.expression {
+            Literal(NumberLit(number)) => number,
+            _ => panic!("expected a number literal"),
+        }
+    }
+
+    fn call_with_arguments(count: usize) -> Vec<Token> {
+        let arguments = (1..=count)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Scanner::new(format!("f({arguments});")).scan()
+    }
+
+    #[test]
+    fn test_call_accepts_255_arguments() {
+        let tokens = call_with_arguments(255);
+        let statements = parse(&tokens).unwrap();
+
+        match &statements[..] {
+            [Statement::Expression(ExpressionNode {
+                expression: Call { arguments, .. },
+                position,
+            })] => {
+                assert_eq!(arguments.len(), 255);
+                // A call's position spans its argument list.
+                let closing = &tokens[2 * 255 + 1];
+                assert_eq!(closing.token_type, RightParent);
+                assert_eq!(position.absolute, 1);
+                assert_eq!(position.end_position(), closing.position.end_position());
+            }
+            _ => panic!("expected a single call expression"),
+        }
+    }
+
+    #[test]
+    fn test_call_rejects_256_arguments() {
+        let tokens = call_with_arguments(256);
+        let argument_256 = &tokens[2 * 256];
+        assert_eq!(argument_256.token_type, Number(256.0));
+
+        match parse(&tokens) {
+            Err(LoxError::ParseError(ParseError::TooManyArguments {
+                count, position, ..
+            })) => {
+                assert_eq!(count, 256);
+                assert_eq!(position, argument_256.position);
+            }
+            _ => panic!("expected a too many arguments error"),
+        }
+    }
+
+    #[test]
+    fn test_rest_parameter_must_be_last() {
+        let tokens = Scanner::new("fun f(a, ...rest) {}".to_string()).scan();
+        match &parse(&tokens).unwrap()[..] {
+            [Statement::Function { parameters, .. }] => {
+                assert_eq!(parameters.names, vec!["a".to_string()]);
+                assert_eq!(parameters.rest, Some("rest".to_string()));
+            }
+            _ => panic!("expected a function declaration"),
+        }
+
+        let tokens = Scanner::new("fun f(...rest, a) {}".to_string()).scan();
+        match parse(&tokens) {
+            Err(LoxError::ParseError(ParseError::MisplacedRestParameter { position, .. })) => {
+                assert_eq!(position, Position::new(6, 7));
+            }
+            _ => panic!("expected a misplaced rest parameter error"),
+        }
+    }
+
+    fn error_context_of(source: &str) -> Option<String> {
+        match parse(&scan(source)) {
+            Err(LoxError::ParseError(error)) => error.help().map(|help| help.to_string()),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_errors_name_the_statement_being_parsed() {
+        assert_eq!(
+            error_context_of("fun f(a { return a; }"),
+            Some("while parsing function declaration".to_string())
+        );
+        assert_eq!(
+            error_context_of("if 1 > 0 print 1;"),
+            Some("while parsing if condition".to_string())
+        );
+        assert_eq!(
+            error_context_of("if (true) print 1; else print ;"),
+            Some("while parsing else branch".to_string())
+        );
+        assert_eq!(
+            error_context_of("while (true) print ;"),
+            Some("while parsing while loop".to_string())
+        );
+        assert_eq!(error_context_of("print ;"), None);
+    }
+
+    #[test]
+    fn test_errors_keep_the_innermost_context() {
+        assert_eq!(
+            error_context_of("while (true) { fun f(a b) {} }"),
+            Some("while parsing function declaration".to_string())
+        );
+    }
+
+    #[test]
+    fn test_binary_position_covers_both_operands() {
+        let binary = expression("1 + 2 + 3");
+        assert_eq!(binary.position, Position::new(0, 9));
+        match binary.expression {
+            Binary { left, right, .. } => {
+                assert_eq!(left.position, Position::new(0, 5));
+                assert_eq!(right.position, Position::new(8, 1));
+            }
+            _ => panic!("expected a binary expression"),
+        }
+
+        assert_eq!(expression("a or b and c").position, Position::new(0, 12));
+    }
+
+    #[test]
+    fn test_binary_position_covers_grouping() {
+        let binary = expression("(1 + 2) + 3");
+        assert_eq!(binary.position, Position::new(0, 11));
+        match binary.expression {
+            Binary { left, .. } => assert_eq!(left.position, Position::new(0, 7)),
+            _ => panic!("expected a binary expression"),
+        }
+
+        assert_eq!(expression("1 + (2 + 3)").position, Position::new(0, 11));
+    }
+
+    #[test]
+    fn test_unary_and_array_positions_cover_their_operands() {
+        assert_eq!(expression("-(1 + 2)").position, Position::new(0, 8));
+        assert_eq!(expression("1 + -2").position, Position::new(0, 6));
+        assert_eq!(expression("[1, 2] + []").position, Position::new(0, 11));
+    }
+
+    #[test]
+    fn test_parse_with_recovery_skips_malformed_statements() {
+        let source = "var a = 1; var = 2; print a; 1 +; var b = 3;";
+        let tokens = Scanner::new(source.to_string()).scan();
+        let result = parse_with_recovery(&tokens);
+
+        assert!(matches!(
+            result.statements.as_slice(),
+            [
+                Statement::Var { .. },
+                Statement::Print(_),
+                Statement::Var { .. }
+            ]
+        ));
+        assert!(matches!(
+            result.errors.as_slice(),
+            [
+                LoxError::ParseError(ParseError::UnexpectedToken { .. }),
+                LoxError::ParseError(ParseError::IllegalToken { .. })
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_parse_with_recovery_stops_at_statement_keywords() {
+        let tokens = Scanner::new("var x = 1 + print x; while (true) break;".to_string()).scan();
+        let result = parse_with_recovery(&tokens);
+
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            result.statements.as_slice(),
+            [Statement::While { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_break_and_continue_outside_loop() {
+        for source in ["break;", "continue;", "loop { fun f() { break; } break; }"] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            assert!(
+                matches!(
+                    parse(&tokens),
+                    Err(LoxError::ParseError(ParseError::OutsideLoop { .. }))
+                ),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_return_outside_function() {
+        for source in [
+            "return 1;",
+            "return;",
+            "{ return; }",
+            "while (true) return;",
+        ] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            assert!(
+                matches!(
+                    parse(&tokens),
+                    Err(LoxError::ParseError(ParseError::OutsideFunction { .. }))
+                ),
+                "{source}"
+            );
+        }
+
+        for source in ["fun f() { return 1; }", "var f = fun () { { return; } };"] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            assert!(parse(&tokens).is_ok(), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_conditions_require_parentheses() {
+        for source in [
+            "if (1 > 0) print 1;",
+            "while (false) print 1;",
+            "for (var i = 0; i < 1; i = i + 1) print i;",
+            "for (;;) break;",
+        ] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            assert!(parse(&tokens).is_ok(), "{source}");
+        }
+
+        for source in [
+            "if 1 > 0 print 1;",
+            "while false print 1;",
+            "for var i = 0; i < 1; i = i + 1; print i;",
+        ] {
+            let tokens = Scanner::new(source.to_string()).scan();
+            assert!(
+                matches!(
+                    parse(&tokens),
+                    Err(LoxError::ParseError(ParseError::UnexpectedToken { .. }))
+                ),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_var_without_initializer() {
+        match statement("var a;") {
+            Statement::Var { name, initializer } => {
+                assert_eq!(name, "a");
+                assert!(initializer.is_none());
+            }
+            _ => panic!("expected a var declaration"),
+        }
+    }
+
+    #[test]
+    fn test_var_with_initializer() {
+        match statement("var a = 1;") {
+            Statement::Var {
+                name,
+                initializer: Some(initializer),
+            } => {
+                assert_eq!(name, "a");
+                assert_eq!(number(&initializer), 1.0);
+            }
+            _ => panic!("expected a var declaration"),
+        }
+    }
+
+    #[test]
+    fn test_var_pattern() {
+        match statement("var [a, _] = pair;") {
+            Statement::VarPattern {
+                pattern,
+                initializer,
+            } => {
+                let names = pattern.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+                assert_eq!(names, vec![Some("a".to_string()), None]);
+                assert!(matches!(initializer.expression, Variable(ref name) if name == "pair"));
+            }
+            _ => panic!("expected a var pattern"),
+        }
+    }
+
+    #[test]
+    fn test_print() {
+        match statement("print 1;") {
+            Statement::Print(expression) => assert_eq!(number(&expression), 1.0),
+            _ => panic!("expected a print statement"),
+        }
+    }
+
+    #[test]
+    fn test_expression_statement() {
+        match statement("f();") {
+            Statement::Expression(expression) => {
+                assert!(matches!(expression.expression, Call { .. }))
+            }
+            _ => panic!("expected an expression statement"),
+        }
+    }
+
+    #[test]
+    fn test_block() {
+        match statement("{ var a = 1; print a; }") {
+            Statement::Block(statements) => assert!(matches!(
+                statements.as_slice(),
+                [Statement::Var { .. }, Statement::Print(_)]
+            )),
+            _ => panic!("expected a block"),
+        }
+    }
+
+    #[test]
+    fn test_if_without_else() {
+        match statement("if (a) print 1;") {
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                assert!(matches!(*then_branch, Statement::Print(_)));
+                assert!(else_branch.is_none());
+            }
+            _ => panic!("expected an if statement"),
+        }
+    }
+
+    #[test]
+    fn test_if_with_else() {
+        match statement("if (a) print 1; else { print 2; }") {
+            Statement::If {
+                else_branch: Some(else_branch),
+                ..
+            } => assert!(matches!(*else_branch, Statement::Block(_))),
+            _ => panic!("expected an if statement with else"),
+        }
+    }
+
+    #[test]
+    fn test_while() {
+        match statement("while (a < 1) a = a + 1;") {
+            Statement::While {
+                condition,
+                body,
+                increment,
+                desugared_for,
+            } => {
+                assert!(matches!(condition.expression, Binary { .. }));
+                assert!(matches!(*body, Statement::Expression(_)));
+                assert!(increment.is_none());
+                assert!(!desugared_for);
+            }
+            _ => panic!("expected a while loop"),
+        }
+    }
+
+    #[test]
+    fn test_for_is_desugared_into_while() {
+        match statement("for (var i = 0; i < 3; i = i + 1) print i;") {
+            Statement::Block(statements) => match statements.as_slice() {
+                [Statement::Var { .. }, Statement::While {
+                    increment: Some(_),
+                    desugared_for: true,
+                    ..
+                }] => {}
+                _ => panic!("expected an initializer and a loop"),
+            },
+            _ => panic!("expected a block"),
+        }
+    }
+
+    #[test]
+    fn test_for_with_empty_clauses() {
+        match statement("for (;;) {}") {
+            Statement::While {
+                condition,
+                increment: None,
+                desugared_for: true,
+                ..
+            } => {
+                assert!(matches!(condition.expression, Literal(TrueLit)));
+                assert_eq!(condition.position, Position::new(6, 1));
+            }
+            _ => panic!("expected a loop without an initializer"),
+        }
+
+        match statement("for (;false;) {}") {
+            Statement::While {
+                condition,
+                increment: None,
+                ..
+            } => assert!(matches!(condition.expression, Literal(FalseLit))),
+            _ => panic!("expected a loop without an initializer"),
+        }
+    }
+
+    #[test]
+    fn test_loop() {
+        match statement("loop { break; }") {
+            Statement::Loop { body, position } => {
+                assert_eq!(position, Position::new(0, 4));
+                match *body {
+                    Statement::Block(statements) => {
+                        assert!(matches!(statements.as_slice(), [Statement::Break]))
+                    }
+                    _ => panic!("expected a block"),
+                }
+            }
+            _ => panic!("expected a loop"),
+        }
+    }
+
+    #[test]
+    fn test_continue_in_loop() {
+        match statement("while (true) continue;") {
+            Statement::While { body, .. } => assert!(matches!(*body, Statement::Continue)),
+            _ => panic!("expected a while loop"),
+        }
+    }
+
+    #[test]
+    fn test_function() {
+        match statement("fun add(a, b) { return a + b; }") {
+            Statement::Function {
+                name,
+                parameters,
+                body,
+            } => {
+                assert_eq!(name, "add");
+                assert_eq!(parameters.names, vec!["a".to_string(), "b".to_string()]);
+                assert_eq!(parameters.rest, None);
+                assert!(matches!(body.as_ref(), Statement::Block(_)));
+            }
+            _ => panic!("expected a function declaration"),
+        }
+    }
+
+    /// Parses `class Name { ... }` into its methods, the way the class parser will.
+    fn class_methods(source: &str) -> ParseResult<Vec<Statement>> {
+        let tokens = scan(source);
+        let mut tokens = TokenIter::new(&tokens);
+        consume(&mut tokens, Class)?;
+        consume_identifier(&mut tokens)?;
+        consume(&mut tokens, LeftBrace)?;
+
+        let mut methods = Vec::new();
+        while tokens
+            .next_if(|token| token.token_type == RightBrace)
+            .is_none()
+        {
+            methods.push(method_declaration(&mut tokens)?);
+        }
+        Ok(methods)
+    }
+
+    fn method_signature(method: &Statement) -> (&str, usize) {
+        match method {
+            Statement::Function {
+                name, parameters, ..
+            } => (name, parameters.names.len()),
+            _ => panic!("expected a method"),
+        }
+    }
+
+    #[test]
+    fn test_class_without_methods() {
+        assert!(class_methods("class Empty {}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_class_with_one_method() {
+        let methods = class_methods("class Greeter { greet() { print \"hi\"; } }").unwrap();
+
+        assert_eq!(methods.len(), 1);
+        assert_eq!(method_signature(&methods[0]), ("greet", 0));
+        match &methods[0] {
+            Statement::Function { body, .. } => {
+                assert!(
+                    matches!(body.as_ref(), Statement::Block(statements) if statements.len() == 1)
+                )
+            }
+            _ => panic!("expected a method"),
+        }
+    }
+
+    #[test]
+    fn test_class_with_several_methods() {
+        let source = "class Point {
+            init(x, y) { var sum = x + y; }
+            norm() { return 0; }
+            scale(factor) { return factor; }
+            sum(...values) { return values; }
+        }";
+        let methods = class_methods(source).unwrap();
+
+        let signatures = methods.iter().map(method_signature).collect::<Vec<_>>();
+        assert_eq!(
+            signatures,
+            vec![("init", 2), ("norm", 0), ("scale", 1), ("sum", 0)]
+        );
+        assert!(matches!(
+            &methods[3],
+            Statement::Function { parameters, .. } if parameters.rest == Some("values".to_string())
+        ));
+    }
+
+    #[test]
+    fn test_methods_have_no_fun_keyword() {
+        match class_methods("class A { fun f() {} }") {
+            Err(LoxError::ParseError(error)) => assert_eq!(
+                error.help().map(|help| help.to_string()).as_deref(),
+                Some(
+                    "`fun` is a keyword and can't be used as a name (while parsing method \
+                    declaration)"
+                )
+            ),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_return_with_value() {
+        match function_body_statement("return 1;") {
+            Statement::Return(Some(value)) => assert_eq!(number(&value), 1.0),
+            _ => panic!("expected a return statement"),
+        }
+    }
+
+    #[test]
+    fn test_return_without_value() {
+        assert!(matches!(
+            function_body_statement("return;"),
+            Statement::Return(None)
+        ));
+    }
+
+    #[test]
+    fn test_assert_without_message() {
+        match statement("assert a;") {
+            Statement::Assert { condition, message } => {
+                assert!(matches!(condition.expression, Variable(_)));
+                assert!(message.is_none());
+            }
+            _ => panic!("expected an assert statement"),
+        }
+    }
+
+    #[test]
+    fn test_assert_with_message() {
+        match statement("assert a, \"a is false\";") {
+            Statement::Assert {
+                message: Some(message),
+                ..
+            } => assert!(matches!(message.expression, Literal(StringLit(_)))),
+            _ => panic!("expected an assert statement with a message"),
+        }
+    }
+
+    #[test]
+    fn test_statement_requires_semicolon() {
+        assert!(parse_statement(&scan("print 1")).is_err());
+    }
+
+    #[test]
+    fn test_statement_rejects_trailing_tokens() {
+        match parse_statement(&scan("print 1; print 2;")) {
+            Err(LoxError::ParseError(ParseError::IllegalToken { position, .. })) => {
+                assert_eq!(position, Position::new(9, 5));
+            }
+            _ => panic!("expected an illegal token error"),
+        }
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(matches!(
+            parse_statement(&[]),
+            Err(LoxError::ParseError(
+                ParseError::UnexpectedEndOfTokenStream { .. }
+            ))
+        ));
+        assert!(matches!(
+            parse_expression(&[]),
+            Err(LoxError::ParseError(
+                ParseError::UnexpectedEndOfTokenStream { .. }
+            ))
+        ));
+        assert!(parse(&[]).unwrap().is_empty());
+        let parsed = parse_with_recovery(&[]);
+        assert!(parsed.statements.is_empty() && parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_expression_rejects_semicolon() {
+        assert!(matches!(
+            parse_expression(&scan("1 + 2;")),
+            Err(LoxError::ParseError(ParseError::IllegalToken { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_binary_precedence() {
+        match expression("1 + 2 * 3").expression {
+            Binary { left, right, op } => {
+                assert!(matches!(op, BinaryOp::Add));
+                assert_eq!(number(&left), 1.0);
+                assert!(matches!(
+                    right.expression,
+                    Binary {
+                        op: BinaryOp::Multiply,
+                        ..
+                    }
+                ));
+            }
+            _ => panic!("expected a binary expression"),
+        }
+    }
+
+    #[test]
+    fn test_logical() {
+        match expression("a or b and c").expression {
+            Logical { right, op, .. } => {
+                assert!(matches!(op, LogicalOp::Or));
+                assert!(matches!(
+                    right.expression,
+                    Logical {
+                        op: LogicalOp::And,
+                        ..
+                    }
+                ));
+            }
+            _ => panic!("expected a logical expression"),
+        }
+    }
+
+    #[test]
+    fn test_unary() {
+        match expression("!!a").expression {
+            Unary { inner, op } => {
+                assert!(matches!(op, UnaryOp::Not));
+                assert!(matches!(inner.expression, Unary { .. }));
+            }
+            _ => panic!("expected a unary expression"),
+        }
+    }
+
+    #[test]
+    fn test_grouping() {
+        match expression("(1)").expression {
+            Grouping(inner) => assert_eq!(number(&inner), 1.0),
+            _ => panic!("expected a grouping"),
+        }
+    }
+
+    #[test]
+    fn test_assignment() {
+        match expression("a = b = 1").expression {
+            Expression::Assignment { name, value, .. } => {
+                assert_eq!(name, "a");
+                assert!(matches!(value.expression, Expression::Assignment { .. }));
+            }
+            _ => panic!("expected an assignment"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_assignment_target() {
+        assert!(matches!(
+            parse_expression(&scan("1 = 2")),
+            Err(LoxError::ParseError(
+                ParseError::InvalidAssignmentTarget { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_index_and_get() {
+        match expression("a.b[0]").expression {
+            Index { object, index } => {
+                assert_eq!(number(&index), 0.0);
+                assert!(matches!(object.expression, Get { ref name, .. } if name == "b"));
+            }
+            _ => panic!("expected an index expression"),
+        }
+    }
+
+    #[test]
+    fn test_call_with_spread() {
+        match expression("f([1], ...rest)").expression {
+            Call { arguments, .. } => assert!(matches!(
+                arguments.as_slice(),
+                [
+                    _,
+                    ExpressionNode {
+                        expression: Spread(_),
+                        ..
+                    }
+                ]
+            )),
+            _ => panic!("expected an array"),
+        }
+    }
+
+    #[test]
+    fn test_lambda() {
+        match expression("fun (x, ...rest) { return x; }").expression {
+            Lambda { arguments, body } => {
+                assert_eq!(arguments.names, vec!["x".to_string()]);
+                assert_eq!(arguments.rest, Some("rest".to_string()));
+                assert!(matches!(body.as_ref(), Statement::Block(_)));
+            }
+            _ => panic!("expected a lambda"),
+        }
+    }
+}
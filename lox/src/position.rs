@@ -1,3 +1,5 @@
+use std::iter;
+
 use miette::SourceSpan;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -28,3 +30,66 @@ impl From<Position> for SourceSpan {
         (val.absolute, val.length).into()
     }
 }
+
+/// Turns the byte offsets of positions into the lines and columns of a source, both counted
+/// from 1. Columns count characters, not bytes. The line starts are found once, so each
+/// lookup is a binary search over them plus a count of the characters before the offset in
+/// its line.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    /// The offset of the first byte of every line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn from_source(source: &'a str) -> LineIndex<'a> {
+        let line_starts = iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        LineIndex {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The line and column of `offset`. An offset at or past the end of the source is
+    /// placed just after its last character.
+    pub fn line_col_of(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let column = match self.source.get(start..offset) {
+            Some(text) => text.chars().count(),
+            None => offset - start,
+        };
+
+        (line + 1, column + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column() {
+        let index = LineIndex::from_source("var a;\nprint a;\n\nx");
+
+        assert_eq!(index.line_col_of(0), (1, 1));
+        assert_eq!(index.line_col_of(4), (1, 5));
+        assert_eq!(index.line_col_of(6), (1, 7));
+        assert_eq!(index.line_col_of(7), (2, 1));
+        assert_eq!(index.line_col_of(13), (2, 7));
+        assert_eq!(index.line_col_of(16), (3, 1));
+        assert_eq!(index.line_col_of(17), (4, 1));
+        assert_eq!(index.line_col_of(100), (4, 2));
+    }
+
+    #[test]
+    fn test_columns_count_characters() {
+        let index = LineIndex::from_source("\"äö\" x");
+
+        assert_eq!(index.line_col_of(7), (1, 6));
+        assert_eq!(LineIndex::from_source("").line_col_of(0), (1, 1));
+    }
+}
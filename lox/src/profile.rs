@@ -0,0 +1,199 @@
+//! `lox --profile`: how often every function is called and how long the calls take.
+//!
+//! Lox functions are told apart by their declaration, so all closures created from one
+//! declaration share an entry. Natives and built-in methods are told apart by their native
+//! function.
+//!
+//! The total time of a function includes the time of the functions it calls. For recursive
+//! functions only the outermost call counts towards the total, so the total never exceeds
+//! the time the program ran. The self time excludes the time spent in other calls.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Identifies a function across calls, see the module documentation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct FunctionKey(pub(crate) usize);
+
+/// The calls and times of one function.
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    pub total: Duration,
+    pub self_time: Duration,
+    /// The number of calls of the function in progress.
+    active: usize,
+}
+
+/// A call in progress.
+struct Frame {
+    key: FunctionKey,
+    start: Instant,
+    /// The time spent in calls made from this one.
+    children: Duration,
+}
+
+#[derive(Default)]
+pub struct Profile {
+    functions: HashMap<FunctionKey, FunctionProfile>,
+    /// The calls in progress, innermost last.
+    stack: Vec<Frame>,
+}
+
+impl Profile {
+    pub(crate) fn enter(&mut self, key: FunctionKey, name: &str) {
+        let function = self
+            .functions
+            .entry(key)
+            .or_insert_with(|| FunctionProfile {
+                name: name.to_string(),
+                calls: 0,
+                total: Duration::ZERO,
+                self_time: Duration::ZERO,
+                active: 0,
+            });
+        function.calls += 1;
+        function.active += 1;
+
+        self.stack.push(Frame {
+            key,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    /// Ends the innermost call in progress.
+    pub(crate) fn exit(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        let elapsed = frame.start.elapsed();
+
+        let function = self
+            .functions
+            .get_mut(&frame.key)
+            .expect("entered functions have a profile");
+        function.active -= 1;
+        function.self_time += elapsed.saturating_sub(frame.children);
+        if function.active == 0 {
+            function.total += elapsed;
+        }
+
+        if let Some(caller) = self.stack.last_mut() {
+            caller.children += elapsed;
+        }
+    }
+
+    /// The profiled functions, the one with the most self time first.
+    pub fn functions(&self) -> Vec<&FunctionProfile> {
+        let mut functions = self.functions.values().collect::<Vec<_>>();
+        functions.sort_by(|a, b| {
+            b.self_time
+                .cmp(&a.self_time)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        functions
+    }
+
+    /// The functions as a table with a header, one line per function, times in
+    /// milliseconds.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "{:<24} {:>10} {:>12} {:>12}\n",
+            "function", "calls", "total ms", "self ms"
+        );
+        for function in self.functions() {
+            let name = match function.name.as_str() {
+                "" => "<lambda>",
+                name => name,
+            };
+            report.push_str(&format!(
+                "{name:<24} {:>10} {:>12.3} {:>12.3}\n",
+                function.calls,
+                function.total.as_secs_f64() * 1000.0,
+                function.self_time.as_secs_f64() * 1000.0,
+            ));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::Interpreter;
+    use crate::parser::parse;
+    use crate::scanner::Scanner;
+
+    use super::*;
+
+    fn profile(source: &str) -> Vec<(String, u64)> {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let statements = parse(&tokens).ok().unwrap();
+        let mut interpreter = Interpreter::new().with_profiling(true);
+        interpreter.evaluate(&statements).unwrap();
+
+        let profile = interpreter.profile().unwrap();
+        for function in profile.functions() {
+            assert!(function.total >= function.self_time, "{}", function.name);
+            assert_eq!(function.active, 0, "{}", function.name);
+        }
+        let mut calls = profile
+            .functions()
+            .into_iter()
+            .map(|function| (function.name.clone(), function.calls))
+            .collect::<Vec<_>>();
+        calls.sort();
+        calls
+    }
+
+    #[test]
+    fn test_counts_recursive_calls() {
+        let source = "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } fib(20);";
+        assert_eq!(profile(source), vec![("fib".to_string(), 21891)]);
+    }
+
+    #[test]
+    fn test_counts_natives_methods_and_lambdas() {
+        let source = "
+            var list = List();
+            var add = fun (x) { list.push(x); };
+            for (var i = 0; i < 3; i = i + 1) add(ord(\"a\"));";
+        assert_eq!(
+            profile(source),
+            vec![
+                ("".to_string(), 3),
+                ("List".to_string(), 1),
+                ("ord".to_string(), 3),
+                ("push".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closures_of_one_declaration_share_an_entry() {
+        let source = "
+            fun adder(n) { fun add(x) { return x + n; } return add; }
+            adder(1)(1);
+            adder(2)(2);";
+        assert_eq!(
+            profile(source),
+            vec![("add".to_string(), 2), ("adder".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_report_is_a_table() {
+        let mut profile = Profile::default();
+        profile.enter(FunctionKey(1), "outer");
+        profile.enter(FunctionKey(2), "");
+        profile.exit();
+        profile.exit();
+
+        let report = profile.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("function"));
+        assert!(lines.iter().any(|line| line.starts_with("<lambda>")));
+        assert!(lines.iter().any(|line| line.starts_with("outer")));
+    }
+}
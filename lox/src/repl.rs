@@ -0,0 +1,892 @@
+//! Line editing support for the REPL: meta-commands, syntax highlighting and deciding when
+//! the buffer is ready to run.
+//!
+//! A line starting with `:` is a meta-command for the REPL itself, see `run_command`. Only a
+//! `:` in the very first column counts, so Lox source is never mistaken for a command.
+//!
+//! Enter submits the buffer only if it is complete. While a delimiter, a string or an
+//! expression is still open, Enter inserts a newline instead, so a function can be typed or
+//! pasted over several lines. Alt-Enter always inserts a newline. The continuation prompt
+//! shows how many delimiters are open, e.g. `...:3{2}> `.
+//!
+//! In terminals that support bracketed paste, a paste lands in the buffer as a whole and is
+//! submitted by the next Enter like typed input. Elsewhere every pasted newline is an Enter.
+//! Either way, see `submitted_input` for what is done with a submitted buffer.
+//!
+//! While typing, the rest of the latest history entry starting with the buffer is suggested
+//! in gray, see `Theme::hinter`. Right arrow accepts the suggestion.
+//!
+//! The buffer is highlighted from the tokens of the error-tolerant scanner, so incomplete
+//! input is highlighted as well. Scan errors, like an unterminated string, are highlighted
+//! as errors.
+
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use nu_ansi_term::{Color, Style};
+use reedline::{DefaultHinter, Highlighter, StyledText, ValidationResult, Validator};
+
+use crate::error::{LoxError, ParseError, ScanError};
+use crate::evaluation::Interpreter;
+use crate::parser;
+use crate::scanner::Scanner;
+use crate::session;
+use crate::statement::Statement;
+use crate::token::{Token, TokenType};
+use crate::value::Value;
+
+const HELP: &str = "\
+Commands:
+  :help            show this help
+  :quit            leave the REPL, like Ctrl-D
+  :reset           forget everything defined so far
+  :stats           show the interpreter's counters
+  :env [all]       list the globals, with the prelude's natives if all is given
+  :save <file>     save the globals holding plain data to a file
+  :restore <file>  define the globals saved in a file, replacing existing ones
+  :trace on|off    print every statement to stderr before running it
+  :type <expr>     show the type of an expression";
+
+/// What the REPL does after a meta-command.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ReplAction {
+    Continue,
+    Quit,
+}
+
+/// Runs `line` if it is a meta-command, writing its output to the interpreter's output.
+/// Returns `None` if `line` is not a command and should be run as Lox source.
+pub(crate) fn run_command(line: &str, interpreter: &mut Interpreter) -> Option<ReplAction> {
+    let command = line.strip_prefix(':')?.trim_end();
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (command, ""),
+    };
+
+    match name {
+        "help" => interpreter.print(HELP),
+        "quit" => return Some(ReplAction::Quit),
+        "reset" => {
+            interpreter.reset();
+            interpreter.print("Cleared all definitions.");
+        }
+        "stats" => interpreter.print(&interpreter.stats().to_string()),
+        "env" => match argument {
+            "" => print_globals(interpreter, false),
+            "all" => print_globals(interpreter, true),
+            _ => interpreter.print("Usage: :env [all]"),
+        },
+        "save" => match argument {
+            "" => interpreter.print("Usage: :save <file>"),
+            path => save_session(path, interpreter),
+        },
+        "restore" => match argument {
+            "" => interpreter.print("Usage: :restore <file>"),
+            path => restore_session(path, interpreter),
+        },
+        "trace" => match argument {
+            "on" => interpreter.set_trace(Some(Box::new(io::stderr()))),
+            "off" => interpreter.set_trace(None),
+            _ => {
+                let state = if interpreter.is_tracing() {
+                    "on"
+                } else {
+                    "off"
+                };
+                interpreter.print(&format!("Tracing is {state}. Usage: :trace on|off"));
+            }
+        },
+        "type" => print_type(argument, interpreter),
+        _ => interpreter.print(&format!("Unknown command :{name}\n{HELP}")),
+    }
+
+    Some(ReplAction::Continue)
+}
+
+fn save_session(path: &str, interpreter: &mut Interpreter) {
+    match session::save(interpreter, Path::new(path)) {
+        Ok(report) => {
+            interpreter.print(&format!("Saved {} globals to {path}.", report.saved));
+            if !report.skipped.is_empty() {
+                interpreter.print(&format!(
+                    "Not saved, since they hold functions, namespaces, cycles or no value: {}",
+                    report.skipped.join(", ")
+                ));
+            }
+        }
+        Err(error) => interpreter.print(&format!("Could not save {path}: {error}")),
+    }
+}
+
+fn restore_session(path: &str, interpreter: &mut Interpreter) {
+    match session::restore(interpreter, Path::new(path)) {
+        Ok(names) => interpreter.print(&format!(
+            "Restored {} globals from {path}: {}",
+            names.len(),
+            names.join(", ")
+        )),
+        Err(error) => interpreter.print(&format!("Could not restore {path}: {error}")),
+    }
+}
+
+/// The longest value preview `:env` prints, in characters.
+const PREVIEW_LENGTH: usize = 40;
+
+/// Prints one line per global with its type and a preview of its value. The natives of the
+/// prelude are left out unless `all` is set.
+fn print_globals(interpreter: &mut Interpreter, all: bool) {
+    let prelude = interpreter.prelude().names();
+    let lines = interpreter
+        .globals()
+        .into_iter()
+        .filter(|variable| all || !prelude.contains(&variable.name))
+        .map(|variable| match variable.value {
+            Some(value) => format!(
+                "{}: {} = {}",
+                variable.name,
+                value.type_name(),
+                preview(&value)
+            ),
+            None => format!("{}: uninitialized", variable.name),
+        })
+        .collect::<Vec<_>>();
+
+    match lines.is_empty() {
+        true => interpreter.print("No globals defined."),
+        false => interpreter.print(&lines.join("\n")),
+    }
+}
+
+/// The value as `print` shows it, with strings in quotes, cut to `PREVIEW_LENGTH`
+/// characters.
+fn preview(value: &Value) -> String {
+    let text = match value {
+        Value::Str(string) => format!("\"{string}\""),
+        value => value.to_string(),
+    };
+
+    match text.char_indices().nth(PREVIEW_LENGTH - 1) {
+        Some((end, _)) if text.chars().count() > PREVIEW_LENGTH => format!("{}…", &text[..end]),
+        _ => text,
+    }
+}
+
+/// Evaluates the expression `source` and prints the name of its type.
+fn print_type(source: &str, interpreter: &mut Interpreter) {
+    let source = format!("{source};");
+    let tokens = Scanner::new(source.clone()).scan();
+
+    let result = match parser::parse(&tokens) {
+        Ok(statements) if matches!(statements[..], [Statement::Expression(_)]) => {
+            interpreter.evaluate(&statements)
+        }
+        Ok(_) => {
+            interpreter.print("Usage: :type <expr>");
+            return;
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(values) => interpreter.print(values[0].type_name()),
+        Err(error) => {
+            let report = miette::Report::new(error).with_source_code(source);
+            interpreter.print(&format!("{report:?}"));
+        }
+    }
+}
+
+/// The styles of the highlighted REPL input and of history suggestions. All colors are
+/// chosen here, so highlighting can be switched off by using `Theme::plain`.
+pub(crate) struct Theme {
+    keyword: Style,
+    number: Style,
+    string: Style,
+    comment: Style,
+    error: Style,
+    hint: Style,
+}
+
+impl Theme {
+    pub(crate) fn colored() -> Theme {
+        Theme {
+            keyword: Style::new().fg(Color::Purple).bold(),
+            number: Style::new().fg(Color::Cyan),
+            string: Style::new().fg(Color::Green),
+            comment: Style::new().fg(Color::DarkGray).italic(),
+            error: Style::new().fg(Color::White).on(Color::Red),
+            hint: Style::new().fg(Color::DarkGray),
+        }
+    }
+
+    /// A theme without any styling.
+    pub(crate) fn plain() -> Theme {
+        Theme {
+            keyword: Style::new(),
+            number: Style::new(),
+            string: Style::new(),
+            comment: Style::new(),
+            error: Style::new(),
+            hint: Style::new(),
+        }
+    }
+
+    /// Suggests the rest of the latest history entry that starts with the buffer, in the
+    /// hint style.
+    pub(crate) fn hinter(&self) -> DefaultHinter {
+        DefaultHinter::default().with_style(self.hint)
+    }
+
+    fn token_style(&self, token_type: &TokenType) -> Style {
+        match token_type {
+            TokenType::Number(_) => self.number,
+            TokenType::StringToken(_) => self.string,
+            TokenType::And
+            | TokenType::Class
+            | TokenType::Else
+            | TokenType::False
+            | TokenType::Fun
+            | TokenType::For
+            | TokenType::If
+            | TokenType::Nil
+            | TokenType::Or
+            | TokenType::Print
+            | TokenType::Return
+            | TokenType::Super
+            | TokenType::This
+            | TokenType::True
+            | TokenType::Var
+            | TokenType::While
+            | TokenType::Loop
+            | TokenType::Break
+            | TokenType::Continue
+            | TokenType::Assert => self.keyword,
+            _ => Style::new(),
+        }
+    }
+}
+
+pub(crate) struct ReplHighlighter {
+    theme: Theme,
+}
+
+impl ReplHighlighter {
+    pub(crate) fn new(theme: Theme) -> ReplHighlighter {
+        ReplHighlighter { theme }
+    }
+
+    /// Adds the text between two tokens, which only contains whitespace and comments.
+    fn push_gap(&self, styled: &mut StyledText, gap: &str) {
+        let mut rest = gap;
+        while let Some(start) = rest.find("//") {
+            let end = rest[start..]
+                .find('\n')
+                .map_or(rest.len(), |end| start + end);
+            push(styled, Style::new(), &rest[..start]);
+            push(styled, self.theme.comment, &rest[start..end]);
+            rest = &rest[end..];
+        }
+        push(styled, Style::new(), rest);
+    }
+}
+
+impl Highlighter for ReplHighlighter {
+    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+        let (tokens, errors) = Scanner::new(line.to_string()).scan_with_errors();
+
+        let mut regions = tokens
+            .iter()
+            .map(|token| {
+                let style = self.theme.token_style(&token.token_type);
+                (token.position.clone(), style)
+            })
+            .collect::<Vec<_>>();
+
+        for error in errors {
+            match error {
+                // The scanner still returns the string up to the end of the line as a token.
+                LoxError::ScanError(ScanError::UnterminatedString { position, .. }) => regions
+                    .iter_mut()
+                    .filter(|(region, _)| region.absolute == position.absolute)
+                    .for_each(|(_, style)| *style = self.theme.error),
+                LoxError::ScanError(
+                    ScanError::UnrecognizedCharacter { position, .. }
+                    | ScanError::InvalidNumber { position, .. },
+                ) => regions.push((position, self.theme.error)),
+                _ => {}
+            }
+        }
+        regions.sort_by_key(|(position, _)| position.absolute);
+
+        let mut styled = StyledText::new();
+        let mut offset = 0;
+        for (position, style) in regions {
+            let start = position.absolute.clamp(offset, line.len());
+            let end = position.end_position().clamp(start, line.len());
+
+            self.push_gap(&mut styled, line.get(offset..start).unwrap_or_default());
+            push(&mut styled, style, line.get(start..end).unwrap_or_default());
+            offset = end;
+        }
+        self.push_gap(&mut styled, line.get(offset..).unwrap_or_default());
+
+        styled
+    }
+}
+
+fn push(styled: &mut StyledText, style: Style, text: &str) {
+    if !text.is_empty() {
+        styled.push((style, text.to_string()));
+    }
+}
+
+/// Decides whether Enter submits the buffer. The delimiter depth of an incomplete buffer is
+/// stored in `depth`, where the prompt reads it from.
+pub(crate) struct ReplValidator {
+    depth: Arc<AtomicUsize>,
+}
+
+impl ReplValidator {
+    pub(crate) fn new(depth: Arc<AtomicUsize>) -> ReplValidator {
+        ReplValidator { depth }
+    }
+}
+
+impl Validator for ReplValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        if is_incomplete(line) {
+            self.depth.store(delimiter_depth(line), Ordering::Relaxed);
+            ValidationResult::Incomplete
+        } else {
+            self.depth.store(0, Ordering::Relaxed);
+            ValidationResult::Complete
+        }
+    }
+}
+
+/// The input to run for a submitted buffer, with Windows line endings turned into `\n` and
+/// trailing whitespace removed. Returns `None` for a blank buffer, such as the empty line
+/// submitted by the newline at the end of a paste, which is skipped without counting as a
+/// line.
+pub(crate) fn submitted_input(buffer: &str) -> Option<String> {
+    let input = buffer.replace("\r\n", "\n").replace('\r', "\n");
+    let input = input.trim_end();
+    (!input.trim_start().is_empty()).then(|| input.to_string())
+}
+
+/// The prompt for the continuation lines of input `line`, with the number of open
+/// delimiters if there are any.
+pub(crate) fn continuation_prompt(line: usize, depth: usize) -> String {
+    match depth {
+        0 => format!("...:{line}> "),
+        depth => format!("...:{line}{{{depth}}}> "),
+    }
+}
+
+/// The number of `(`, `{` and `[` in `source` that are not closed yet. Delimiters in strings
+/// and comments are not tokens, so they do not count.
+pub(crate) fn delimiter_depth(source: &str) -> usize {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_with_errors();
+    open_delimiters(&tokens)
+}
+
+fn open_delimiters(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .fold(0, |depth: usize, token| match token.token_type {
+            TokenType::LeftParent | TokenType::LeftBrace | TokenType::LeftBracket => depth + 1,
+            TokenType::RightParent | TokenType::RightBrace | TokenType::RightBracket => {
+                depth.saturating_sub(1)
+            }
+            _ => depth,
+        })
+}
+
+/// Whether `source` fails to parse only because it ends too early. Source with any other
+/// error is complete, so that the error is shown.
+fn is_incomplete(source: &str) -> bool {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_with_errors();
+
+    if !errors.is_empty() {
+        return errors.iter().all(|error| {
+            matches!(
+                error,
+                LoxError::ScanError(ScanError::UnterminatedString { .. })
+            )
+        });
+    }
+    if tokens.is_empty() {
+        return false;
+    }
+
+    let open_delimiters = open_delimiters(&tokens);
+    let errors = parser::parse_with_recovery(&tokens).errors;
+
+    !errors.is_empty()
+        && errors.iter().all(|error| match error {
+            // A statement missing its `;` at the end of the buffer is only unfinished
+            // inside a block, at the top level it is a mistake.
+            LoxError::ParseError(ParseError::UnexpectedEndOfTokenStream { expected, .. })
+                if expected == "`;`" =>
+            {
+                open_delimiters > 0
+            }
+            LoxError::ParseError(ParseError::UnexpectedEndOfTokenStream { .. })
+            | LoxError::ParseError(ParseError::UnclosedDelimiter { .. }) => true,
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    use crate::error::RuntimeError;
+    use crate::value::Value;
+
+    use reedline::{FileBackedHistory, Hinter, History, HistoryItem};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// An interpreter and a function that returns what it printed since the last call.
+    fn interpreter() -> (Interpreter, impl Fn() -> String) {
+        let buffer = SharedBuffer::default();
+        let interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        let output = move || String::from_utf8(buffer.0.borrow_mut().split_off(0)).unwrap();
+        (interpreter, output)
+    }
+
+    fn evaluate(interpreter: &mut Interpreter, source: &str) -> Result<Value, LoxError> {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let mut values = interpreter.evaluate(&parser::parse(&tokens)?)?;
+        Ok(values.pop().unwrap_or(Value::Nil))
+    }
+
+    #[test]
+    fn test_help_lists_commands() {
+        let (mut interpreter, output) = interpreter();
+
+        assert_eq!(
+            run_command(":help", &mut interpreter),
+            Some(ReplAction::Continue)
+        );
+        let help = output();
+        for command in [
+            ":env", ":help", ":quit", ":reset", ":restore", ":save", ":stats", ":trace", ":type",
+        ] {
+            assert!(help.contains(command), "{command} missing from {help}");
+        }
+    }
+
+    #[test]
+    fn test_trace_toggles_tracing() {
+        let (mut interpreter, output) = interpreter();
+
+        run_command(":trace on", &mut interpreter);
+        assert!(interpreter.is_tracing());
+        run_command(":trace", &mut interpreter);
+        assert_eq!(output(), "Tracing is on. Usage: :trace on|off\n");
+
+        run_command(":trace off", &mut interpreter);
+        assert!(!interpreter.is_tracing());
+    }
+
+    #[test]
+    fn test_env_lists_globals() {
+        let (mut interpreter, output) = interpreter();
+
+        run_command(":env", &mut interpreter);
+        assert_eq!(output(), "No globals defined.\n");
+
+        let long = "x".repeat(50);
+        evaluate(
+            &mut interpreter,
+            &format!("var s = \"hi\"; var n = 1.5; fun f(a) {{}} var long = \"{long}\"; var u;"),
+        )
+        .unwrap();
+        run_command(":env", &mut interpreter);
+        assert_eq!(
+            output(),
+            format!(
+                "f: Function = fun f\nlong: String = \"{}…\nn: Number = 1.5\ns: String = \"hi\"\nu: uninitialized\n",
+                "x".repeat(38)
+            )
+        );
+    }
+
+    #[test]
+    fn test_env_all_includes_the_prelude() {
+        let (mut interpreter, output) = interpreter();
+
+        run_command(":env all", &mut interpreter);
+        let globals = output();
+        assert!(
+            globals.contains("ord: Function = native fun ord\n"),
+            "{globals}"
+        );
+
+        run_command(":env some", &mut interpreter);
+        assert_eq!(output(), "Usage: :env [all]\n");
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.lox-env");
+        let path = path.to_str().unwrap();
+        let (mut interpreter, output) = interpreter();
+
+        evaluate(&mut interpreter, "var n = 1; var s = \"hi\"; fun f() {}").unwrap();
+        run_command(&format!(":save {path}"), &mut interpreter);
+        assert_eq!(
+            output(),
+            format!(
+                "Saved 2 globals to {path}.\n\
+                Not saved, since they hold functions, namespaces, cycles or no value: f\n"
+            )
+        );
+
+        run_command(":reset", &mut interpreter);
+        evaluate(&mut interpreter, "var n = 2;").unwrap();
+        output();
+        run_command(&format!(":restore {path}"), &mut interpreter);
+        assert_eq!(output(), format!("Restored 2 globals from {path}: n, s\n"));
+        assert_eq!(
+            evaluate(&mut interpreter, "n;").unwrap(),
+            Value::Number(1.0)
+        );
+
+        run_command(":save", &mut interpreter);
+        assert_eq!(output(), "Usage: :save <file>\n");
+    }
+
+    #[test]
+    fn test_quit() {
+        let (mut interpreter, _) = interpreter();
+        assert_eq!(
+            run_command(":quit", &mut interpreter),
+            Some(ReplAction::Quit)
+        );
+        assert_eq!(
+            run_command(":quit  ", &mut interpreter),
+            Some(ReplAction::Quit)
+        );
+    }
+
+    #[test]
+    fn test_reset_drops_definitions_but_keeps_the_prelude() {
+        let (mut interpreter, _) = interpreter();
+        evaluate(&mut interpreter, "var a = 1; fun f() { return a; }").unwrap();
+
+        run_command(":reset", &mut interpreter);
+
+        assert!(matches!(
+            evaluate(&mut interpreter, "a;"),
+            Err(LoxError::RuntimeError(
+                RuntimeError::UnknownIdentifier { .. }
+            ))
+        ));
+        assert!(evaluate(&mut interpreter, "f;").is_err());
+        assert_eq!(
+            evaluate(&mut interpreter, "ord(\"a\");").unwrap(),
+            Value::Number(97.0)
+        );
+    }
+
+    #[test]
+    fn test_type_prints_only_the_type_name() {
+        let (mut interpreter, output) = interpreter();
+        evaluate(&mut interpreter, "var a = [1];").unwrap();
+
+        run_command(":type 1 + 2", &mut interpreter);
+        assert_eq!(output(), "Number\n");
+        run_command(":type a", &mut interpreter);
+        assert_eq!(output(), "Array\n");
+        run_command(":type \"x\" + \"y\"", &mut interpreter);
+        assert_eq!(output(), "String\n");
+    }
+
+    #[test]
+    fn test_type_reports_errors() {
+        let (mut interpreter, output) = interpreter();
+
+        run_command(":type", &mut interpreter);
+        assert!(!output().is_empty());
+        run_command(":type undefined", &mut interpreter);
+        assert!(output().contains("UnknownIdentifier"));
+        run_command(":type print 1", &mut interpreter);
+        assert_eq!(output(), "Usage: :type <expr>\n");
+    }
+
+    #[test]
+    fn test_unknown_command_prints_help() {
+        let (mut interpreter, output) = interpreter();
+
+        assert_eq!(
+            run_command(":frobnicate now", &mut interpreter),
+            Some(ReplAction::Continue)
+        );
+        let printed = output();
+        assert!(printed.starts_with("Unknown command :frobnicate\n"));
+        assert!(printed.contains(HELP));
+    }
+
+    #[test]
+    fn test_lox_source_is_not_a_command() {
+        let (mut interpreter, output) = interpreter();
+
+        for line in [" :help", "\":help\";", "print \":quit\";", ""] {
+            assert_eq!(run_command(line, &mut interpreter), None, "{line:?}");
+        }
+        assert!(output().is_empty());
+    }
+
+    fn highlight(line: &str) -> Vec<(Style, String)> {
+        let highlighter = ReplHighlighter::new(Theme::colored());
+        highlighter.highlight(line, line.len()).buffer
+    }
+
+    #[test]
+    fn test_highlight_segments_line_up_with_tokens() {
+        let line = "var x = 1.5; print \"a\" + x; // done\nx";
+        let segments = highlight(line);
+
+        assert_eq!(
+            segments
+                .iter()
+                .map(|(_, text)| text.as_str())
+                .collect::<String>(),
+            line
+        );
+
+        let mut boundaries = vec![0];
+        for (_, text) in &segments {
+            boundaries.push(boundaries.last().unwrap() + text.len());
+        }
+        for token in Scanner::new(line.to_string()).scan() {
+            assert!(boundaries.contains(&token.position.absolute), "{token:?}");
+            assert!(
+                boundaries.contains(&token.position.end_position()),
+                "{token:?}"
+            );
+        }
+
+        let theme = Theme::colored();
+        let style_of = |text: &str| {
+            segments
+                .iter()
+                .find(|(_, segment)| segment == text)
+                .map(|(style, _)| *style)
+        };
+        assert_eq!(style_of("var"), Some(theme.keyword));
+        assert_eq!(style_of("1.5"), Some(theme.number));
+        assert_eq!(style_of("\"a\""), Some(theme.string));
+        assert_eq!(style_of("// done"), Some(theme.comment));
+        assert_eq!(style_of("x"), Some(Style::new()));
+    }
+
+    #[test]
+    fn test_highlight_scan_errors() {
+        let error = Theme::colored().error;
+
+        assert_eq!(
+            highlight("print \"open").last(),
+            Some(&(error, "\"open".to_string()))
+        );
+        assert!(highlight("1 # 2").contains(&(error, "#".to_string())));
+        assert!(highlight("").is_empty());
+    }
+
+    #[test]
+    fn test_plain_theme_has_no_styles() {
+        let highlighter = ReplHighlighter::new(Theme::plain());
+        let styled = highlighter.highlight("print \"a\"; // b", 0);
+
+        assert!(styled
+            .buffer
+            .iter()
+            .all(|(style, _)| *style == Style::new()));
+    }
+
+    #[test]
+    fn test_unfinished_input_is_incomplete() {
+        for source in [
+            "fun f() {",
+            "if (x",
+            "1 +",
+            "var a = [1, 2,",
+            "fun f() {\n    print 1",
+            "while (true) {\n    if (x) {\n        break;\n    }",
+            "print \"multi\nline",
+        ] {
+            assert!(is_incomplete(source), "{source:?} should be incomplete");
+        }
+    }
+
+    #[test]
+    fn test_finished_or_malformed_input_is_complete() {
+        for source in [
+            "",
+            "   ",
+            "print 1;",
+            "fun f() {\n    print 1;\n}",
+            "print 1",
+            "var = 1;",
+            ")",
+            "fun f() { var = ",
+            "1 + #",
+        ] {
+            assert!(!is_incomplete(source), "{source:?} should be complete");
+        }
+    }
+
+    #[test]
+    fn test_delimiter_depth() {
+        for (source, depth) in [
+            ("", 0),
+            ("print 1;", 0),
+            ("fun f() {", 1),
+            ("if (x) {\n    while (y) {", 2),
+            ("var a = [f(", 2),
+            ("{ }", 0),
+            (")", 0),
+        ] {
+            assert_eq!(delimiter_depth(source), depth, "depth of {source:?}");
+        }
+    }
+
+    #[test]
+    fn test_delimiter_depth_ignores_strings_and_comments() {
+        assert_eq!(delimiter_depth("{ print \"{{\";"), 1);
+        assert_eq!(delimiter_depth("{ // }\n"), 1);
+        assert_eq!(delimiter_depth("{ print \"} unterminated"), 1);
+    }
+
+    #[test]
+    fn test_validator_records_the_depth() {
+        let depth = Arc::new(AtomicUsize::new(0));
+        let validator = ReplValidator::new(depth.clone());
+
+        assert!(matches!(
+            validator.validate("fun f() {\n    if (x) {"),
+            ValidationResult::Incomplete
+        ));
+        assert_eq!(depth.load(Ordering::Relaxed), 2);
+
+        assert!(matches!(
+            validator.validate("print 1;"),
+            ValidationResult::Complete
+        ));
+        assert_eq!(depth.load(Ordering::Relaxed), 0);
+    }
+
+    /// The inputs run for `paste`, simulating the line editor. With bracketed paste, the
+    /// paste is inserted as a whole and then Enter is pressed. Without, every newline in it
+    /// is an Enter, which submits the buffer if the validator finds it complete.
+    fn pasted_inputs(paste: &str, bracketed: bool) -> Vec<String> {
+        let validator = ReplValidator::new(Arc::new(AtomicUsize::new(0)));
+        let mut buffer = String::new();
+        let mut inputs = Vec::new();
+
+        let mut enter = |buffer: &mut String| match validator.validate(buffer) {
+            ValidationResult::Complete => {
+                inputs.extend(submitted_input(buffer));
+                buffer.clear();
+            }
+            ValidationResult::Incomplete => buffer.push('\n'),
+        };
+        if bracketed {
+            buffer.push_str(paste);
+            enter(&mut buffer);
+        } else {
+            for (i, line) in paste.split('\n').enumerate() {
+                if i > 0 {
+                    enter(&mut buffer);
+                }
+                buffer.push_str(line);
+            }
+        }
+
+        assert_eq!(buffer, "", "input left in the buffer");
+        inputs
+    }
+
+    const FUNCTION: &str = "fun add(a, b) {\n    return a + b;\n}\n";
+
+    #[test]
+    fn test_bracketed_paste_is_one_input() {
+        assert_eq!(
+            pasted_inputs(FUNCTION, true),
+            vec!["fun add(a, b) {\n    return a + b;\n}"]
+        );
+        assert_eq!(
+            pasted_inputs("print 1;\r\nprint 2;\r\n", true),
+            vec!["print 1;\nprint 2;"]
+        );
+    }
+
+    #[test]
+    fn test_unbracketed_paste_waits_for_complete_input() {
+        assert_eq!(
+            pasted_inputs(FUNCTION, false),
+            vec!["fun add(a, b) {\n    return a + b;\n}"]
+        );
+        assert_eq!(
+            pasted_inputs("print 1;\nprint 2;\n", false),
+            vec!["print 1;", "print 2;"]
+        );
+    }
+
+    #[test]
+    fn test_blank_input_is_skipped() {
+        assert_eq!(submitted_input(""), None);
+        assert_eq!(submitted_input(" \r\n\t"), None);
+        assert_eq!(
+            submitted_input("  print 1;  \n"),
+            Some("  print 1;".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hinter_suggests_from_history() {
+        let mut history = FileBackedHistory::new(10).unwrap();
+        for line in ["print fib(10);", "var answer = 42;", "print fib(20);"] {
+            history.save(HistoryItem::from_command_line(line)).unwrap();
+        }
+        let mut hinter = Theme::colored().hinter();
+
+        assert_eq!(hinter.handle("pri", 3, &history, false, ""), "nt fib(20);");
+        assert_eq!(hinter.complete_hint(), "nt fib(20);");
+        assert_eq!(
+            hinter.handle("var", 3, &history, false, ""),
+            " answer = 42;"
+        );
+        assert_eq!(hinter.handle("fun", 3, &history, false, ""), "");
+
+        let styled = hinter.handle("pri", 3, &history, true, "");
+        assert_eq!(styled, Color::DarkGray.paint("nt fib(20);").to_string());
+    }
+
+    #[test]
+    fn test_continuation_prompt() {
+        assert_eq!(continuation_prompt(3, 0), "...:3> ");
+        assert_eq!(continuation_prompt(3, 2), "...:3{2}> ");
+    }
+}
@@ -0,0 +1,149 @@
+use crate::error::Warning;
+use crate::expression::{Expression, ExpressionNode};
+use crate::statement::Statement;
+
+/// Statically checks a program for suspicious code. Warnings don't stop the program from
+/// running.
+pub fn resolve(statements: &[Statement]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for stmt in statements {
+        resolve_statement(stmt, &mut warnings);
+    }
+
+    warnings
+}
+
+fn resolve_statement(stmt: &Statement, warnings: &mut Vec<Warning>) {
+    match stmt {
+        Statement::Loop { body, position } => {
+            if !exits_loop(body, false) {
+                warnings.push(Warning::InfiniteLoop {
+                    position: position.clone(),
+                });
+            }
+            resolve_statement(body, warnings);
+        }
+        Statement::Block(statements) => {
+            for stmt in statements {
+                resolve_statement(stmt, warnings);
+            }
+        }
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            resolve_condition(condition, warnings);
+            resolve_statement(then_branch, warnings);
+            if let Some(else_branch) = else_branch {
+                resolve_statement(else_branch, warnings);
+            }
+        }
+        Statement::While {
+            condition, body, ..
+        } => {
+            resolve_condition(condition, warnings);
+            resolve_statement(body, warnings);
+        }
+        Statement::Function { body, .. } => resolve_statement(body, warnings),
+        _ => (),
+    }
+}
+
+/// Warns if `condition` is an assignment, which is probably a comparison written with `=`.
+/// An assignment in parentheses, `if ((line = next()))`, is taken as intended and is not
+/// warned about.
+fn resolve_condition(condition: &ExpressionNode, warnings: &mut Vec<Warning>) {
+    if let Expression::Assignment { equals, .. } = &condition.expression {
+        warnings.push(Warning::AssignmentInCondition {
+            position: equals.clone(),
+        });
+    }
+}
+
+/// Whether `stmt` contains a `return` or a `break` of the loop it is the body of. `break`s
+/// of nested loops don't count.
+fn exits_loop(stmt: &Statement, nested: bool) -> bool {
+    match stmt {
+        Statement::Break => !nested,
+        Statement::Return(_) => true,
+        Statement::Block(statements) => statements.iter().any(|stmt| exits_loop(stmt, nested)),
+        Statement::If {
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            exits_loop(then_branch, nested)
+                || else_branch
+                    .as_ref()
+                    .is_some_and(|else_branch| exits_loop(else_branch, nested))
+        }
+        Statement::While { body, .. } | Statement::Loop { body, .. } => exits_loop(body, true),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+    use crate::scanner::Scanner;
+
+    use super::*;
+
+    fn warnings(source: &str) -> Vec<Warning> {
+        let tokens = Scanner::new(source.to_string()).scan();
+        resolve(&parse(&tokens).unwrap())
+    }
+
+    #[test]
+    fn test_loop_without_break_warns() {
+        let warnings = warnings("var i = 0; loop { i = i + 1; }");
+        match warnings.as_slice() {
+            [Warning::InfiniteLoop { position }] => assert_eq!(position.absolute, 11),
+            _ => panic!("expected a single infinite loop warning"),
+        }
+    }
+
+    #[test]
+    fn test_loop_with_break_or_return_does_not_warn() {
+        assert!(warnings("loop { if (true) { break; } }").is_empty());
+        assert!(warnings("fun f() { loop { return 1; } }").is_empty());
+    }
+
+    #[test]
+    fn test_assignment_in_condition_warns() {
+        for (source, equals) in [
+            ("var x; if (x = 1) print x;", 13),
+            ("var x; while (x = nil) {}", 16),
+            ("for (var i = 0; i = 10; i = i + 1) {}", 18),
+        ] {
+            match warnings(source).as_slice() {
+                [Warning::AssignmentInCondition { position }] => {
+                    assert_eq!(position.absolute, equals, "{source}");
+                    assert_eq!(position.length, 1, "{source}");
+                }
+                _ => panic!("expected a single assignment warning for {source}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_assignment_in_extra_parentheses_does_not_warn() {
+        assert!(warnings("var x; if ((x = 1)) print x;").is_empty());
+        assert!(warnings("var x; while ((x = nil)) {}").is_empty());
+    }
+
+    #[test]
+    fn test_comparisons_and_nested_assignments_do_not_warn() {
+        assert!(warnings("var x; if (x == 1) print x;").is_empty());
+        assert!(warnings("var x; var y; if ((x = 1) == y) print x;").is_empty());
+        assert!(warnings("var x; x = 1;").is_empty());
+    }
+
+    #[test]
+    fn test_break_of_nested_loop_does_not_count() {
+        let warnings = warnings("loop { while (true) { break; } }");
+        assert_eq!(warnings.len(), 1);
+    }
+}
@@ -1,9 +1,18 @@
+//! Turns source code into tokens. Tokens and errors locate themselves with byte offsets,
+//! [`LineIndex`] turns those into lines and columns when they are shown.
+
 use std::string::String;
 
+use crate::error::{LoxError, ScanError};
+use crate::position::Position;
+use crate::scanner::number_parser::parse_number_str;
 use crate::scanner::source_iterator::{Entry, SourceIterator};
 use crate::token::TokenType::*;
 use crate::token::{Token, TokenType};
 
+pub use crate::position::LineIndex;
+
+pub(crate) mod number_parser;
 pub(crate) mod source_iterator;
 
 pub struct Scanner {
@@ -16,155 +25,275 @@ impl Scanner {
     }
 
     pub fn scan(&self) -> Vec<Token> {
-        let mut tokens: Vec<Token> = Vec::new();
-        let mut source_iter = SourceIterator::new(self.code.clone());
-
-        while let Some(e) = source_iter.next() {
-            match e.value {
-                '(' => tokens.push(Token::new(LeftParent, e, 1)),
-                ')' => tokens.push(Token::new(RightParent, e, 1)),
-                '{' => tokens.push(Token::new(LeftBrace, e, 1)),
-                '}' => tokens.push(Token::new(RightBrace, e, 1)),
-                ',' => tokens.push(Token::new(Comma, e, 1)),
-                '.' => tokens.push(Token::new(Dot, e, 1)),
-                '-' => tokens.push(Token::new(Minus, e, 1)),
-                '+' => tokens.push(Token::new(Plus, e, 1)),
-                ';' => tokens.push(Token::new(Semicolon, e, 1)),
-                '*' => tokens.push(Token::new(Star, e, 1)),
-                '!' => scan_with_equal(&mut tokens, &mut source_iter, BangEqual, Bang, e),
-                '=' => scan_with_equal(&mut tokens, &mut source_iter, EqualEqual, Equal, e),
-                '<' => scan_with_equal(&mut tokens, &mut source_iter, LessEqual, Less, e),
-                '>' => scan_with_equal(&mut tokens, &mut source_iter, GreaterEqual, Greater, e),
-                '/' => {
-                    if source_iter.next_match('/') {
-                        source_iter.scan_until('\n');
-                    } else {
-                        tokens.push(Token::new(Slash, e, 1))
-                    }
-                }
-                ' ' | '\r' | '\t' | '\n' => (),
-                '"' => match scan_string(&mut source_iter, e) {
-                    Ok(token) => tokens.push(token),
-                    Err(e) => {
-                        println!("Error!: {e}");
-                        break;
-                    }
-                },
-                value if value.is_numeric() => tokens.push(scan_number(&mut source_iter, e)),
-                value if value.is_alphanumeric() => {
-                    tokens.push(scan_identifier(&mut source_iter, e))
-                }
-                value => {
-                    println!("Error!: Unrecognized Character '{value}'");
-                    break;
-                }
-            }
+        let (tokens, errors) = self.scan_with_errors();
+
+        for error in errors {
+            println!("Error!: {error}");
         }
 
-        return tokens;
-
-        fn scan_with_equal(
-            tokens: &mut Vec<Token>,
-            source_iter: &mut SourceIterator,
-            a: TokenType,
-            b: TokenType,
-            entry: Entry,
-        ) {
-            if source_iter.next_match('=') {
-                tokens.push(Token::new(a, entry, 2))
-            } else {
-                tokens.push(Token::new(b, entry, 1))
-            };
+        tokens
+    }
+
+    /// Scans the whole source, continuing after errors.
+    pub fn scan_with_errors(&self) -> (Vec<Token>, Vec<LoxError>) {
+        scan_tokens(SourceIterator::new(self.code.clone()))
+    }
+
+    /// Scans the source and rebuilds it from the tokens, see `tokens_to_source`.
+    pub fn scan_and_reconstruct(&self) -> (Vec<Token>, String) {
+        let (tokens, _) = self.scan_with_errors();
+        let source = Scanner::tokens_to_source(&tokens, &self.code);
+        (tokens, source)
+    }
+
+    /// Rebuilds the part of `source` that `tokens` were scanned from. The text of each token
+    /// is copied from `source`, whitespace, comments and unrecognized characters between two
+    /// tokens become a single space. Scanning the result produces the same tokens, but at
+    /// different positions.
+    pub fn tokens_to_source(tokens: &[Token], source: &str) -> String {
+        let mut reconstructed = String::with_capacity(source.len());
+        let mut previous_end = None;
+
+        for token in tokens {
+            let start = token.position.absolute;
+            if previous_end.is_some_and(|end| end < start) {
+                reconstructed.push(' ');
+            }
+
+            let end = token.position.end_position();
+            reconstructed.push_str(source.get(start..end).unwrap_or_default());
+            previous_end = Some(end);
         }
 
-        fn scan_string(
-            source_iter: &mut SourceIterator,
-            first_entry: Entry,
-        ) -> Result<Token, String> {
-            let entry = source_iter.scan_until('"');
+        reconstructed
+    }
+
+    /// Finds the `//` comments in the gaps between `tokens`, which scanning drops, and
+    /// returns their positions in `source`. A comment ends before the line break ending it.
+    pub fn comments(tokens: &[Token], source: &str) -> Vec<Position> {
+        let mut comments = Vec::new();
+        let mut gap_start = 0;
+        let token_ranges = tokens
+            .iter()
+            .map(|token| (token.position.absolute, token.position.end_position()))
+            .chain([(source.len(), source.len())]);
+
+        for (gap_end, next_gap_start) in token_ranges {
+            let gap = source.get(gap_start..gap_end).unwrap_or_default();
+            let mut offset = 0;
 
-            if entry.is_none() {
-                return Err("Unterminated String".to_string());
+            while let Some(start) = gap[offset..].find("//").map(|start| offset + start) {
+                let length = gap[start..].find('\n').unwrap_or(gap.len() - start);
+                comments.push(Position::new(gap_start + start, length));
+                offset = start + length;
             }
 
-            let entry = entry.unwrap();
+            gap_start = next_gap_start;
+        }
+
+        comments
+    }
+
+    /// Scans the byte range `from..to` of the source. Token positions are relative to the
+    /// whole source. `from` is moved forward and `to` backward to the nearest character
+    /// boundary. A token cut off by `to` is returned partially, an unterminated string
+    /// additionally produces a `ScanError`.
+    pub fn scan_segment(&self, from: usize, to: usize) -> (Vec<Token>, Vec<LoxError>) {
+        let mut to = to.min(self.code.len());
+        while !self.code.is_char_boundary(to) {
+            to -= 1;
+        }
 
-            let value = source_iter.substring(first_entry.position + 1, entry.position - 1);
-            let token = StringToken(value);
-            Ok(Token::new(
-                token,
-                first_entry,
-                entry.position - first_entry.position + 1,
-            ))
+        let mut from = from.min(to);
+        while !self.code.is_char_boundary(from) {
+            from += 1;
         }
 
-        fn scan_number(source_iter: &mut SourceIterator, first_entry: Entry) -> Token {
-            let mut found_dot = false;
-
-            let mut last_entry = first_entry;
-            loop {
-                match (source_iter.peek(), source_iter.peek_next()) {
-                    (Some(c), _) if c.is_numeric() => {
-                        last_entry = source_iter.next().unwrap();
-                    }
-                    (Some(c), Some(d)) if c == '.' && !found_dot && d.is_numeric() => {
-                        found_dot = true;
-                        last_entry = source_iter.next().unwrap();
-                    }
-                    _ => break,
+        scan_tokens(SourceIterator::new_at(&self.code[..to], from))
+    }
+}
+
+fn scan_tokens(mut source_iter: SourceIterator) -> (Vec<Token>, Vec<LoxError>) {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut errors: Vec<LoxError> = Vec::new();
+
+    while let Some(e) = source_iter.next() {
+        match e.value {
+            '(' => tokens.push(Token::new(LeftParent, e, 1)),
+            ')' => tokens.push(Token::new(RightParent, e, 1)),
+            '{' => tokens.push(Token::new(LeftBrace, e, 1)),
+            '}' => tokens.push(Token::new(RightBrace, e, 1)),
+            '[' => tokens.push(Token::new(LeftBracket, e, 1)),
+            ']' => tokens.push(Token::new(RightBracket, e, 1)),
+            ',' => tokens.push(Token::new(Comma, e, 1)),
+            '.' if source_iter.peek_count(2) == ['.', '.'] => {
+                source_iter.next();
+                source_iter.next();
+                tokens.push(Token::new(DotDotDot, e, 3))
+            }
+            '.' => tokens.push(Token::new(Dot, e, 1)),
+            '-' => tokens.push(Token::new(Minus, e, 1)),
+            '+' => tokens.push(Token::new(Plus, e, 1)),
+            ';' => tokens.push(Token::new(Semicolon, e, 1)),
+            '*' => tokens.push(Token::new(Star, e, 1)),
+            '!' => scan_with_equal(&mut tokens, &mut source_iter, BangEqual, Bang, e),
+            '=' => scan_with_equal(&mut tokens, &mut source_iter, EqualEqual, Equal, e),
+            '<' => scan_with_equal(&mut tokens, &mut source_iter, LessEqual, Less, e),
+            '>' => scan_with_equal(&mut tokens, &mut source_iter, GreaterEqual, Greater, e),
+            '/' => {
+                if source_iter.next_match('/') {
+                    source_iter.scan_until('\n');
+                } else {
+                    tokens.push(Token::new(Slash, e, 1))
                 }
             }
+            ' ' | '\r' | '\t' | '\n' => (),
+            // Pasted code often brings non-breaking spaces and other Unicode whitespace.
+            value if value.is_whitespace() => (),
+            '"' => {
+                let (token, error) = scan_string(&mut source_iter, e);
+                tokens.push(token);
+                errors.extend(error);
+            }
+            value if value.is_numeric() => match scan_number(&mut source_iter, e) {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            },
+            '_' => tokens.push(scan_identifier(&mut source_iter, e)),
+            value if value.is_alphanumeric() => tokens.push(scan_identifier(&mut source_iter, e)),
+            value => errors.push(ScanError::unrecognized_character(
+                value,
+                Position::new(e.position, value.len_utf8()),
+            )),
+        }
+    }
+
+    return (tokens, errors);
 
-            let value = source_iter
-                .substring(first_entry.position, last_entry.position)
-                .parse::<f64>()
-                .unwrap();
-            let token_type = Number(value);
-            Token::new(
-                token_type,
-                first_entry,
-                last_entry.position - first_entry.position + 1,
-            )
+    fn scan_with_equal(
+        tokens: &mut Vec<Token>,
+        source_iter: &mut SourceIterator,
+        a: TokenType,
+        b: TokenType,
+        entry: Entry,
+    ) {
+        if source_iter.next_match('=') {
+            tokens.push(Token::new(a, entry, 2))
+        } else {
+            tokens.push(Token::new(b, entry, 1))
+        };
+    }
+
+    fn scan_string(
+        source_iter: &mut SourceIterator,
+        first_entry: Entry,
+    ) -> (Token, Option<LoxError>) {
+        match source_iter.scan_until('"') {
+            Some(entry) => {
+                let value = source_iter.substring(first_entry.position + 1, entry.position - 1);
+                let token = Token::new(
+                    StringToken(value),
+                    first_entry,
+                    entry.position - first_entry.position + 1,
+                );
+                (token, None)
+            }
+            None => {
+                let end = source_iter.offset();
+                let value = source_iter.substring(first_entry.position + 1, end - 1);
+                let token = Token::new(StringToken(value), first_entry, end - first_entry.position);
+                let escaped_quote = source_iter
+                    .text_before(first_entry.position)
+                    .contains("\\\"");
+                let error = ScanError::unterminated_string(
+                    Position::new(first_entry.position, 1),
+                    source_iter.last_char_position(),
+                    escaped_quote,
+                );
+                (token, Some(error))
+            }
         }
+    }
 
-        fn scan_identifier(source_iter: &mut SourceIterator, first_entry: Entry) -> Token {
-            let mut last_entry = first_entry;
-            loop {
-                match source_iter.peek() {
-                    Some(e) if !e.is_alphanumeric() => break,
-                    None => break,
-                    _ => last_entry = source_iter.next().unwrap(),
+    /// Scans a number literal. A single `_` between two digits separates digit groups and
+    /// is ignored, so `1_000` is `1000`. Digits other than `0` to `9`, e.g. `²`, are scanned
+    /// as part of the literal but make it invalid.
+    fn scan_number(
+        source_iter: &mut SourceIterator,
+        first_entry: Entry,
+    ) -> Result<Token, LoxError> {
+        let mut found_dot = false;
+
+        let mut last_entry = first_entry;
+        loop {
+            match (source_iter.peek(), source_iter.peek_next()) {
+                (Some(c), _) if c.is_numeric() => {
+                    last_entry = source_iter.next().unwrap();
+                }
+                (Some(c), Some(d)) if c == '.' && !found_dot && d.is_numeric() => {
+                    found_dot = true;
+                    last_entry = source_iter.next().unwrap();
                 }
+                (Some('_'), Some(d)) if d.is_numeric() => {
+                    last_entry = source_iter.next().unwrap();
+                }
+                _ => break,
             }
+        }
+
+        let length = last_entry.position + last_entry.value.len_utf8() - first_entry.position;
+        let literal =
+            source_iter.substring(first_entry.position, first_entry.position + length - 1);
+        match parse_number_str(&literal) {
+            Ok(value) => Ok(Token::new(Number(value), first_entry, length)),
+            Err(_) => Err(ScanError::invalid_number(
+                literal,
+                Position::new(first_entry.position, length),
+            )),
+        }
+    }
 
-            let value = source_iter.substring(first_entry.position, last_entry.position);
-
-            let token_type = match value.as_ref() {
-                "and" => And,
-                "class" => Class,
-                "else" => Else,
-                "false" => False,
-                "for" => For,
-                "fun" => Fun,
-                "if" => If,
-                "nil" => Nil,
-                "or" => Or,
-                "print" => Print,
-                "return" => Return,
-                "super" => Super,
-                "this" => This,
-                "true" => True,
-                "var" => Var,
-                "while" => While,
-                _ => Identifier(value),
-            };
-
-            Token::new(
-                token_type,
-                first_entry,
-                last_entry.position - first_entry.position + 1,
-            )
+    fn scan_identifier(source_iter: &mut SourceIterator, first_entry: Entry) -> Token {
+        let mut last_entry = first_entry;
+        loop {
+            match source_iter.peek() {
+                Some(e) if !(e.is_alphanumeric() || e == '_') => break,
+                None => break,
+                _ => last_entry = source_iter.next().unwrap(),
+            }
         }
+
+        let value = source_iter.substring(first_entry.position, last_entry.position);
+
+        let token_type = match value.as_ref() {
+            "and" => And,
+            "class" => Class,
+            "else" => Else,
+            "false" => False,
+            "for" => For,
+            "fun" => Fun,
+            "if" => If,
+            "nil" => Nil,
+            "or" => Or,
+            "print" => Print,
+            "return" => Return,
+            "super" => Super,
+            "this" => This,
+            "true" => True,
+            "var" => Var,
+            "while" => While,
+            "loop" => Loop,
+            "break" => Break,
+            "continue" => Continue,
+            "assert" => Assert,
+            _ => Identifier(value),
+        };
+
+        Token::new(
+            token_type,
+            first_entry,
+            last_entry.position - first_entry.position + 1,
+        )
     }
 }
 
@@ -178,4 +307,228 @@ mod tests {
         let tokens = scanner.scan();
         println!("{tokens:?}")
     }
+
+    fn token_types(tokens: &[Token]) -> Vec<TokenType> {
+        tokens.iter().map(|t| t.token_type.clone()).collect()
+    }
+
+    #[test]
+    fn test_tokens_to_source() {
+        let scanner = Scanner::new("  var x=1; // one\n\tprint   x # ;".to_string());
+        let (_, reconstructed) = scanner.scan_and_reconstruct();
+
+        assert_eq!(reconstructed, "var x=1; print x ;");
+    }
+
+    #[test]
+    fn test_reconstructed_source_scans_to_the_same_tokens() {
+        let sources = [
+            include_str!("../../tests/classes.lox"),
+            include_str!("../../tests/control_flow.lox"),
+            include_str!("../../tests/expressions.lox"),
+            include_str!("../../tests/functions.lox"),
+            include_str!("../../tests/statements.lox"),
+            include_str!("../../tests/types.lox"),
+            "a/ /b//c\nd",
+            "123_ 1_0.5 ...x.y",
+            "\"multi\nline\" \"unterminated",
+            "fun héllo(ünïcode) { return \"✓\"; }",
+            "1#2@3",
+            "",
+        ];
+
+        for source in sources {
+            let (tokens, reconstructed) = Scanner::new(source.to_string()).scan_and_reconstruct();
+            let (rescanned, _) = Scanner::new(reconstructed.clone()).scan_with_errors();
+
+            assert_eq!(
+                token_types(&rescanned),
+                token_types(&tokens),
+                "{source:?} was reconstructed as {reconstructed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let source = "// first\nvar a = \"// not a comment\"; // second // still second\r\n//";
+        let tokens = Scanner::new(source.to_string()).scan();
+        let comments = Scanner::comments(&tokens, source)
+            .iter()
+            .map(|comment| &source[comment.absolute..comment.end_position()])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            comments,
+            vec!["// first", "// second // still second\r", "//"]
+        );
+        assert!(Scanner::comments(&[], "").is_empty());
+    }
+
+    #[test]
+    fn test_scan_segment_keeps_absolute_positions() {
+        let scanner = Scanner::new("var x = 1;".to_string());
+        let (tokens, errors) = scanner.scan_segment(4, 10);
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            token_types(&tokens),
+            vec![Identifier("x".to_string()), Equal, Number(1.0), Semicolon]
+        );
+        assert_eq!(tokens[0].position, Position::new(4, 1));
+        assert_eq!(tokens[3].position, Position::new(9, 1));
+    }
+
+    #[test]
+    fn test_scan_segment_snaps_to_char_boundaries() {
+        let scanner = Scanner::new("// ä\nx;".to_string());
+        let (tokens, errors) = scanner.scan_segment(4, 100);
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            token_types(&tokens),
+            vec![Identifier("x".to_string()), Semicolon]
+        );
+        assert_eq!(tokens[0].position, Position::new(6, 1));
+    }
+
+    #[test]
+    fn test_scan_segment_returns_partial_identifier() {
+        let scanner = Scanner::new("var answer = 42;".to_string());
+        let (tokens, errors) = scanner.scan_segment(0, 7);
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            token_types(&tokens),
+            vec![Var, Identifier("ans".to_string())]
+        );
+        assert_eq!(tokens[1].position, Position::new(4, 3));
+    }
+
+    #[test]
+    fn test_scan_segment_reports_cut_string() {
+        let scanner = Scanner::new("print \"hello\";".to_string());
+        let (tokens, errors) = scanner.scan_segment(0, 9);
+
+        assert_eq!(
+            token_types(&tokens),
+            vec![Print, StringToken("he".to_string())]
+        );
+        assert_eq!(tokens[1].position, Position::new(6, 3));
+        assert!(matches!(
+            errors.as_slice(),
+            [LoxError::ScanError(ScanError::UnterminatedString { .. })]
+        ));
+    }
+
+    #[test]
+    fn test_scan_continues_after_unrecognized_character() {
+        let scanner = Scanner::new("1 # 2".to_string());
+        let (tokens, errors) = scanner.scan_with_errors();
+
+        assert_eq!(token_types(&tokens), vec![Number(1.0), Number(2.0)]);
+        assert!(matches!(
+            errors.as_slice(),
+            [LoxError::ScanError(ScanError::UnrecognizedCharacter {
+                character: '#',
+                ..
+            })]
+        ));
+    }
+
+    #[test]
+    fn test_unicode_whitespace_separates_tokens() {
+        for whitespace in ['\u{a0}', '\u{3000}', '\u{2028}', '\u{2029}'] {
+            let source = format!("var{whitespace}x{whitespace}={whitespace}1;");
+            let (tokens, errors) = Scanner::new(source).scan_with_errors();
+
+            assert!(errors.is_empty(), "{whitespace:?}");
+            assert_eq!(
+                token_types(&tokens),
+                vec![
+                    Var,
+                    Identifier("x".to_string()),
+                    Equal,
+                    Number(1.0),
+                    Semicolon
+                ],
+                "{whitespace:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unicode_whitespace_keeps_byte_positions() {
+        let (tokens, _) = Scanner::new("a\u{a0}b".to_string()).scan_with_errors();
+
+        assert_eq!(tokens[0].position, Position::new(0, 1));
+        assert_eq!(tokens[1].position, Position::new(3, 1));
+    }
+
+    #[test]
+    fn test_identifiers_with_underscores() {
+        for identifier in ["_priv", "snake_case", "__init__", "_"] {
+            let scanner = Scanner::new(format!("{identifier};"));
+            let (tokens, errors) = scanner.scan_with_errors();
+
+            assert!(errors.is_empty());
+            assert_eq!(
+                token_types(&tokens),
+                vec![Identifier(identifier.to_string()), Semicolon]
+            );
+            assert_eq!(tokens[0].position, Position::new(0, identifier.len()));
+        }
+    }
+
+    #[test]
+    fn test_numbers_with_separators() {
+        for (literal, value) in [
+            ("1_000", 1000.0),
+            ("1_000_000", 1000000.0),
+            ("3.14_15", 3.1415),
+            ("0.0_1", 0.01),
+        ] {
+            let scanner = Scanner::new(format!("{literal};"));
+            let (tokens, errors) = scanner.scan_with_errors();
+
+            assert!(errors.is_empty());
+            assert_eq!(token_types(&tokens), vec![Number(value), Semicolon]);
+            assert_eq!(tokens[0].position, Position::new(0, literal.len()));
+        }
+    }
+
+    #[test]
+    fn test_separators_must_be_between_digits() {
+        let cases = [
+            ("_123", vec![Identifier("_123".to_string())]),
+            ("123_", vec![Number(123.0), Identifier("_".to_string())]),
+            ("1__0", vec![Number(1.0), Identifier("__0".to_string())]),
+            (
+                "1_.5",
+                vec![Number(1.0), Identifier("_".to_string()), Dot, Number(5.0)],
+            ),
+            ("1._5", vec![Number(1.0), Dot, Identifier("_5".to_string())]),
+        ];
+
+        for (source, expected) in cases {
+            let (tokens, errors) = Scanner::new(source.to_string()).scan_with_errors();
+
+            assert!(errors.is_empty());
+            assert_eq!(token_types(&tokens), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn test_numbers_with_other_digits_are_errors() {
+        let (tokens, errors) = Scanner::new("1² + ٣;".to_string()).scan_with_errors();
+
+        assert_eq!(token_types(&tokens), vec![Plus, Semicolon]);
+        assert!(matches!(
+            &errors[..],
+            [
+                LoxError::ScanError(ScanError::InvalidNumber { literal: first, position: p1 }),
+                LoxError::ScanError(ScanError::InvalidNumber { literal: second, position: p2 }),
+            ] if first == "1²" && *p1 == Position::new(0, 3) && second == "٣" && *p2 == Position::new(6, 2)
+        ));
+    }
 }
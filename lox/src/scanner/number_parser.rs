@@ -0,0 +1,116 @@
+//! Turns the text of a number literal into its value.
+//!
+//! The scanner collects the characters of a literal, this module checks them and converts
+//! them. A literal starts with a digit, `_` separates digit groups and is ignored.
+
+use std::num::ParseFloatError;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub(crate) enum ParseNumberError {
+    #[error("`{literal}` does not start with a digit")]
+    MissingLeadingDigit { literal: String },
+    #[error("`{literal}` is not a number: {error}")]
+    Invalid {
+        literal: String,
+        error: ParseFloatError,
+    },
+}
+
+/// Parses the number literal `s`, e.g. `42`, `3.14` or `1_000`. Rust's own float syntax
+/// accepts more than Lox does, `.5`, `+1` and `inf` are rejected here because they don't
+/// start with a digit.
+pub(crate) fn parse_number_str(s: &str) -> Result<f64, ParseNumberError> {
+    if !s.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(ParseNumberError::MissingLeadingDigit {
+            literal: s.to_string(),
+        });
+    }
+
+    s.replace('_', "")
+        .parse::<f64>()
+        .map_err(|error| ParseNumberError::Invalid {
+            literal: s.to_string(),
+            error,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_parses(literal: &str, expected: f64) {
+        assert_eq!(parse_number_str(literal), Ok(expected), "{literal}");
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_parses("123", 123.0);
+        assert_parses("0", 0.0);
+        assert_parses("007", 7.0);
+    }
+
+    #[test]
+    fn test_decimals() {
+        assert_parses("3.14", 3.14);
+        assert_parses("0.5", 0.5);
+        assert_parses("1.0", 1.0);
+    }
+
+    #[test]
+    fn test_exponents() {
+        assert_parses("1e10", 1e10);
+        assert_parses("2.5E-3", 2.5e-3);
+    }
+
+    #[test]
+    fn test_separators_are_ignored() {
+        assert_parses("1_000", 1000.0);
+        assert_parses("3.14_15", 3.1415);
+    }
+
+    #[test]
+    fn test_large_literals_round() {
+        assert_parses("9007199254740993", 9007199254740992.0);
+        assert_parses("1e400", f64::INFINITY);
+    }
+
+    #[test]
+    fn test_literals_must_start_with_a_digit() {
+        for literal in [".5", "+1", "-1", "inf", "NaN", "_1", ""] {
+            assert_eq!(
+                parse_number_str(literal),
+                Err(ParseNumberError::MissingLeadingDigit {
+                    literal: literal.to_string()
+                }),
+                "{literal}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_literals() {
+        for literal in ["123abc", "1.2.3", "1²", "1e"] {
+            assert!(
+                matches!(
+                    parse_number_str(literal),
+                    Err(ParseNumberError::Invalid { literal: ref l, .. }) if l == literal
+                ),
+                "{literal}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_message_names_the_literal() {
+        assert_eq!(
+            parse_number_str(".5").unwrap_err().to_string(),
+            "`.5` does not start with a digit"
+        );
+        assert_eq!(
+            parse_number_str("123abc").unwrap_err().to_string(),
+            "`123abc` is not a number: invalid float literal"
+        );
+    }
+}
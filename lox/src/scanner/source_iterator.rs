@@ -1,5 +1,8 @@
+use std::collections::VecDeque;
 use std::vec::IntoIter;
 
+use crate::position::Position;
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub(crate) struct Entry {
     pub(crate) value: char,
@@ -17,6 +20,9 @@ pub(crate) struct SourceIterator {
     chars: IntoIter<char>,
     peek: Option<Option<char>>,
     peek_next: Option<Option<char>>,
+    peek_third: Option<Option<char>>,
+    /// Characters peeked beyond the third.
+    lookahead: VecDeque<char>,
     pos: usize,
 }
 
@@ -27,11 +33,18 @@ impl Iterator for SourceIterator {
         let next_value = match self.peek.take() {
             Some(c) => {
                 self.peek = self.peek_next.take();
+                self.peek_next = self.peek_third.take();
                 c
             }
             None => match self.peek_next.take() {
-                Some(c) => c,
-                None => self.chars.next(),
+                Some(c) => {
+                    self.peek_next = self.peek_third.take();
+                    c
+                }
+                None => match self.peek_third.take() {
+                    Some(c) => c,
+                    None => self.pull(),
+                },
             },
         };
 
@@ -50,27 +63,95 @@ impl Iterator for SourceIterator {
 
 impl SourceIterator {
     pub(crate) fn new(text: String) -> SourceIterator {
-        let chars = text.chars().collect::<Vec<_>>().into_iter();
+        SourceIterator::new_at(&text, 0)
+    }
+
+    /// Creates an iterator over `text` that starts at the byte offset `start_offset`, which
+    /// must be a character boundary. Entry positions stay relative to the start of `text`.
+    pub(crate) fn new_at(text: &str, start_offset: usize) -> SourceIterator {
+        let chars = text[start_offset..].chars().collect::<Vec<_>>().into_iter();
 
         SourceIterator {
-            source: text,
+            source: text.to_string(),
             chars,
             peek: None,
             peek_next: None,
-            pos: 0,
+            peek_third: None,
+            lookahead: VecDeque::new(),
+            pos: start_offset,
         }
     }
 
+    /// The byte offset of the next entry returned by `next`.
+    pub(crate) fn offset(&self) -> usize {
+        self.pos
+    }
+
     pub(crate) fn peek(&mut self) -> Option<char> {
-        let chars = &mut self.chars;
-        *self.peek.get_or_insert_with(|| chars.next())
+        if self.peek.is_none() {
+            self.peek = Some(self.pull());
+        }
+        self.peek.unwrap()
     }
 
     pub(crate) fn peek_next(&mut self) -> Option<char> {
         self.peek();
 
-        let chars = &mut self.chars;
-        *self.peek_next.get_or_insert_with(|| chars.next())
+        if self.peek_next.is_none() {
+            self.peek_next = Some(self.pull());
+        }
+        self.peek_next.unwrap()
+    }
+
+    pub(crate) fn peek_third(&mut self) -> Option<char> {
+        self.peek_next();
+
+        if self.peek_third.is_none() {
+            self.peek_third = Some(self.pull());
+        }
+        self.peek_third.unwrap()
+    }
+
+    /// Returns the upcoming characters satisfying `pred` without consuming them.
+    #[allow(dead_code)] // For lookahead of variable length, e.g. block comments.
+    pub(crate) fn peek_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let mut result = String::new();
+        let mut n = 0;
+
+        while let Some(c) = self.peek_nth(n) {
+            if !pred(c) {
+                break;
+            }
+            result.push(c);
+            n += 1;
+        }
+
+        result
+    }
+
+    /// Returns up to `n` upcoming characters without consuming them.
+    pub(crate) fn peek_count(&mut self, n: usize) -> Vec<char> {
+        (0..n).map_while(|i| self.peek_nth(i)).collect()
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Option<char> {
+        match n {
+            0 => self.peek(),
+            1 => self.peek_next(),
+            2 => self.peek_third(),
+            _ => {
+                self.peek_third()?;
+
+                while self.lookahead.len() < n - 2 {
+                    self.lookahead.push_back(self.chars.next()?);
+                }
+                self.lookahead.get(n - 3).copied()
+            }
+        }
+    }
+
+    fn pull(&mut self) -> Option<char> {
+        self.lookahead.pop_front().or_else(|| self.chars.next())
     }
 
     pub(crate) fn next_match(&mut self, expected: char) -> bool {
@@ -93,10 +174,59 @@ impl SourceIterator {
         }
     }
 
+    /// Consumes the rest of the input and returns it.
+    #[allow(dead_code)] // For diagnostics that quote the input after an error.
+    pub(crate) fn collect_rest(&mut self) -> String {
+        self.map(|entry| entry.value).collect()
+    }
+
+    /// The number of bytes of input that have not been consumed yet, including peeked
+    /// characters.
+    #[allow(dead_code)] // For diagnostics that quote the input after an error.
+    pub(crate) fn remaining_len(&self) -> usize {
+        self.source.len() - self.pos
+    }
+
+    /// The source before the byte offset `offset`.
+    pub(crate) fn text_before(&self, offset: usize) -> &str {
+        &self.source[..offset]
+    }
+
+    /// The position of the last character of the source, for labels at the end of the input.
+    /// A final line break is labeled with length 0, so the label is shown at the end of the
+    /// last line rather than on a line of its own.
+    pub(crate) fn last_char_position(&self) -> Position {
+        match self.source.char_indices().next_back() {
+            Some((offset, '\n')) => Position::new(offset, 0),
+            Some((offset, c)) => Position::new(offset, c.len_utf8()),
+            None => Position::new(0, 0),
+        }
+    }
+
     pub(crate) fn substring(&self, from: usize, to: usize) -> String {
         let text: &str = &self.source;
         text[from..=to].to_string()
     }
+
+    /// Converts a byte offset into the source into the number of characters that start
+    /// before it. Offsets past the end count all characters. O(n) in the offset.
+    #[allow(dead_code)] // For tools that count characters, like a language server.
+    pub(crate) fn byte_offset_to_char_offset(&self, byte_offset: usize) -> usize {
+        self.source
+            .char_indices()
+            .take_while(|(i, _)| *i < byte_offset)
+            .count()
+    }
+
+    /// Converts a character offset into the byte offset at which that character starts.
+    /// Offsets past the last character map to the length of the source. O(n) in the offset.
+    #[allow(dead_code)] // For tools that count characters, like a language server.
+    pub(crate) fn char_offset_to_byte_offset(&self, char_offset: usize) -> usize {
+        self.source
+            .char_indices()
+            .nth(char_offset)
+            .map_or(self.source.len(), |(i, _)| i)
+    }
 }
 
 #[cfg(test)]
@@ -170,6 +300,49 @@ mod tests {
         assert_eq!(iterator.peek_next(), Some('r'));
     }
 
+    #[test]
+    fn test_peek_third_looks_ahead_three() {
+        let mut iterator = SourceIterator::new("<<=;".to_string());
+
+        assert_eq!(iterator.peek_third(), Some('='));
+        assert_eq!(iterator.next(), Some(Entry::new('<', 0)));
+        assert_eq!(iterator.peek_third(), Some(';'));
+        assert_eq!(iterator.next(), Some(Entry::new('<', 1)));
+        assert_eq!(iterator.peek_third(), None);
+        iterator.next();
+        iterator.next();
+        assert_eq!(iterator.peek_third, None);
+    }
+
+    #[test]
+    fn test_peek_third_can_be_run_multiple_times() {
+        let mut iterator = SourceIterator::new("...x".to_string());
+
+        assert_eq!(iterator.peek_third(), Some('.'));
+        assert_eq!(iterator.peek_third(), Some('.'));
+        assert_eq!(iterator.next(), Some(Entry::new('.', 0)));
+    }
+
+    #[test]
+    fn test_next_shifts_the_peeked_characters() {
+        let mut iterator = SourceIterator::new("abcde".to_string());
+
+        assert_eq!(iterator.peek_third(), Some('c'));
+        assert_eq!(iterator.peek(), Some('a'));
+        assert_eq!(iterator.peek_next(), Some('b'));
+        assert_eq!(iterator.next(), Some(Entry::new('a', 0)));
+        assert_eq!(iterator.peek(), Some('b'));
+        assert_eq!(iterator.peek_next(), Some('c'));
+        assert_eq!(iterator.peek_third(), Some('d'));
+        assert_eq!(iterator.peek_count(5), vec!['b', 'c', 'd', 'e']);
+        assert_eq!(iterator.next(), Some(Entry::new('b', 1)));
+        assert_eq!(iterator.next(), Some(Entry::new('c', 2)));
+        assert_eq!(iterator.next(), Some(Entry::new('d', 3)));
+        assert_eq!(iterator.peek_third(), None);
+        assert_eq!(iterator.next(), Some(Entry::new('e', 4)));
+        assert_eq!(iterator.next(), None);
+    }
+
     #[test]
     fn test_next_match_returns_true_if_next_character_matches() {
         let mut iterator = SourceIterator::new("BarBaz".to_string());
@@ -210,10 +383,127 @@ mod tests {
         assert_eq!(iterator.next(), None)
     }
 
+    #[test]
+    fn test_peek_while_does_not_consume() {
+        let mut iterator = SourceIterator::new("abc1d".to_string());
+
+        assert_eq!(iterator.peek_while(|c| c.is_alphabetic()), "abc");
+        assert_eq!(iterator.peek_while(|c| c.is_alphabetic()), "abc");
+        assert_eq!(iterator.next(), Some(Entry::new('a', 0)));
+        assert_eq!(iterator.next(), Some(Entry::new('b', 1)));
+        assert_eq!(iterator.next(), Some(Entry::new('c', 2)));
+        assert_eq!(iterator.next(), Some(Entry::new('1', 3)));
+        assert_eq!(iterator.next(), Some(Entry::new('d', 4)));
+        assert_eq!(iterator.next(), None);
+    }
+
+    #[test]
+    fn test_peek_while_stops_at_end_of_input() {
+        let mut iterator = SourceIterator::new("/*".to_string());
+
+        assert_eq!(iterator.peek_while(|_| true), "/*");
+        assert_eq!(iterator.peek_while(|c| c == '*'), "");
+    }
+
+    #[test]
+    fn test_peek_count_looks_ahead_n() {
+        let mut iterator = SourceIterator::new("0x1F".to_string());
+
+        assert_eq!(iterator.peek_count(3), vec!['0', 'x', '1']);
+        assert_eq!(iterator.peek_count(10), vec!['0', 'x', '1', 'F']);
+        assert_eq!(iterator.peek_count(0), vec![]);
+        assert_eq!(iterator.next(), Some(Entry::new('0', 0)));
+        assert_eq!(iterator.peek_count(2), vec!['x', '1']);
+    }
+
+    #[test]
+    fn test_peek_count_works_with_peek_and_next() {
+        let mut iterator = SourceIterator::new("Bar!".to_string());
+
+        assert_eq!(iterator.peek_count(4), vec!['B', 'a', 'r', '!']);
+        assert_eq!(iterator.peek(), Some('B'));
+        assert_eq!(iterator.peek_next(), Some('a'));
+        assert_eq!(iterator.next(), Some(Entry::new('B', 0)));
+        assert_eq!(iterator.next(), Some(Entry::new('a', 1)));
+        assert_eq!(iterator.peek_next(), Some('!'));
+        assert_eq!(iterator.next(), Some(Entry::new('r', 2)));
+        assert_eq!(iterator.next(), Some(Entry::new('!', 3)));
+        assert_eq!(iterator.next(), None);
+    }
+
+    #[test]
+    fn test_new_at_starts_at_offset() {
+        let mut iterator = SourceIterator::new_at("Foo bär", 4);
+
+        assert_eq!(iterator.next(), Some(Entry::new('b', 4)));
+        assert_eq!(iterator.next(), Some(Entry::new('ä', 5)));
+        assert_eq!(iterator.next(), Some(Entry::new('r', 7)));
+        assert_eq!(iterator.next(), None);
+        assert_eq!(iterator.offset(), 8);
+        assert_eq!(iterator.substring(0, 2), "Foo");
+    }
+
+    #[test]
+    fn test_collect_rest_consumes_remaining_input() {
+        let mut iterator = SourceIterator::new("Bär!".to_string());
+        assert_eq!(iterator.remaining_len(), 5);
+
+        iterator.next();
+        assert_eq!(iterator.peek_next(), Some('r'));
+        assert_eq!(iterator.remaining_len(), 4);
+
+        assert_eq!(iterator.collect_rest(), "är!");
+        assert_eq!(iterator.next(), None);
+        assert_eq!(iterator.remaining_len(), 0);
+        assert_eq!(iterator.collect_rest(), "");
+    }
+
+    #[test]
+    fn test_remaining_len_of_segment() {
+        let mut iterator = SourceIterator::new_at("Foo bär", 4);
+        assert_eq!(iterator.remaining_len(), 4);
+
+        iterator.next();
+        assert_eq!(iterator.collect_rest(), "är");
+    }
+
     #[test]
     fn test_substring() {
         let iterator = SourceIterator::new("BarBaz".to_string());
         assert_eq!(iterator.substring(1, 2), "ar");
         assert_eq!(iterator.substring(0, 0), "B");
     }
+
+    #[test]
+    fn test_offset_conversion_ascii() {
+        let iterator = SourceIterator::new("var a;".to_string());
+
+        assert_eq!(iterator.byte_offset_to_char_offset(4), 4);
+        assert_eq!(iterator.char_offset_to_byte_offset(4), 4);
+        assert_eq!(iterator.byte_offset_to_char_offset(100), 6);
+        assert_eq!(iterator.char_offset_to_byte_offset(100), 6);
+    }
+
+    #[test]
+    fn test_offset_conversion_cjk() {
+        let iterator = SourceIterator::new("漢字テスト".to_string());
+
+        assert_eq!(iterator.byte_offset_to_char_offset(6), 2);
+        assert_eq!(iterator.char_offset_to_byte_offset(2), 6);
+        assert_eq!(iterator.byte_offset_to_char_offset(15), 5);
+        assert_eq!(iterator.char_offset_to_byte_offset(5), 15);
+    }
+
+    #[test]
+    fn test_offset_conversion_mixed() {
+        let iterator = SourceIterator::new("a = \"ä漢\";".to_string());
+
+        // 'a', ' ', '=', ' ', '"' are one byte each, 'ä' two and '漢' three.
+        assert_eq!(iterator.char_offset_to_byte_offset(6), 7);
+        assert_eq!(iterator.char_offset_to_byte_offset(7), 10);
+        assert_eq!(iterator.byte_offset_to_char_offset(7), 6);
+        assert_eq!(iterator.byte_offset_to_char_offset(10), 7);
+        // An offset inside 'ä' counts the characters starting before it.
+        assert_eq!(iterator.byte_offset_to_char_offset(6), 6);
+    }
 }
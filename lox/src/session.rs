@@ -0,0 +1,261 @@
+//! `:save` and `:restore`, which keep the plain data globals of a REPL session in a file.
+//!
+//! The file is JSON holding every saved global by name. Values are tagged with their type,
+//! so map keys that are numbers or booleans survive the round trip. Nil, booleans, finite
+//! numbers, strings and arrays and maps of them can be saved. Functions, namespaces,
+//! uninitialized variables and cyclic structures can't, and neither can anything holding
+//! one of them. Arrays and maps are saved by value, so two globals sharing one array restore
+//! as two separate arrays.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::evaluation::Interpreter;
+use crate::value::{MapKey, Value};
+
+#[derive(Serialize, Deserialize)]
+struct Session {
+    globals: BTreeMap<String, SavedValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum SavedValue {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<SavedValue>),
+    Map(Vec<(SavedKey, SavedValue)>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum SavedKey {
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+/// The outcome of [`save`].
+#[derive(Debug, PartialEq)]
+pub(crate) struct SaveReport {
+    pub saved: usize,
+    /// The names of the globals that can't be saved, sorted.
+    pub skipped: Vec<String>,
+}
+
+/// Writes the globals the program defined to `path`, leaving out the natives of the prelude.
+pub(crate) fn save(interpreter: &Interpreter, path: &Path) -> Result<SaveReport, String> {
+    let prelude = interpreter.prelude().names();
+    let mut globals = BTreeMap::new();
+    let mut skipped = Vec::new();
+
+    for variable in interpreter.globals() {
+        if prelude.contains(&variable.name) {
+            continue;
+        }
+
+        match variable
+            .value
+            .and_then(|value| to_saved(&value, &mut Vec::new()))
+        {
+            Some(value) => {
+                globals.insert(variable.name, value);
+            }
+            None => skipped.push(variable.name),
+        }
+    }
+
+    let saved = globals.len();
+    let json =
+        serde_json::to_string_pretty(&Session { globals }).expect("saved values are valid JSON");
+    fs::write(path, json).map_err(|error| error.to_string())?;
+
+    Ok(SaveReport { saved, skipped })
+}
+
+/// Defines the globals saved in `path`, replacing globals of the same name. Returns the
+/// restored names, sorted.
+pub(crate) fn restore(interpreter: &mut Interpreter, path: &Path) -> Result<Vec<String>, String> {
+    let json = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let session: Session = serde_json::from_str(&json).map_err(|error| error.to_string())?;
+
+    let mut names = Vec::new();
+    for (name, value) in session.globals {
+        interpreter.define_global(&name, from_saved(value));
+        names.push(name);
+    }
+
+    Ok(names)
+}
+
+/// Converts `value`, or returns `None` if it can't be saved. `path` holds the arrays and maps
+/// currently being converted, to detect cycles.
+fn to_saved(value: &Value, path: &mut Vec<*const ()>) -> Option<SavedValue> {
+    let saved = match value {
+        Value::Nil => SavedValue::Nil,
+        Value::Boolean(b) => SavedValue::Boolean(*b),
+        Value::Number(n) if n.is_finite() => SavedValue::Number(*n),
+        Value::Str(s) => SavedValue::String(s.to_string()),
+        Value::Array(array) => {
+            enter(path, Rc::as_ptr(array) as *const ())?;
+            let elements = array
+                .borrow()
+                .iter()
+                .map(|element| to_saved(element, path))
+                .collect::<Option<Vec<_>>>()?;
+            path.pop();
+
+            SavedValue::Array(elements)
+        }
+        Value::Map(map) => {
+            enter(path, Rc::as_ptr(map) as *const ())?;
+            let mut entries = map
+                .borrow()
+                .iter()
+                .map(|(key, value)| Some((to_saved_key(key)?, to_saved(value, path)?)))
+                .collect::<Option<Vec<_>>>()?;
+            path.pop();
+
+            // Sorted, so saving the same map twice writes the same file.
+            entries.sort_by_key(|(key, _)| serde_json::to_string(key).unwrap_or_default());
+            SavedValue::Map(entries)
+        }
+        _ => return None,
+    };
+
+    Some(saved)
+}
+
+fn to_saved_key(key: &MapKey) -> Option<SavedKey> {
+    match key.to_value() {
+        Value::Boolean(b) => Some(SavedKey::Boolean(b)),
+        Value::Number(n) if n.is_finite() => Some(SavedKey::Number(n)),
+        Value::Str(s) => Some(SavedKey::String(s.to_string())),
+        _ => None,
+    }
+}
+
+fn enter(path: &mut Vec<*const ()>, pointer: *const ()) -> Option<()> {
+    if path.contains(&pointer) {
+        return None;
+    }
+
+    path.push(pointer);
+    Some(())
+}
+
+fn from_saved(value: SavedValue) -> Value {
+    match value {
+        SavedValue::Nil => Value::Nil,
+        SavedValue::Boolean(b) => Value::Boolean(b),
+        SavedValue::Number(n) => Value::Number(n),
+        SavedValue::String(s) => Value::Str(s.into()),
+        SavedValue::Array(elements) => {
+            let elements = elements.into_iter().map(from_saved).collect();
+            Value::Array(Rc::new(RefCell::new(elements)))
+        }
+        SavedValue::Map(entries) => {
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| (from_saved_key(key), from_saved(value)))
+                .collect();
+            Value::Map(Rc::new(RefCell::new(entries)))
+        }
+    }
+}
+
+fn from_saved_key(key: SavedKey) -> MapKey {
+    match key {
+        SavedKey::Boolean(b) => MapKey::Boolean(b),
+        SavedKey::Number(n) => {
+            // Normalized like keys created by Lox code, see `MapKey`.
+            let n = if n == 0.0 { 0.0 } else { n };
+            MapKey::Number(n.to_bits())
+        }
+        SavedKey::String(s) => MapKey::Str(s.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+    use crate::scanner::Scanner;
+
+    use super::*;
+
+    fn evaluate(interpreter: &mut Interpreter, source: &str) -> Value {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let mut values = interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+        values.pop().unwrap_or(Value::Nil)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.lox-env");
+
+        let mut interpreter = Interpreter::new();
+        evaluate(
+            &mut interpreter,
+            "var n = 1.5; var s = \"hi\"; var b = true; var nothing = nil;
+            var xs = [1, [\"two\"], nil];
+            var m = Map(); m[1] = \"one\"; m[true] = [2]; m[\"k\"] = Map();",
+        );
+        let report = save(&interpreter, &path).unwrap();
+        assert_eq!(
+            report,
+            SaveReport {
+                saved: 6,
+                skipped: vec![]
+            }
+        );
+
+        let mut restored = Interpreter::new();
+        evaluate(&mut restored, "var n = 0; var other = 2;");
+        let names = restore(&mut restored, &path).unwrap();
+        assert_eq!(names, vec!["b", "m", "n", "nothing", "s", "xs"]);
+
+        let source = "[n, s, b, nothing, xs, m[1], m[true], m[\"k\"].keys(), other];";
+        assert_eq!(
+            evaluate(&mut restored, source).to_string(),
+            "[1.5, hi, true, Nil, [1, [two], Nil], one, [2], [], 2]"
+        );
+    }
+
+    #[test]
+    fn test_skips_what_cannot_be_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.lox-env");
+
+        let mut interpreter = Interpreter::new();
+        evaluate(
+            &mut interpreter,
+            "fun f() {} var fs = [f]; var cycle = [1]; cycle.push(cycle); var u; var n = 1;",
+        );
+        let report = save(&interpreter, &path).unwrap();
+        assert_eq!(report.saved, 1);
+        assert_eq!(report.skipped, vec!["cycle", "f", "fs", "u"]);
+
+        let mut restored = Interpreter::new();
+        assert_eq!(restore(&mut restored, &path).unwrap(), vec!["n"]);
+    }
+
+    #[test]
+    fn test_restore_reports_invalid_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.lox-env");
+        let mut interpreter = Interpreter::new();
+
+        assert!(restore(&mut interpreter, &path).is_err());
+
+        fs::write(&path, "{\"globals\": {\"x\": 1}}").unwrap();
+        assert!(restore(&mut interpreter, &path).is_err());
+    }
+}
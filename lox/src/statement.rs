@@ -1,6 +1,8 @@
 use std::rc::Rc;
 
-use crate::expression::ExpressionNode;
+use crate::ast::display::{parameter_list, pattern_to_source, to_source};
+use crate::expression::{tree_prefix, ExpressionNode, Parameters, PatternElement};
+use crate::position::Position;
 
 pub enum Statement {
     Print(ExpressionNode),
@@ -9,6 +11,10 @@ pub enum Statement {
         name: String,
         initializer: Option<ExpressionNode>,
     },
+    VarPattern {
+        pattern: Vec<PatternElement>,
+        initializer: ExpressionNode,
+    },
     Block(Vec<Statement>),
     If {
         condition: ExpressionNode,
@@ -18,11 +24,159 @@ pub enum Statement {
     While {
         condition: ExpressionNode,
         body: Box<Statement>,
+        increment: Option<ExpressionNode>,
+        /// Whether the parser synthesized this loop from a `for` loop.
+        desugared_for: bool,
+    },
+    Loop {
+        body: Box<Statement>,
+        position: Position,
     },
+    Break,
+    Continue,
     Function {
         name: String,
-        parameters: Vec<String>,
+        parameters: Parameters,
         body: Rc<Statement>,
     },
     Return(Option<ExpressionNode>),
+    Assert {
+        condition: ExpressionNode,
+        message: Option<ExpressionNode>,
+    },
+}
+
+impl Statement {
+    /// Renders the statement as an indented tree. Expressions are rendered with their spans.
+    pub fn pretty(&self) -> String {
+        self.pretty_at(0)
+    }
+
+    /// Renders the statement as one line of Lox source, without the statements nested in it:
+    /// a loop is rendered as its header and a block as `{ ... }`.
+    pub(crate) fn summary(&self) -> String {
+        match self {
+            Statement::Print(expr) => format!("print {};", to_source(expr)),
+            Statement::Expression(expr) => format!("{};", to_source(expr)),
+            Statement::Var {
+                name,
+                initializer: Some(initializer),
+            } => format!("var {name} = {};", to_source(initializer)),
+            Statement::Var { name, .. } => format!("var {name};"),
+            Statement::VarPattern {
+                pattern,
+                initializer,
+            } => format!(
+                "var {} = {};",
+                pattern_to_source(pattern),
+                to_source(initializer)
+            ),
+            Statement::Block(_) => "{ ... }".to_string(),
+            Statement::If { condition, .. } => format!("if ({})", to_source(condition)),
+            Statement::While {
+                condition,
+                increment,
+                desugared_for: true,
+                ..
+            } => {
+                let increment = increment.as_ref().map(to_source).unwrap_or_default();
+                format!("for (; {}; {increment})", to_source(condition))
+            }
+            Statement::While { condition, .. } => format!("while ({})", to_source(condition)),
+            Statement::Loop { .. } => "loop".to_string(),
+            Statement::Break => "break;".to_string(),
+            Statement::Continue => "continue;".to_string(),
+            Statement::Function {
+                name, parameters, ..
+            } => format!("fun {name}({})", parameter_list(parameters)),
+            Statement::Return(Some(value)) => format!("return {};", to_source(value)),
+            Statement::Return(None) => "return;".to_string(),
+            Statement::Assert {
+                condition,
+                message: Some(message),
+            } => format!("assert {}, {};", to_source(condition), to_source(message)),
+            Statement::Assert { condition, .. } => format!("assert {};", to_source(condition)),
+        }
+    }
+
+    fn pretty_at(&self, level: u32) -> String {
+        let expression = |expr: &ExpressionNode| expr.pretty_at(level + 1);
+        let statement = |stmt: &Statement| stmt.pretty_at(level + 1);
+
+        let (name, children) = match self {
+            Statement::Print(expr) => ("Print".to_string(), vec![expression(expr)]),
+            Statement::Expression(expr) => ("Expression".to_string(), vec![expression(expr)]),
+            Statement::Var { name, initializer } => (
+                format!("Var {name}"),
+                initializer.iter().map(expression).collect(),
+            ),
+            Statement::VarPattern {
+                pattern,
+                initializer,
+            } => {
+                let names = pattern
+                    .iter()
+                    .map(|element| element.name.as_deref().unwrap_or("_"))
+                    .collect::<Vec<_>>();
+                (
+                    format!("Var [{}]", names.join(", ")),
+                    vec![expression(initializer)],
+                )
+            }
+            Statement::Block(statements) => (
+                "Block".to_string(),
+                statements.iter().map(statement).collect(),
+            ),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut children = vec![expression(condition), statement(then_branch)];
+                children.extend(else_branch.iter().map(|stmt| statement(stmt)));
+                ("If".to_string(), children)
+            }
+            Statement::While {
+                condition,
+                body,
+                increment,
+                desugared_for,
+            } => {
+                let name = match desugared_for {
+                    true => "While (desugared from for)",
+                    false => "While",
+                };
+                let mut children = vec![expression(condition), statement(body)];
+                children.extend(increment.iter().map(expression));
+                (name.to_string(), children)
+            }
+            Statement::Loop { body, position } => (
+                format!("Loop ({}:{})", position.absolute, position.length),
+                vec![statement(body)],
+            ),
+            Statement::Break => ("Break".to_string(), vec![]),
+            Statement::Continue => ("Continue".to_string(), vec![]),
+            Statement::Function {
+                name,
+                parameters,
+                body,
+            } => (
+                format!("Function {name}({parameters})"),
+                vec![statement(body)],
+            ),
+            Statement::Return(value) => {
+                ("Return".to_string(), value.iter().map(expression).collect())
+            }
+            Statement::Assert { condition, message } => {
+                let mut children = vec![expression(condition)];
+                children.extend(message.iter().map(expression));
+                ("Assert".to_string(), children)
+            }
+        };
+
+        let mut lines = vec![format!("{}{name}", tree_prefix(level))];
+        lines.extend(children);
+
+        lines.join("\n")
+    }
 }
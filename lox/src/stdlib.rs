@@ -0,0 +1,1057 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::builtins::{format, iter, json, list, pattern};
+use crate::callable::{NativeContext, NativeFunction, NativeFunctionContainer};
+use crate::environment::Environment;
+use crate::error::RuntimeError;
+use crate::evaluation::{EvaluationResult, Interpreter, ReturnOrError};
+use crate::scanner::number_parser::parse_number_str;
+use crate::value::{MapKey, Value};
+
+/// Configures which natives are registered as globals. Natives with access to the outside
+/// world are behind capability flags so sandboxed interpreters can leave them out.
+#[derive(Clone)]
+pub struct Prelude {
+    environment_variables: bool,
+    file_io: bool,
+    /// The arguments returned by `args()`.
+    script_args: Rc<[String]>,
+}
+
+impl Prelude {
+    /// A prelude with the default capabilities: environment variables are readable, file
+    /// IO is disabled.
+    pub fn new() -> Prelude {
+        Prelude {
+            environment_variables: true,
+            file_io: false,
+            script_args: Rc::new([]),
+        }
+    }
+
+    /// A prelude without any capabilities, for running untrusted code.
+    pub fn sandboxed() -> Prelude {
+        Prelude {
+            environment_variables: false,
+            file_io: false,
+            script_args: Rc::new([]),
+        }
+    }
+
+    /// Whether `env()` is available to read environment variables.
+    pub fn with_environment_variables(mut self, enabled: bool) -> Prelude {
+        self.environment_variables = enabled;
+        self
+    }
+
+    /// Whether `readFile()` and `writeFile()` are available.
+    pub fn with_file_io(mut self, enabled: bool) -> Prelude {
+        self.file_io = enabled;
+        self
+    }
+
+    /// The arguments `args()` returns, those given to the script on the command line.
+    pub fn with_script_args(mut self, args: Vec<String>) -> Prelude {
+        self.script_args = args.into();
+        self
+    }
+
+    pub(crate) fn register_globals(&self, env: &mut Environment) {
+        register_globals(env);
+
+        if self.environment_variables {
+            register(env, "env", 1..=2, env_var);
+        }
+        if self.file_io {
+            register(env, "readFile", 1..=1, read_file);
+            register(env, "writeFile", 2..=2, write_file);
+        }
+    }
+
+    /// The names of the globals this prelude registers.
+    pub(crate) fn names(&self) -> HashSet<String> {
+        let mut env = Environment::empty();
+        self.register_globals(&mut env);
+        env.variable_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Explains why `name` is not defined if it is a native left out by this prelude.
+    pub(crate) fn disabled_reason(&self, name: &str) -> Option<&'static str> {
+        match name {
+            "env" if !self.environment_variables => Some("environment variables are disabled"),
+            "readFile" | "writeFile" if !self.file_io => {
+                Some("file IO is disabled; run with --allow-io")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for Prelude {
+    fn default() -> Self {
+        Prelude::new()
+    }
+}
+
+/// The source of time for `clockMillis()` and `sleep()`, see `Interpreter::with_clock`.
+pub trait Clock {
+    /// The number of milliseconds since the Unix epoch.
+    fn now_millis(&self) -> f64;
+
+    fn sleep(&self, duration: Duration);
+}
+
+/// The clock of the operating system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |since_epoch| since_epoch.as_millis() as f64)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+fn register_globals(env: &mut Environment) {
+    register(env, "parseNumber", 1..=2, parse_number);
+    register(env, "ord", 1..=1, ord);
+    register(env, "chr", 1..=1, chr);
+    register(env, "Map", 0..=0, map);
+    register(env, "List", 0..=0, list::new_list);
+    register(env, "sort", 1..=1, list::sort);
+    register(env, "sortBy", 2..=2, list::sort_by);
+    register(env, "forEach", 2..=2, iter::for_each);
+    register(env, "mapArray", 2..=2, iter::map_array);
+    register(env, "filterArray", 2..=2, iter::filter_array);
+    register(env, "reduce", 3..=3, iter::reduce);
+    register(env, "stats", 0..=0, stats);
+    register(env, "arity", 1..=1, arity);
+    register(env, "slice", 2..=3, slice);
+    register(env, "printRaw", 1..=1, print_raw);
+    register(env, "clockMillis", 0..=0, clock_millis);
+    register(env, "sleep", 1..=1, sleep);
+    register(env, "panic", 1..=1, panic);
+    register(env, "exit", 0..=1, exit);
+    register(env, "exitWithError", 1..=1, exit_with_error);
+    register(env, "args", 0..=0, args);
+    register(env, "assertEq", 2..=2, assert_eq);
+    register(env, "assertNe", 2..=2, assert_ne);
+    register(env, "assertClose", 3..=3, assert_close);
+    register(env, "jsonParse", 1..=1, json::json_parse);
+    register(env, "jsonStringify", 1..=2, json::json_stringify);
+    register(env, "matches", 2..=2, pattern::matches);
+    register(env, "find", 2..=2, pattern::find);
+    register(env, "findAll", 2..=2, pattern::find_all);
+    register(env, "format", 1..=usize::MAX, format::format);
+
+    #[cfg(feature = "random")]
+    {
+        use crate::builtins::random;
+        register(env, "rand", 0..=0, random::rand);
+        register(env, "randInt", 2..=2, random::rand_int);
+        register(env, "seed", 1..=1, random::seed);
+    }
+}
+
+fn register(
+    env: &mut Environment,
+    name: &str,
+    arity: RangeInclusive<usize>,
+    function: NativeFunction,
+) {
+    let container = NativeFunctionContainer::new(name, arity, function);
+    env.register(name.to_string(), Some(Value::Function(Rc::new(container))));
+}
+
+/// `parseNumber(text, radix?)` parses `text` (ignoring surrounding whitespace) in the
+/// given radix, which defaults to 10. Returns `nil` if `text` is not a valid number.
+///
+/// Radix 10 accepts the scanner's number grammar plus an optional sign and exponent,
+/// every other radix only accepts integers.
+fn parse_number(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let text = ctx.string(0)?;
+    let radix = match ctx.argument_count() {
+        2 => {
+            let value = ctx.integer(1)?;
+            if !(2..=36).contains(&value) {
+                return Err(ctx.invalid_argument(1, "radix must be between 2 and 36".to_string()));
+            }
+            value as u32
+        }
+        _ => 10,
+    };
+
+    let text = text.trim();
+    let parsed = if radix == 10 {
+        parse_decimal(text)
+    } else {
+        i64::from_str_radix(text, radix).ok().map(|n| n as f64)
+    };
+
+    Ok(parsed.map_or(Value::Nil, Value::Number))
+}
+
+/// `ord(character)` returns the Unicode scalar value of a single-character string.
+fn ord(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let text = ctx.string(0)?;
+    let mut chars = text.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Value::Number(c as u32 as f64)),
+        _ => Err(ctx.invalid_argument(
+            0,
+            format!(
+                "expected a single character but found {} characters",
+                text.chars().count()
+            ),
+        )),
+    }
+}
+
+/// `chr(code)` returns the single-character string for a Unicode scalar value.
+fn chr(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let code = ctx.integer(0)?;
+
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(c) => Ok(Value::Str(c.to_string().into())),
+        None => Err(ctx.invalid_argument_with_help(
+            0,
+            format!("{code} is not a valid Unicode scalar value"),
+            "scalar values range from 0 to 1114111, excluding 55296 to 57343".to_string(),
+        )),
+    }
+}
+
+/// `Map()` creates an empty map. Keys are restricted to strings, numbers and booleans.
+fn map(_: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Map(Rc::new(RefCell::new(HashMap::new()))))
+}
+
+/// `stats()` returns a map of the interpreter's instrumentation counters.
+fn stats(_: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    let entries = interpreter
+        .stats()
+        .entries()
+        .into_iter()
+        .map(|(name, count)| (MapKey::Str(name.into()), Value::Number(count as f64)))
+        .collect();
+
+    Ok(Value::Map(Rc::new(RefCell::new(entries))))
+}
+
+/// `arity(f)` returns the number of arguments `f` requires, or `nil` if `f` is not a
+/// function.
+fn arity(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    match ctx.arguments[0].value.arity() {
+        Some(arity) => Ok(Value::Number(arity as f64)),
+        None => Ok(Value::Nil),
+    }
+}
+
+/// `slice(array, start, end?)` returns a new array with the elements from `start` up to,
+/// but not including, `end`. `end` defaults to the array's length and both bounds are
+/// clamped to the array.
+fn slice(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let array = ctx.array(0)?;
+    let array = array.borrow();
+
+    let clamp = |index: usize| -> EvaluationResult<usize> {
+        Ok(ctx.integer(index)?.clamp(0, array.len() as i64) as usize)
+    };
+    let start = clamp(1)?;
+    let end = match ctx.argument_count() {
+        3 => clamp(2)?,
+        _ => array.len(),
+    };
+
+    let elements = array[start..end.max(start)].to_vec();
+    Ok(Value::Array(Rc::new(RefCell::new(elements))))
+}
+
+/// `printRaw(value)` prints `value` like the `print` statement, but without a trailing
+/// newline.
+fn print_raw(ctx: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    interpreter.print_raw(&ctx.arguments[0].to_string());
+    Ok(Value::Nil)
+}
+
+/// `clockMillis()` returns the number of milliseconds since the Unix epoch.
+fn clock_millis(_: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    Ok(Value::Number(interpreter.clock().now_millis()))
+}
+
+/// `sleep(ms)` pauses the program for `ms` milliseconds.
+fn sleep(ctx: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    let millis = ctx.number(0)?;
+    if millis < 0.0 || millis.is_nan() {
+        return Err(ctx.invalid_argument(0, "cannot sleep a negative duration".to_string()));
+    }
+
+    interpreter
+        .clock()
+        .sleep(Duration::from_millis(millis as u64));
+    Ok(Value::Nil)
+}
+
+/// `panic(message)` stops the program with a `UserPanic` error. `message` may be any value,
+/// it is converted to a string.
+fn panic(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let message = ctx.arguments[0].value.to_string();
+    Err(ReturnOrError::Error(RuntimeError::user_panic(
+        message,
+        ctx.position,
+    )))
+}
+
+/// `exit(code?)` stops the program, and with it the `lox` process, with the exit code `code`
+/// (0 if not given). It is raised as an `Exit` error, so the interpreter unwinds normally.
+fn exit(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let code = match ctx.arguments.is_empty() {
+        true => 0,
+        false => i32::try_from(ctx.integer(0)?)
+            .map_err(|_| ctx.invalid_argument(0, "exit code must fit in 32 bits".to_string()))?,
+    };
+
+    Err(ReturnOrError::Error(RuntimeError::exit(code, None)))
+}
+
+/// `exitWithError(message)` prints `message` to stderr and exits with code 1. `message` may
+/// be any value, it is converted to a string.
+fn exit_with_error(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let message = ctx.arguments[0].value.to_string();
+    Err(ReturnOrError::Error(RuntimeError::exit(1, Some(message))))
+}
+
+/// `assertEq(actual, expected)` fails with an `AssertionFailed` error unless both values are
+/// equal, the same way `==` compares them.
+fn assert_eq(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let (actual, expected) = (&ctx.arguments[0], &ctx.arguments[1]);
+    match actual.equals(expected)? {
+        Value::Boolean(true) => Ok(Value::Nil),
+        _ => assertion_failed(
+            &ctx,
+            format!("expected {} == got {}", expected.value, actual.value),
+        ),
+    }
+}
+
+/// `assertNe(actual, unexpected)` fails with an `AssertionFailed` error if both values are
+/// equal, the same way `==` compares them.
+fn assert_ne(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let (actual, unexpected) = (&ctx.arguments[0], &ctx.arguments[1]);
+    match actual.not_equals(unexpected)? {
+        Value::Boolean(true) => Ok(Value::Nil),
+        _ => assertion_failed(
+            &ctx,
+            format!("expected {} != got {}", unexpected.value, actual.value),
+        ),
+    }
+}
+
+/// `assertClose(actual, expected, epsilon)` fails with an `AssertionFailed` error unless the
+/// numbers differ by at most `epsilon`.
+fn assert_close(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let (actual, expected, epsilon) = (ctx.number(0)?, ctx.number(1)?, ctx.number(2)?);
+    if (actual - expected).abs() <= epsilon {
+        return Ok(Value::Nil);
+    }
+
+    assertion_failed(
+        &ctx,
+        format!(
+            "expected {} ~= got {} (epsilon {})",
+            Value::Number(expected),
+            Value::Number(actual),
+            Value::Number(epsilon)
+        ),
+    )
+}
+
+fn assertion_failed(ctx: &NativeContext, message: String) -> EvaluationResult<Value> {
+    Err(ReturnOrError::Error(RuntimeError::assertion_failed(
+        Some(message),
+        ctx.position.clone(),
+    )))
+}
+
+/// `env(name, default?)` returns the value of the environment variable `name`, or `default`
+/// (`nil` if not given) when it is not set. Values that are not valid UTF-8 are converted
+/// lossily, replacing invalid sequences with `U+FFFD`.
+fn env_var(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let name = ctx.string(0)?;
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(ctx.invalid_argument(0, "not a valid environment variable name".to_string()));
+    }
+
+    match std::env::var_os(&*name) {
+        Some(value) => Ok(Value::Str(value.to_string_lossy().into())),
+        None => Ok(ctx.arguments.get(1).map_or(Value::Nil, |d| d.value.clone())),
+    }
+}
+
+/// `args()` returns the arguments given to the script after `--` on the command line, as a
+/// new array of strings.
+fn args(_: NativeContext, interpreter: &mut Interpreter) -> EvaluationResult<Value> {
+    let args = interpreter
+        .prelude()
+        .script_args
+        .iter()
+        .map(|arg| Value::Str(arg.as_str().into()))
+        .collect();
+
+    Ok(Value::Array(Rc::new(RefCell::new(args))))
+}
+
+/// `readFile(path)` returns the contents of the file at `path`.
+fn read_file(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let path = ctx.string(0)?;
+
+    match std::fs::read_to_string(&*path) {
+        Ok(text) => Ok(Value::Str(text.into())),
+        Err(error) => Err(ctx.invalid_argument(0, format!("could not read {path}: {error}"))),
+    }
+}
+
+/// `writeFile(path, text)` replaces the contents of the file at `path` with `text`.
+fn write_file(ctx: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+    let path = ctx.string(0)?;
+    let text = ctx.string(1)?;
+
+    match std::fs::write(&*path, &*text) {
+        Ok(()) => Ok(Value::Nil),
+        Err(error) => Err(ctx.invalid_argument(0, format!("could not write {path}: {error}"))),
+    }
+}
+
+/// Parses an optional sign followed by a number literal, which is read with the scanner's
+/// number parser. Like in the scanner, a `.` has to be followed by a digit.
+fn parse_decimal(text: &str) -> Option<f64> {
+    let (sign, literal) = match text.strip_prefix('-') {
+        Some(literal) => (-1.0, literal),
+        None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if literal
+        .split('.')
+        .skip(1)
+        .any(|fraction| !fraction.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    parse_number_str(literal).ok().map(|number| sign * number)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{LoxError, RuntimeError};
+    use crate::evaluation::evaluate_source;
+    use crate::parser::parse;
+    use crate::scanner::Scanner;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_number_decimal() {
+        assert_eq!(
+            evaluate_source("parseNumber(\"42\");").unwrap(),
+            Value::Number(42.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"3.25\");").unwrap(),
+            Value::Number(3.25)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"-1.5e3\");").unwrap(),
+            Value::Number(-1500.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"1_000\");").unwrap(),
+            Value::Number(1000.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"42\", 10);").unwrap(),
+            Value::Number(42.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_number_radix() {
+        assert_eq!(
+            evaluate_source("parseNumber(\"ff\", 16);").unwrap(),
+            Value::Number(255.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"1010\", 2);").unwrap(),
+            Value::Number(10.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"z\", 36);").unwrap(),
+            Value::Number(35.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"17\", 8);").unwrap(),
+            Value::Number(15.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_number_ignores_surrounding_whitespace() {
+        assert_eq!(
+            evaluate_source("parseNumber(\"  42 \");").unwrap(),
+            Value::Number(42.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"\tff\n\", 16);").unwrap(),
+            Value::Number(255.0)
+        );
+        assert_eq!(
+            evaluate_source("parseNumber(\"4 2\");").unwrap(),
+            Value::Nil
+        );
+    }
+
+    #[test]
+    fn test_parse_number_returns_nil_on_garbage() {
+        for input in [
+            "", "abc", "12abc", "1.", "1.e5", ".5", "1e", "inf", "NaN", "--1", "+-1",
+        ] {
+            let source = format!("parseNumber(\"{input}\");");
+            assert_eq!(evaluate_source(&source).unwrap(), Value::Nil, "{input}");
+        }
+
+        assert_eq!(
+            evaluate_source("parseNumber(\"12\", 2);").unwrap(),
+            Value::Nil
+        );
+    }
+
+    #[test]
+    fn test_parse_number_rejects_fractional_input_for_radix() {
+        assert_eq!(
+            evaluate_source("parseNumber(\"1.5\", 16);").unwrap(),
+            Value::Nil
+        );
+    }
+
+    #[test]
+    fn test_parse_number_rejects_invalid_radix() {
+        for radix in ["1", "37"] {
+            let source = format!("parseNumber(\"1\", {radix});");
+            assert!(matches!(
+                evaluate_source(&source),
+                Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+            ));
+        }
+
+        assert!(matches!(
+            evaluate_source("parseNumber(\"1\", 2.5);"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_ord() {
+        assert_eq!(evaluate_source("ord(\"A\");").unwrap(), Value::Number(65.0));
+        assert_eq!(
+            evaluate_source("ord(\"ß\");").unwrap(),
+            Value::Number(223.0)
+        );
+    }
+
+    #[test]
+    fn test_ord_requires_a_single_character() {
+        for (input, length) in [("", 0), ("ab", 2), ("äöü", 3)] {
+            let source = format!("ord(\"{input}\");");
+            match evaluate_source(&source) {
+                Err(LoxError::RuntimeError(RuntimeError::NativeError {
+                    reason, position, ..
+                })) => {
+                    assert_eq!(
+                        reason,
+                        format!("expected a single character but found {length} characters")
+                    );
+                    assert_eq!(position.absolute, 4);
+                }
+                _ => panic!("expected an invalid argument error for {input:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_chr() {
+        assert_eq!(evaluate_source("chr(97);").unwrap(), Value::Str("a".into()));
+        assert_eq!(
+            evaluate_source("chr(ord(\"€\"));").unwrap(),
+            Value::Str("€".into())
+        );
+    }
+
+    #[test]
+    fn test_chr_rejects_invalid_scalar_values() {
+        for code in ["-1", "55296", "1114112"] {
+            let source = format!("chr({code});");
+            assert!(matches!(
+                evaluate_source(&source),
+                Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+            ));
+        }
+
+        assert!(matches!(
+            evaluate_source("chr(97.5);"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_string_repeat_requires_an_integer() {
+        assert_eq!(
+            evaluate_source("\"ab\" * 3;").unwrap(),
+            Value::Str("ababab".into())
+        );
+        assert!(matches!(
+            evaluate_source("\"ab\" * 1.5;"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+        assert!(matches!(
+            evaluate_source("\"ab\" * -1;"),
+            Err(LoxError::RuntimeError(RuntimeError::InvalidArgument { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_string_repeat_is_bounded() {
+        assert!(matches!(
+            evaluate_source("\"a\" * 1000000000000000000000000000000;"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+        for source in ["\"ab\" * 9007199254740992;", "\"a\" * 2147483648;"] {
+            assert!(
+                matches!(
+                    evaluate_source(source),
+                    Err(LoxError::RuntimeError(RuntimeError::InvalidArgument { .. }))
+                ),
+                "{source}"
+            );
+        }
+        assert_eq!(
+            evaluate_source("\"\" * 1000000000000000;").unwrap(),
+            Value::Str("".into())
+        );
+    }
+
+    #[test]
+    fn test_integer_arguments_must_fit_an_i64() {
+        assert!(matches!(
+            evaluate_source("chr(1000000000000000000000000000000);"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+        assert!(matches!(
+            evaluate_source("[1, 2][9223372036854775808];"),
+            Err(LoxError::RuntimeError(RuntimeError::TypeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_number_checks_arity() {
+        assert!(matches!(
+            evaluate_source("parseNumber();"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_arity() {
+        assert_eq!(
+            evaluate_source("fun f(a, b, c) {} arity(f);").unwrap(),
+            Value::Number(3.0)
+        );
+        assert_eq!(
+            evaluate_source("arity(fun (a, ...rest) {});").unwrap(),
+            Value::Number(1.0)
+        );
+        assert_eq!(
+            evaluate_source("arity(parseNumber);").unwrap(),
+            Value::Number(1.0)
+        );
+        assert_eq!(
+            evaluate_source("arity(List().push);").unwrap(),
+            Value::Number(1.0)
+        );
+        assert_eq!(evaluate_source("arity(3);").unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_slice() {
+        assert_eq!(
+            evaluate_source("slice([1, 2, 3], 1);").unwrap().to_string(),
+            "[2, 3]"
+        );
+        assert_eq!(
+            evaluate_source("slice([1, 2, 3], 0, 2);")
+                .unwrap()
+                .to_string(),
+            "[1, 2]"
+        );
+        assert_eq!(
+            evaluate_source("slice([1, 2, 3], 5, -1);")
+                .unwrap()
+                .to_string(),
+            "[]"
+        );
+    }
+
+    /// A clock that only advances when slept on.
+    #[derive(Clone, Default)]
+    struct FakeClock(Rc<RefCell<f64>>);
+
+    impl Clock for FakeClock {
+        fn now_millis(&self) -> f64 {
+            *self.0.borrow()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            *self.0.borrow_mut() += duration.as_millis() as f64;
+        }
+    }
+
+    fn evaluate_with_clock(source: &str, clock: FakeClock) -> Result<Value, LoxError> {
+        let tokens = Scanner::new(source.to_string()).scan();
+        let mut interpreter = Interpreter::new().with_clock(Box::new(clock));
+        let mut values = interpreter.evaluate(&parse(&tokens)?)?;
+        Ok(values.pop().unwrap_or(Value::Nil))
+    }
+
+    #[test]
+    fn test_sleep_advances_the_clock() {
+        let clock = FakeClock::default();
+        *clock.0.borrow_mut() = 1000.0;
+
+        let source = "var start = clockMillis(); sleep(250); sleep(0); clockMillis() - start;";
+        assert_eq!(
+            evaluate_with_clock(source, clock.clone()).unwrap(),
+            Value::Number(250.0)
+        );
+        assert_eq!(clock.now_millis(), 1250.0);
+    }
+
+    #[test]
+    fn test_sleep_rejects_negative_durations() {
+        match evaluate_with_clock("sleep(-1);", FakeClock::default()) {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { reason, .. })) => {
+                assert_eq!(reason, "cannot sleep a negative duration")
+            }
+            result => panic!("expected a native error, got {result:?}"),
+        }
+        assert!(evaluate_with_clock("sleep(\"1\");", FakeClock::default()).is_err());
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn captured_output(source: &str) -> String {
+        let buffer = SharedBuffer::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+
+        let tokens = Scanner::new(source.to_string()).scan();
+        interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+
+        let output = buffer.0.borrow().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_print_raw_writes_without_newline() {
+        assert_eq!(
+            captured_output("printRaw(\"[\"); printRaw(1); printRaw(\"]\");"),
+            "[1]"
+        );
+        assert_eq!(
+            captured_output("printRaw(\"a\"); print \"b\"; printRaw(\"c\");"),
+            "ab\nc"
+        );
+    }
+
+    #[test]
+    fn test_env() {
+        std::env::set_var("LOX_TEST_ENV_SET", "value");
+        std::env::remove_var("LOX_TEST_ENV_UNSET");
+
+        assert_eq!(
+            evaluate_source("env(\"LOX_TEST_ENV_SET\");").unwrap(),
+            Value::Str("value".into())
+        );
+        assert_eq!(
+            evaluate_source("env(\"LOX_TEST_ENV_SET\", \"default\");").unwrap(),
+            Value::Str("value".into())
+        );
+        assert_eq!(
+            evaluate_source("env(\"LOX_TEST_ENV_UNSET\");").unwrap(),
+            Value::Nil
+        );
+        assert_eq!(
+            evaluate_source("env(\"LOX_TEST_ENV_UNSET\", \"default\");").unwrap(),
+            Value::Str("default".into())
+        );
+        assert!(matches!(
+            evaluate_source("env(\"A=B\");"),
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_args() {
+        let prelude = Prelude::new().with_script_args(vec!["a".to_string(), "b c".to_string()]);
+        let mut interpreter = Interpreter::with_prelude(prelude, Box::new(std::io::sink()));
+        let tokens = Scanner::new("var a = args(); a.push(1); args();".to_string()).scan();
+        let values = interpreter.evaluate(&parse(&tokens).unwrap()).unwrap();
+
+        assert_eq!(values[2].to_string(), "[a, b c]");
+        assert_eq!(evaluate_source("args();").unwrap().to_string(), "[]");
+    }
+
+    #[test]
+    fn test_sandboxed_prelude_has_no_env() {
+        let mut interpreter =
+            Interpreter::with_prelude(Prelude::sandboxed(), Box::new(std::io::sink()));
+        let tokens = Scanner::new("env(\"HOME\");".to_string()).scan();
+
+        assert!(matches!(
+            interpreter.evaluate(&parse(&tokens).unwrap()),
+            Err(LoxError::RuntimeError(RuntimeError::Disabled { .. }))
+        ));
+    }
+
+    fn evaluate_with_io(source: &str) -> Result<Value, LoxError> {
+        let prelude = Prelude::new().with_file_io(true);
+        let mut interpreter = Interpreter::with_prelude(prelude, Box::new(std::io::sink()));
+        let tokens = Scanner::new(source.to_string()).scan();
+        let mut values = interpreter.evaluate(&parse(&tokens)?)?;
+        Ok(values.pop().unwrap_or(Value::Nil))
+    }
+
+    #[test]
+    fn test_write_and_read_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let path = path.to_str().unwrap();
+
+        let source = format!("writeFile(\"{path}\", \"hello\"); readFile(\"{path}\");");
+        assert_eq!(
+            evaluate_with_io(&source).unwrap(),
+            Value::Str("hello".into())
+        );
+    }
+
+    #[test]
+    fn test_read_missing_file_reports_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.txt");
+        let path = path.to_str().unwrap();
+
+        match evaluate_with_io(&format!("readFile(\"{path}\");")) {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError {
+                reason, position, ..
+            })) => {
+                assert!(reason.starts_with(&format!("could not read {path}: ")));
+                assert_eq!(position.absolute, 9);
+            }
+            _ => panic!("expected an invalid argument error"),
+        }
+    }
+
+    #[test]
+    fn test_file_io_is_disabled_by_default() {
+        match evaluate_source("readFile(\"data.txt\");") {
+            Err(LoxError::RuntimeError(RuntimeError::Disabled { reason, .. })) => {
+                assert_eq!(reason, "file IO is disabled; run with --allow-io")
+            }
+            _ => panic!("expected file IO to be disabled"),
+        }
+    }
+
+    fn render(source: &str) -> String {
+        let error = evaluate_source(source).unwrap_err();
+        let report = miette::Report::new(error).with_source_code(source.to_string());
+        let mut rendered = String::new();
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        rendered
+    }
+
+    #[test]
+    fn test_native_errors_render_uniformly() {
+        let ord = render("ord(123);");
+        let chr = render("chr(\"a\");");
+
+        assert!(ord.contains("invalid call to ord"), "{ord}");
+        assert!(ord.contains("expected String, found Number"), "{ord}");
+        assert!(chr.contains("invalid call to chr"), "{chr}");
+        assert!(chr.contains("expected Number, found String"), "{chr}");
+
+        let shape = |rendered: &str| {
+            rendered
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '"' {
+                        'x'
+                    } else {
+                        c
+                    }
+                })
+                .collect::<String>()
+        };
+        assert_eq!(shape(&ord), shape(&chr));
+    }
+
+    #[test]
+    fn test_native_errors_can_carry_help() {
+        match evaluate_source("chr(55296);") {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError { help, .. })) => {
+                assert!(help.unwrap().starts_with("scalar values range"))
+            }
+            _ => panic!("expected a native error"),
+        }
+    }
+
+    #[test]
+    fn test_panic() {
+        match evaluate_source("print 1;\npanic(\"unreachable\");") {
+            Err(LoxError::RuntimeError(RuntimeError::UserPanic { message, position })) => {
+                assert_eq!(message, "unreachable");
+                assert_eq!(position, crate::position::Position::new(14, 15));
+            }
+            _ => panic!("expected a user panic"),
+        }
+
+        match evaluate_source("panic([1, 2]);") {
+            Err(LoxError::RuntimeError(RuntimeError::UserPanic { message, .. })) => {
+                assert_eq!(message, "[1, 2]")
+            }
+            _ => panic!("expected a user panic"),
+        }
+    }
+
+    fn assertion_message(source: &str) -> String {
+        match evaluate_source(source) {
+            Err(LoxError::RuntimeError(RuntimeError::AssertionFailed { message, .. })) => message,
+            _ => panic!("expected an assertion failure"),
+        }
+    }
+
+    #[test]
+    fn test_assert_eq() {
+        assert_eq!(evaluate_source("assertEq(1 + 2, 3);").unwrap(), Value::Nil);
+        assert_eq!(
+            assertion_message("assertEq(3, 5);"),
+            "assertion failed: expected 5 == got 3"
+        );
+        assert_eq!(
+            assertion_message("assertEq(\"a\", [1]);"),
+            "assertion failed: expected [1] == got a"
+        );
+    }
+
+    #[test]
+    fn test_assert_ne() {
+        assert_eq!(evaluate_source("assertNe(1, 2);").unwrap(), Value::Nil);
+        assert_eq!(
+            assertion_message("assertNe(nil, nil);"),
+            "assertion failed: expected Nil != got Nil"
+        );
+    }
+
+    #[test]
+    fn test_assert_close() {
+        assert_eq!(
+            evaluate_source("assertClose(0.1 + 0.2, 0.3, 0.000001);").unwrap(),
+            Value::Nil
+        );
+        assert_eq!(
+            assertion_message("assertClose(3.2, 3, 0.1);"),
+            "assertion failed: expected 3 ~= got 3.2 (epsilon 0.1)"
+        );
+    }
+
+    #[test]
+    fn test_assertions_label_the_call() {
+        match evaluate_source("print 1;\nassertEq(1, 2);") {
+            Err(LoxError::RuntimeError(RuntimeError::AssertionFailed { position, .. })) => {
+                assert_eq!(position, crate::position::Position::new(17, 6));
+            }
+            _ => panic!("expected an assertion failure"),
+        }
+    }
+
+    #[test]
+    fn test_exit() {
+        for (source, expected) in [("exit();", 0), ("exit(3);", 3), ("exit(-1);", -1)] {
+            match evaluate_source(source) {
+                Err(LoxError::RuntimeError(RuntimeError::Exit { code, message })) => {
+                    assert_eq!((code, message), (expected, None), "{source}")
+                }
+                _ => panic!("expected an exit"),
+            }
+        }
+
+        for source in ["exit(1.5);", "exit(\"1\");", "exit(4294967296);"] {
+            assert!(
+                !matches!(
+                    evaluate_source(source),
+                    Err(LoxError::RuntimeError(RuntimeError::Exit { .. }))
+                ),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_exit_with_error() {
+        match evaluate_source("exitWithError([\"bad\", 1]); print 1;") {
+            Err(LoxError::RuntimeError(RuntimeError::Exit { code, message })) => {
+                assert_eq!(code, 1);
+                assert_eq!(message.as_deref(), Some("[bad, 1]"));
+            }
+            _ => panic!("expected an exit"),
+        }
+    }
+
+    #[test]
+    fn test_arity_errors_label_the_call() {
+        match evaluate_source("ord(\"a\", \"b\");") {
+            Err(LoxError::RuntimeError(RuntimeError::NativeError {
+                function,
+                reason,
+                position,
+                ..
+            })) => {
+                assert_eq!(function, "ord");
+                assert_eq!(reason, "expected 1 arguments but got 2");
+                assert_eq!(position, crate::position::Position::new(3, 10));
+            }
+            _ => panic!("expected a native error"),
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use std::fmt::{Display, Formatter};
 
+use serde::Serialize;
+
+use crate::expression::LiteralType;
 use crate::position::Position;
 use crate::scanner::source_iterator::Entry;
 
@@ -19,17 +22,52 @@ impl Token {
             },
         }
     }
+
+    /// Whether the token is a unary or binary operator, including assignment.
+    pub fn is_operator(&self) -> bool {
+        matches!(self.token_type, TokenType::Bang) || self.is_binary_operator()
+    }
+
+    /// The binding power of the token between two operands, following the parser's
+    /// precedence levels: assignment 1, `or` 2, `and` 3, equality 4, comparison 5, term 6,
+    /// factor 7 and call 9. Level 8 is unary, whose operators only appear as prefixes.
+    pub fn infix_precedence(&self) -> Option<u8> {
+        match self.token_type {
+            TokenType::Equal => Some(1),
+            TokenType::Or => Some(2),
+            TokenType::And => Some(3),
+            TokenType::BangEqual | TokenType::EqualEqual => Some(4),
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => Some(5),
+            TokenType::Minus | TokenType::Plus => Some(6),
+            TokenType::Slash | TokenType::Star => Some(7),
+            TokenType::LeftParent | TokenType::Dot | TokenType::LeftBracket => Some(9),
+            _ => None,
+        }
+    }
+
+    fn is_binary_operator(&self) -> bool {
+        matches!(self.infix_precedence(), Some(1..=7))
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Serializes as `{"type": "Identifier", "value": "name"}`, without `value` for tokens that
+/// carry none.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "type", content = "value")]
 pub enum TokenType {
     // Single-character tokens.
     LeftParent,
     RightParent,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
+    DotDotDot,
     Minus,
     Plus,
     Semicolon,
@@ -68,10 +106,61 @@ pub enum TokenType {
     True,
     Var,
     While,
+    Loop,
+    Break,
+    Continue,
+    Assert,
 
     Eof,
 }
 
+impl TokenType {
+    /// Whether the token is a literal: a number, a string, `true`, `false` or `nil`.
+    pub fn is_literal(&self) -> bool {
+        self.literal_value().is_some()
+    }
+
+    /// How a keyword token is spelled, `None` for every other token.
+    pub fn keyword(&self) -> Option<&'static str> {
+        let keyword = match self {
+            TokenType::And => "and",
+            TokenType::Class => "class",
+            TokenType::Else => "else",
+            TokenType::False => "false",
+            TokenType::Fun => "fun",
+            TokenType::For => "for",
+            TokenType::If => "if",
+            TokenType::Nil => "nil",
+            TokenType::Or => "or",
+            TokenType::Print => "print",
+            TokenType::Return => "return",
+            TokenType::Super => "super",
+            TokenType::This => "this",
+            TokenType::True => "true",
+            TokenType::Var => "var",
+            TokenType::While => "while",
+            TokenType::Loop => "loop",
+            TokenType::Break => "break",
+            TokenType::Continue => "continue",
+            TokenType::Assert => "assert",
+            _ => return None,
+        };
+        Some(keyword)
+    }
+
+    /// The value of a literal token, `None` for every other token.
+    pub fn literal_value(&self) -> Option<LiteralType> {
+        match self {
+            TokenType::Number(value) => Some(LiteralType::NumberLit(*value)),
+            TokenType::StringToken(value) => Some(LiteralType::StringLit(value.clone())),
+            TokenType::True => Some(LiteralType::TrueLit),
+            TokenType::False => Some(LiteralType::FalseLit),
+            TokenType::Nil => Some(LiteralType::NilLit),
+            _ => None,
+        }
+    }
+}
+
 impl Display for TokenType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -81,12 +170,106 @@ impl Display for TokenType {
             TokenType::RightParent => write!(f, ")"),
             TokenType::LeftBrace => write!(f, "{{"),
             TokenType::RightBrace => write!(f, "}}"),
+            TokenType::LeftBracket => write!(f, "["),
+            TokenType::RightBracket => write!(f, "]"),
             TokenType::Semicolon => write!(f, ";"),
             TokenType::Comma => write!(f, ","),
+            TokenType::DotDotDot => write!(f, "..."),
             TokenType::Eof => write!(f, "EOF"),
             TokenType::Identifier(i) => write!(f, "{i:}"),
+            token_type => write!(f, "{}", token_type.keyword().unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(token_type: TokenType) -> Token {
+        Token {
+            token_type,
+            position: Position::new(0, 1),
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        for token_type in [
+            TokenType::Bang,
+            TokenType::Minus,
+            TokenType::Equal,
+            TokenType::Or,
+        ] {
+            assert!(token(token_type).is_operator());
+        }
+        for token_type in [
+            TokenType::LeftParent,
+            TokenType::Semicolon,
+            TokenType::Number(1.0),
+            TokenType::Print,
+        ] {
+            assert!(!token(token_type).is_operator());
+        }
+    }
+
+    #[test]
+    fn test_infix_precedence_follows_the_grammar() {
+        let precedences = [
+            TokenType::Equal,
+            TokenType::Or,
+            TokenType::And,
+            TokenType::EqualEqual,
+            TokenType::Less,
+            TokenType::Plus,
+            TokenType::Star,
+            TokenType::Dot,
+        ]
+        .map(|token_type| token(token_type).infix_precedence().unwrap());
+
+        assert_eq!(precedences, [1, 2, 3, 4, 5, 6, 7, 9]);
+        assert_eq!(token(TokenType::Bang).infix_precedence(), None);
+        assert_eq!(
+            token(TokenType::Identifier("a".into())).infix_precedence(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_literal_values() {
+        assert!(matches!(
+            TokenType::Number(1.5).literal_value(),
+            Some(LiteralType::NumberLit(value)) if value == 1.5
+        ));
+        assert!(matches!(
+            TokenType::StringToken("lox".into()).literal_value(),
+            Some(LiteralType::StringLit(value)) if value == "lox"
+        ));
+        assert!(matches!(
+            TokenType::True.literal_value(),
+            Some(LiteralType::TrueLit)
+        ));
+        assert!(matches!(
+            TokenType::False.literal_value(),
+            Some(LiteralType::FalseLit)
+        ));
+        assert!(matches!(
+            TokenType::Nil.literal_value(),
+            Some(LiteralType::NilLit)
+        ));
+    }
 
-            _ => write!(f, ""),
+    #[test]
+    fn test_non_literals() {
+        for token_type in [
+            TokenType::Identifier("a".into()),
+            TokenType::Plus,
+            TokenType::Print,
+            TokenType::Eof,
+        ] {
+            assert!(!token_type.is_literal());
+            assert!(token_type.literal_value().is_none());
         }
+        assert!(TokenType::Number(0.0).is_literal());
     }
 }
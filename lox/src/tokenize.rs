@@ -0,0 +1,122 @@
+//! `lox tokenize`: the tokens of a script as JSON lines, for tools like editor plugins.
+
+use miette::Diagnostic;
+use serde::Serialize;
+
+use crate::error::LoxError;
+use crate::scanner::Scanner;
+use crate::token::{Token, TokenType};
+
+/// A token as emitted by `tokenize`. `type` and, for literals, `value` come from the token
+/// type.
+#[derive(Serialize)]
+struct TokenLine<'a> {
+    #[serde(flatten)]
+    token_type: &'a TokenType,
+    lexeme: &'a str,
+    start: usize,
+    length: usize,
+}
+
+/// A scan error as emitted by `tokenize`.
+#[derive(Serialize)]
+struct DiagnosticLine {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// The stable error code, see `lox --explain`.
+    code: Option<String>,
+    message: String,
+    label: Option<String>,
+    start: usize,
+    length: usize,
+}
+
+/// Scans `source` and returns one JSON object per line for every token and every scan
+/// error, in the order they appear in the source. Scanning continues after errors, so the
+/// tokens are always complete.
+pub fn tokenize(source: &str) -> String {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_with_errors();
+
+    let mut lines = tokens
+        .iter()
+        .map(|token| (token.position.absolute, token_line(token, source)))
+        .chain(errors.iter().map(diagnostic_line))
+        .collect::<Vec<_>>();
+    lines.sort_by_key(|(start, _)| *start);
+
+    lines.into_iter().map(|(_, line)| line + "\n").collect()
+}
+
+fn token_line(token: &Token, source: &str) -> String {
+    let position = &token.position;
+    let line = TokenLine {
+        token_type: &token.token_type,
+        lexeme: source
+            .get(position.absolute..position.end_position())
+            .unwrap_or_default(),
+        start: position.absolute,
+        length: position.length,
+    };
+
+    serde_json::to_string(&line).expect("tokens can be serialized")
+}
+
+fn diagnostic_line(error: &LoxError) -> (usize, String) {
+    let label = error.labels().and_then(|mut labels| labels.next());
+    let line = DiagnosticLine {
+        kind: "Diagnostic",
+        code: error.code().map(|code| code.to_string()),
+        message: error.to_string(),
+        label: label.as_ref().and_then(|l| l.label().map(str::to_string)),
+        start: label.as_ref().map_or(0, |l| l.offset()),
+        length: label.as_ref().map_or(0, |l| l.len()),
+    };
+
+    let json = serde_json::to_string(&line).expect("diagnostics can be serialized");
+    (line.start, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn lines(source: &str) -> Vec<Value> {
+        tokenize(source)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            lines("var x = \"s\";"),
+            vec![
+                json!({"type": "Var", "lexeme": "var", "start": 0, "length": 3}),
+                json!({"type": "Identifier", "value": "x", "lexeme": "x", "start": 4, "length": 1}),
+                json!({"type": "Equal", "lexeme": "=", "start": 6, "length": 1}),
+                json!({"type": "StringToken", "value": "s", "lexeme": "\"s\"", "start": 8, "length": 3}),
+                json!({"type": "Semicolon", "lexeme": ";", "start": 11, "length": 1}),
+            ]
+        );
+        assert_eq!(tokenize(""), "");
+    }
+
+    #[test]
+    fn test_scan_errors_are_emitted_in_order() {
+        let lines = lines("1 # 2");
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["value"], json!(1.0));
+        assert_eq!(lines[1]["type"], "Diagnostic");
+        assert_eq!(lines[1]["code"], "E0002");
+        assert_eq!(lines[1]["message"], "Unrecognized Character '#' (U+0023)");
+        assert_eq!(
+            (&lines[1]["start"], &lines[1]["length"]),
+            (&json!(2), &json!(1))
+        );
+        assert_eq!(lines[2]["lexeme"], "2");
+    }
+}
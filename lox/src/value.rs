@@ -1,21 +1,167 @@
+use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::rc::Rc;
 
-use crate::callable::{Callable, FunctionContainer};
+use crate::builtins;
+use crate::callable::{BoundMethod, Callable};
 use crate::error::RuntimeError;
-use crate::evaluation::EvaluationResult;
-use crate::evaluation::ReturnOrError::{Error, Return};
+use crate::evaluation::ReturnOrError::Error;
+use crate::evaluation::{EvaluationResult, Interpreter};
 use crate::expression::LiteralType;
 use crate::position::Position;
 
+/// A Lox value.
+///
+/// `Nil`, `Boolean` and `Number` are copied on clone. All other variants are reference types:
+/// cloning them clones an `Rc`, so the clone shares its data with the original. Strings,
+/// functions and namespaces are immutable, so sharing them is unobservable, but a mutation
+/// of an array or map is visible through every clone.
 #[derive(PartialEq, Clone)]
 pub enum Value {
     Nil,
     Boolean(bool),
     Number(f64),
     Str(Rc<str>),
-    Function(Rc<FunctionContainer>),
+    Function(Rc<dyn Callable>),
+    Map(Rc<RefCell<HashMap<MapKey, Value>>>),
+    Array(Rc<RefCell<Vec<Value>>>),
+    Namespace(Rc<Namespace>),
+}
+
+impl Value {
+    /// Lox truthiness: `nil` and `false` are falsy, every other value (including `0`, `""`
+    /// and empty maps) is truthy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Boolean(false))
+    }
+
+    /// Whether the value is `nil`. Use this instead of comparing with `Value::Nil`, which
+    /// goes through `PartialEq` and its special cases for functions and numbers.
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    /// Whether the value can be called, which is true for Lox functions, natives and bound
+    /// methods.
+    pub fn is_callable(&self) -> bool {
+        matches!(self, Value::Function(_))
+    }
+
+    /// The name of the value's type as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "Nil",
+            Value::Boolean(_) => "Boolean",
+            Value::Number(_) => "Number",
+            Value::Str(_) => "String",
+            Value::Function(_) => "Function",
+            Value::Map(_) => "Map",
+            Value::Array(_) => "Array",
+            Value::Namespace(_) => "Namespace",
+        }
+    }
+
+    /// Same as `clone`, spelled out for call sites where the sharing matters: reference
+    /// types share their data with the returned value, see [`Value`].
+    pub fn shallow_clone(&self) -> Value {
+        self.clone()
+    }
+
+    /// Whether clones of this value share its data instead of copying it.
+    pub fn is_reference_type(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Boolean(_) | Value::Number(_))
+    }
+
+    /// The number of arguments a function requires, or `None` if the value is not callable.
+    /// Optional arguments and rest parameters are not counted.
+    pub fn arity(&self) -> Option<usize> {
+        match self {
+            Value::Function(function) => Some(function.arity()),
+            _ => None,
+        }
+    }
+}
+
+/// The top-level definitions of an imported module, accessed as `name.member`.
+#[derive(PartialEq)]
+pub struct Namespace {
+    pub name: String,
+    pub members: HashMap<String, Value>,
+}
+
+/// The subset of values that can be used as map keys.
+///
+/// Numbers are stored by their bit pattern after normalizing `-0.0` to `0.0`, so `m[1]`,
+/// `m[1.0]` and `m[-0]`/`m[0]` address the same entries. `NaN` is never a valid key since it
+/// is not equal to itself.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Str(Rc<str>),
+    Number(u64),
+    Boolean(bool),
+}
+
+impl MapKey {
+    pub(crate) fn from_value_node(node: &ValueNode) -> EvaluationResult<MapKey> {
+        match &node.value {
+            Value::Str(str) => Ok(MapKey::Str(str.clone())),
+            Value::Boolean(b) => Ok(MapKey::Boolean(*b)),
+            Value::Number(n) if n.is_nan() => Err(Error(RuntimeError::invalid_argument(
+                node,
+                "NaN cannot be used as a map key".to_string(),
+            ))),
+            Value::Number(n) if *n == 0.0 => Ok(MapKey::Number(0.0f64.to_bits())),
+            Value::Number(n) => Ok(MapKey::Number(n.to_bits())),
+            _ => Err(Error(RuntimeError::type_error(
+                node,
+                "String, Number or Boolean".to_string(),
+            ))),
+        }
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        match self {
+            MapKey::Str(str) => Value::Str(str.clone()),
+            MapKey::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            MapKey::Boolean(b) => Value::Boolean(*b),
+        }
+    }
+}
+
+impl Display for MapKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+fn display_array(f: &mut Formatter<'_>, array: &[Value]) -> std::fmt::Result {
+    let elements = array.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    write!(f, "[{}]", elements.join(", "))
+}
+
+fn display_map(f: &mut Formatter<'_>, map: &HashMap<MapKey, Value>) -> std::fmt::Result {
+    let mut entries = map
+        .iter()
+        .map(|(key, value)| format!("{key}: {value}"))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    write!(f, "{{{}}}", entries.join(", "))
+}
+
+/// Numbers, booleans and strings are ordered among values of the same type. Values of
+/// different types, and all other values, are not comparable.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+            (Value::Boolean(l), Value::Boolean(r)) => l.partial_cmp(r),
+            (Value::Str(l), Value::Str(r)) => l.partial_cmp(r),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Value {
@@ -23,9 +169,12 @@ impl Display for Value {
         match self {
             Value::Nil => f.write_str("Nil"),
             Value::Boolean(b) => write!(f, "{b}"),
-            Value::Number(n) => write!(f, "{n}"),
+            Value::Number(n) => write!(f, "{}", display_number(*n)),
             Value::Str(str) => write!(f, "{str}"),
-            Value::Function(fun) => write!(f, "fun {}", fun.id),
+            Value::Function(function) => write!(f, "{function}"),
+            Value::Map(map) => display_map(f, &map.borrow()),
+            Value::Array(array) => display_array(f, &array.borrow()),
+            Value::Namespace(namespace) => write!(f, "namespace {}", namespace.name),
         }
     }
 }
@@ -35,15 +184,50 @@ impl Debug for Value {
         match self {
             Value::Nil => f.write_str("Nil"),
             Value::Boolean(b) => write!(f, "{b}:Boolean"),
-            Value::Number(n) => write!(f, "{n}:Number"),
+            Value::Number(n) => write!(f, "{}:Number", display_number(*n)),
             Value::Str(str) => write!(f, "{str}:String"),
-            Value::Function(fun) => write!(f, "fun {}", fun.id),
+            Value::Function(function) => write!(f, "{function}"),
+            Value::Map(map) => {
+                display_map(f, &map.borrow())?;
+                f.write_str(":Map")
+            }
+            Value::Array(array) => {
+                display_array(f, &array.borrow())?;
+                f.write_str(":Array")
+            }
+            Value::Namespace(namespace) => write!(f, "namespace {}", namespace.name),
         }
     }
 }
 
+/// Checks that `index` is an integer addressing an element of an array of length `len`.
+fn array_index(index: &ValueNode, len: usize) -> EvaluationResult<usize> {
+    let i = index.as_integer()?;
+    match usize::try_from(i).ok().filter(|i| *i < len) {
+        Some(i) => Ok(i),
+        None => Err(Error(RuntimeError::invalid_argument(
+            index,
+            format!("index {i} is out of bounds for an array of length {len}"),
+        ))),
+    }
+}
+
+/// The longest string, in bytes, that repeating a string with `*` may build. Larger
+/// allocations abort the process instead of raising an error.
+const MAX_REPEATED_LENGTH: usize = 1 << 30;
+
+/// Formats integer-valued numbers without a fractional part and all other numbers
+/// with Rust's shortest round-tripping representation.
+pub(crate) fn display_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 2f64.powi(63) {
+        format!("{}", n as i64)
+    } else {
+        format!("{n}")
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
-pub(crate) struct ValueNode {
+pub struct ValueNode {
     pub(crate) value: Value,
     pub(crate) position: Position,
 }
@@ -74,6 +258,10 @@ impl ValueNode {
         ValueNode::new(value, position)
     }
 
+    pub(crate) fn is_nil(&self) -> bool {
+        self.value.is_nil()
+    }
+
     pub(crate) fn as_number(&self) -> EvaluationResult<f64> {
         match self.value {
             Value::Number(num) => Ok(num),
@@ -81,14 +269,24 @@ impl ValueNode {
         }
     }
 
-    pub(crate) fn as_boolean(&self) -> EvaluationResult<bool> {
-        match self.value {
-            Value::Boolean(b) => Ok(b),
-            Value::Nil => Ok(false),
-            _ => Err(Error(RuntimeError::type_error(self, "Boolean".to_string()))),
+    /// Returns the number if it has no fractional part and fits into an `i64`.
+    pub(crate) fn as_integer(&self) -> EvaluationResult<i64> {
+        let number = self.as_number()?;
+        // i64::MAX as f64 rounds up to 2^63, which is out of range.
+        match number.fract() == 0.0 && number >= i64::MIN as f64 && number < i64::MAX as f64 {
+            true => Ok(number as i64),
+            false => Err(Error(RuntimeError::type_error(self, "Integer".to_string()))),
         }
     }
 
+    pub(crate) fn is_truthy(&self) -> bool {
+        self.value.is_truthy()
+    }
+
+    pub(crate) fn as_boolean(&self) -> EvaluationResult<bool> {
+        Ok(self.is_truthy())
+    }
+
     pub(crate) fn as_str(&self) -> EvaluationResult<Rc<str>> {
         match &self.value {
             Value::Str(str) => Ok(str.clone()),
@@ -96,25 +294,99 @@ impl ValueNode {
         }
     }
 
-    pub(crate) fn call(&self, arguments: Vec<ValueNode>) -> EvaluationResult<Value> {
+    pub(crate) fn as_array(&self) -> EvaluationResult<Rc<RefCell<Vec<Value>>>> {
         match &self.value {
-            Value::Function(container) => match container.call(arguments) {
-                Ok(v) => Ok(v),
-                Err(Return(r)) => Ok(r),
-                error => error,
-            },
+            Value::Array(array) => Ok(array.clone()),
+            _ => Err(Error(RuntimeError::type_error(self, "Array".to_string()))),
+        }
+    }
+
+    /// Calls the value from the call at `position`, which spans the argument list.
+    pub(crate) fn call(
+        &self,
+        arguments: Vec<ValueNode>,
+        position: &Position,
+        interpreter: &mut Interpreter,
+    ) -> EvaluationResult<Value> {
+        match &self.value {
+            Value::Function(function) => function.call(arguments, position, interpreter),
+            _ => Err(Error(RuntimeError::not_callable(self))),
+        }
+    }
+
+    pub(crate) fn get_index(&self, index: &ValueNode) -> EvaluationResult<Value> {
+        match &self.value {
+            Value::Map(map) => {
+                let key = MapKey::from_value_node(index)?;
+                Ok(map.borrow().get(&key).cloned().unwrap_or(Value::Nil))
+            }
+            Value::Array(array) => {
+                let array = array.borrow();
+                let i = array_index(index, array.len())?;
+                Ok(array[i].clone())
+            }
             _ => Err(Error(RuntimeError::type_error(
                 self,
-                "Callable".to_string(),
+                "Map or Array".to_string(),
             ))),
         }
     }
 
+    pub(crate) fn set_index(&self, index: &ValueNode, value: Value) -> EvaluationResult<()> {
+        match &self.value {
+            Value::Map(map) => {
+                let key = MapKey::from_value_node(index)?;
+                map.borrow_mut().insert(key, value);
+                Ok(())
+            }
+            Value::Array(array) => {
+                let mut array = array.borrow_mut();
+                let i = array_index(index, array.len())?;
+                array[i] = value;
+                Ok(())
+            }
+            _ => Err(Error(RuntimeError::type_error(
+                self,
+                "Map or Array".to_string(),
+            ))),
+        }
+    }
+
+    pub(crate) fn get_member(&self, name: &str, position: &Position) -> EvaluationResult<Value> {
+        match &self.value {
+            Value::Namespace(namespace) => match namespace.members.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => Err(Error(RuntimeError::unknown_member(
+                    namespace.name.clone(),
+                    name.to_string(),
+                    position.clone(),
+                ))),
+            },
+            value => match builtins::method(value, name) {
+                Some(method) => Ok(Value::Function(Rc::new(BoundMethod {
+                    receiver: self.clone(),
+                    method: method.to_container(),
+                }))),
+                None if value.is_nil() => Err(Error(RuntimeError::nil_operation(
+                    format!("cannot access member {name} of nil"),
+                    self,
+                ))),
+                None => Err(Error(RuntimeError::unknown_member(
+                    value.type_name().to_string(),
+                    name.to_string(),
+                    position.clone(),
+                ))),
+            },
+        }
+    }
+
     pub(crate) fn negative(&self) -> EvaluationResult<Value> {
         Ok(Value::Number(-self.as_number()?))
     }
 
     pub(crate) fn add(&self, other: &ValueNode) -> EvaluationResult<Value> {
+        self.check_nil_operand(other, "add nil to")?;
+
         match &self.value {
             Value::Number(l) => {
                 let added = l + other.as_number()?;
@@ -133,9 +405,32 @@ impl ValueNode {
     }
 
     pub(crate) fn multiply(&self, other: &ValueNode) -> EvaluationResult<Value> {
+        self.check_nil_operand(other, "multiply nil by")?;
+
         match &self.value {
             Value::Number(l) => Ok(Value::Number(l * other.as_number()?)),
-            Value::Str(l) => Ok(Value::Str(l.repeat(other.as_number()? as usize).into())),
+            Value::Str(l) => {
+                let count = other.as_integer()?;
+                let Ok(count) = usize::try_from(count) else {
+                    return Err(Error(RuntimeError::invalid_argument(
+                        other,
+                        "cannot repeat a string a negative number of times".to_string(),
+                    )));
+                };
+                match l.len().checked_mul(count) {
+                    Some(length) if length <= MAX_REPEATED_LENGTH => {
+                        Ok(Value::Str(l.repeat(count).into()))
+                    }
+                    _ => Err(Error(RuntimeError::invalid_argument(
+                        other,
+                        format!(
+                            "repeating a string of {} bytes {count} times exceeds the limit of \
+                            {MAX_REPEATED_LENGTH} bytes",
+                            l.len()
+                        ),
+                    ))),
+                }
+            }
             _ => Err(Error(RuntimeError::type_error(
                 self,
                 "Number or String".to_string(),
@@ -147,18 +442,34 @@ impl ValueNode {
         Ok(Value::Number(self.as_number()? / other.as_number()?))
     }
 
+    /// Fails with a dedicated message if either operand of a binary operation is nil, e.g.
+    /// `cannot add nil to number`. `operation` is the message without the other operand's type.
+    fn check_nil_operand(&self, other: &ValueNode, operation: &str) -> EvaluationResult<()> {
+        let (nil, other) = match (&self.value, &other.value) {
+            (Value::Nil, _) => (self, other),
+            (_, Value::Nil) => (other, self),
+            _ => return Ok(()),
+        };
+
+        Err(Error(RuntimeError::nil_operation(
+            format!(
+                "cannot {operation} {}",
+                other.value.type_name().to_lowercase()
+            ),
+            nil,
+        )))
+    }
+
     pub(crate) fn equals(&self, other: &ValueNode) -> EvaluationResult<Value> {
-        Ok(Value::Boolean(self.eq(other)))
+        Ok(Value::Boolean(self.value == other.value))
     }
 
     pub(crate) fn not_equals(&self, other: &ValueNode) -> EvaluationResult<Value> {
-        Ok(Value::Boolean(!self.eq(other)))
+        Ok(Value::Boolean(self.value != other.value))
     }
 
     pub(crate) fn less_than(&self, other: &ValueNode) -> EvaluationResult<Value> {
-        let b = self
-            .compare(other)
-            .map_or(false, |ordering| ordering.is_lt());
+        let b = self.compare(other).is_some_and(|ordering| ordering.is_lt());
 
         Ok(Value::Boolean(b))
     }
@@ -166,15 +477,13 @@ impl ValueNode {
     pub(crate) fn less_than_or_equals(&self, other: &ValueNode) -> EvaluationResult<Value> {
         let b = self
             .compare(other)
-            .map_or(false, |ordering| ordering.is_lt() || ordering.is_eq());
+            .is_some_and(|ordering| ordering.is_lt() || ordering.is_eq());
 
         Ok(Value::Boolean(b))
     }
 
     pub(crate) fn greater_than(&self, other: &ValueNode) -> EvaluationResult<Value> {
-        let b = self
-            .compare(other)
-            .map_or(false, |ordering| ordering.is_gt());
+        let b = self.compare(other).is_some_and(|ordering| ordering.is_gt());
 
         Ok(Value::Boolean(b))
     }
@@ -182,22 +491,181 @@ impl ValueNode {
     pub(crate) fn greater_than_or_equals(&self, other: &ValueNode) -> EvaluationResult<Value> {
         let b = self
             .compare(other)
-            .map_or(false, |ordering| ordering.is_gt() || ordering.is_eq());
+            .is_some_and(|ordering| ordering.is_gt() || ordering.is_eq());
 
         Ok(Value::Boolean(b))
     }
 
     pub(crate) fn compare(&self, other: &ValueNode) -> Option<Ordering> {
-        match (&self.value, &other.value) {
-            // (Value::Nil, Value::Nil) => Some(Ordering::Equal),
-            (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
-            (Value::Boolean(l), Value::Boolean(r)) => l.partial_cmp(r),
-            (Value::Str(l), Value::Str(r)) => l.partial_cmp(r),
-            _ => None,
-        }
+        self.value.partial_cmp(&other.value)
     }
 
     pub(crate) fn not(&self) -> EvaluationResult<Value> {
         Ok(Value::Boolean(!self.as_boolean()?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use regex::Regex;
+
+    use crate::callable::{NativeContext, NativeFunctionContainer};
+
+    use super::*;
+
+    #[test]
+    fn test_is_nil() {
+        assert!(Value::Nil.is_nil());
+        assert!(!Value::Boolean(false).is_nil());
+        assert!(!Value::Str("".into()).is_nil());
+        assert!(ValueNode::new(Value::Nil, &Position::new(0, 1)).is_nil());
+    }
+
+    fn rust_files(directory: &Path, files: &mut Vec<String>) {
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                rust_files(&path, files);
+            } else if path.extension().is_some_and(|extension| extension == "rs") {
+                files.push(path.display().to_string());
+            }
+        }
+    }
+
+    /// Stands in for a lint: nil checks must use `is_nil`, see [`Value::is_nil`].
+    #[test]
+    fn test_nil_is_checked_with_is_nil() {
+        let comparison = Regex::new(r"[!=]=\s*(Value::)?Nil\b|\bNil\s*[!=]=").unwrap();
+        let single_pattern = Regex::new(r"matches!\([^,]*,\s*(Value::)?Nil\s*\)").unwrap();
+        let if_let = Regex::new(r"if let (Value::)?Nil\s*=").unwrap();
+        let string = Regex::new(r#""[^"]*""#).unwrap();
+
+        let mut files = Vec::new();
+        rust_files(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut files,
+        );
+        let offenders = files
+            .iter()
+            .flat_map(|file| {
+                let source = fs::read_to_string(file).unwrap();
+                source
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| {
+                        let code = string.replace_all(line, "\"\"");
+                        // The definition of `is_nil` itself.
+                        code.trim() != "matches!(self, Value::Nil)"
+                            && [&comparison, &single_pattern, &if_let]
+                                .iter()
+                                .any(|check| check.is_match(&code))
+                    })
+                    .map(|(index, line)| format!("{file}:{}: {}", index + 1, line.trim()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(
+            offenders.is_empty(),
+            "use `is_nil()`:\n{}",
+            offenders.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_display_number() {
+        assert_eq!(display_number(1.0), "1");
+        assert_eq!(display_number(-3.0), "-3");
+        assert_eq!(display_number(1.5), "1.5");
+        assert_eq!(display_number(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(display_number(1.0000000000001), "1.0000000000001");
+        assert_eq!(display_number(1e20), "100000000000000000000");
+        assert_eq!(display_number(2f64.powi(63)), "9223372036854776000");
+        assert_eq!(display_number(-(2f64.powi(63))), "-9223372036854776000");
+        assert_eq!(display_number(9223372036854774784.0), "9223372036854774784");
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Value::Number(1.0) < Value::Number(2.0));
+        assert!(Value::Str("b".into()) > Value::Str("a".into()));
+        assert!(Value::Boolean(false) < Value::Boolean(true));
+        assert_eq!(
+            Value::Number(1.0).partial_cmp(&Value::Str("1".into())),
+            None
+        );
+        assert_eq!(
+            Value::Number(f64::NAN).partial_cmp(&Value::Number(1.0)),
+            None
+        );
+        assert_eq!(Value::Nil.partial_cmp(&Value::Nil), None);
+    }
+
+    #[test]
+    fn test_only_nil_and_false_are_falsy() {
+        assert!(!Value::Nil.is_truthy());
+        assert!(!Value::Boolean(false).is_truthy());
+        assert!(Value::Boolean(true).is_truthy());
+        assert!(Value::Number(0.0).is_truthy());
+        assert!(Value::Number(f64::NAN).is_truthy());
+        assert!(Value::Str("".into()).is_truthy());
+        assert!(Value::Map(Rc::new(RefCell::new(HashMap::new()))).is_truthy());
+    }
+
+    #[test]
+    fn test_number_display_and_debug() {
+        assert_eq!(Value::Number(1.0).to_string(), "1");
+        assert_eq!(Value::Number(1.5).to_string(), "1.5");
+        assert_eq!(format!("{:?}", Value::Number(1.0)), "1:Number");
+        assert_eq!(format!("{:?}", Value::Number(0.5)), "0.5:Number");
+    }
+
+    #[test]
+    fn test_native_functions_and_bound_methods_are_functions() {
+        fn one(_: NativeContext, _: &mut Interpreter) -> EvaluationResult<Value> {
+            Ok(Value::Number(1.0))
+        }
+
+        let position = Position::new(0, 1);
+        let native = Value::Function(Rc::new(NativeFunctionContainer::new("one", 0..=1, one)));
+        let bound = Value::Function(Rc::new(BoundMethod {
+            receiver: ValueNode::new(Value::Nil, &position),
+            method: NativeFunctionContainer::new("one", 1..=1, one),
+        }));
+
+        assert_eq!(native.to_string(), "native fun one");
+        assert_eq!(format!("{bound:?}"), "native fun one");
+        assert_eq!((native.arity(), bound.arity()), (Some(0), Some(1)));
+        assert_eq!(bound.type_name(), "Function");
+        assert!(native.is_callable() && bound.is_callable());
+        assert!(!Value::Nil.is_callable());
+        assert!(!Value::Str("one".into()).is_callable());
+
+        // Functions are only equal to themselves, even if they have the same name.
+        assert_eq!(native, native.clone());
+        assert_ne!(native, bound);
+
+        let result =
+            ValueNode::new(native, &position).call(vec![], &position, &mut Interpreter::new());
+        assert!(matches!(result, Ok(Value::Number(n)) if n == 1.0));
+    }
+
+    #[test]
+    fn test_cloned_arrays_share_mutations() {
+        let array = Value::Array(Rc::new(RefCell::new(vec![Value::Number(1.0)])));
+        let clone = array.shallow_clone();
+
+        if let Value::Array(elements) = &clone {
+            elements.borrow_mut().push(Value::Number(2.0));
+        }
+
+        assert_eq!(array.to_string(), "[1, 2]");
+        assert!(array.is_reference_type());
+        assert!(Value::Str("a".into()).is_reference_type());
+        assert!(!Value::Number(1.0).is_reference_type());
+        assert!(!Value::Nil.is_reference_type());
+    }
+}
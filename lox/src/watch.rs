@@ -0,0 +1,153 @@
+//! `lox --watch script`, which runs a script again whenever it changes.
+//!
+//! The run loop in [`watch`] only sees [`WatchEvent`]s from an [`EventSource`]. In the CLI
+//! they come from the file system through [`watch_file`] and from Ctrl-C.
+
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Editors often save a file in several writes. Changes this close together cause one run.
+pub(crate) const DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum WatchEvent {
+    /// The watched script changed.
+    Changed,
+    /// Watching should end.
+    Stop,
+}
+
+pub(crate) trait EventSource {
+    /// Waits for the next event, at most for `timeout` if one is given. Returns `None` if
+    /// the timeout elapsed first.
+    fn next_event(&mut self, timeout: Option<Duration>) -> Option<WatchEvent>;
+}
+
+impl EventSource for Receiver<WatchEvent> {
+    fn next_event(&mut self, timeout: Option<Duration>) -> Option<WatchEvent> {
+        // Once every sender is gone, nothing can change anymore.
+        match timeout {
+            None => Some(self.recv().unwrap_or(WatchEvent::Stop)),
+            Some(timeout) => match self.recv_timeout(timeout) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => Some(WatchEvent::Stop),
+            },
+        }
+    }
+}
+
+/// Calls `run`, then calls it again after every burst of changes until `events` stops.
+/// Changes that arrive within `debounce` of each other form one burst.
+pub(crate) fn watch(events: &mut impl EventSource, debounce: Duration, mut run: impl FnMut()) {
+    run();
+
+    loop {
+        match events.next_event(None) {
+            Some(WatchEvent::Changed) => {}
+            Some(WatchEvent::Stop) => return,
+            None => continue,
+        }
+
+        loop {
+            match events.next_event(Some(debounce)) {
+                Some(WatchEvent::Changed) => {}
+                Some(WatchEvent::Stop) => return,
+                None => break,
+            }
+        }
+
+        run();
+    }
+}
+
+/// Sends [`WatchEvent::Changed`] to `events` whenever the file at `path` changes, until the
+/// returned watcher is dropped. The directory of the file is watched, so that the file is
+/// still followed when an editor saves it by replacing it.
+pub(crate) fn watch_file(
+    path: &Path,
+    events: Sender<WatchEvent>,
+) -> notify::Result<RecommendedWatcher> {
+    let path = path.canonicalize()?;
+    let directory = path.parent().unwrap_or(&path).to_path_buf();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if !matches!(event.kind, EventKind::Access(_)) && event.paths.contains(&path) {
+                let _ = events.send(WatchEvent::Changed);
+            }
+        }
+    })?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::mpsc;
+
+    use super::*;
+
+    /// Replays events, where `None` is a timeout. Stops once all events are replayed.
+    struct ScriptedEvents(VecDeque<Option<WatchEvent>>);
+
+    impl ScriptedEvents {
+        fn new(events: &[Option<WatchEvent>]) -> Self {
+            ScriptedEvents(events.iter().copied().collect())
+        }
+    }
+
+    impl EventSource for ScriptedEvents {
+        fn next_event(&mut self, _: Option<Duration>) -> Option<WatchEvent> {
+            self.0.pop_front().unwrap_or(Some(WatchEvent::Stop))
+        }
+    }
+
+    fn count_runs(events: &[Option<WatchEvent>]) -> usize {
+        let mut runs = 0;
+        watch(&mut ScriptedEvents::new(events), DEBOUNCE, || runs += 1);
+        runs
+    }
+
+    const CHANGED: Option<WatchEvent> = Some(WatchEvent::Changed);
+    const STOP: Option<WatchEvent> = Some(WatchEvent::Stop);
+    const TIMEOUT: Option<WatchEvent> = None;
+
+    #[test]
+    fn test_runs_once_before_any_change() {
+        assert_eq!(count_runs(&[STOP]), 1);
+    }
+
+    #[test]
+    fn test_runs_again_after_a_change() {
+        assert_eq!(count_runs(&[CHANGED, TIMEOUT, STOP]), 2);
+        assert_eq!(count_runs(&[CHANGED, TIMEOUT, CHANGED, TIMEOUT, STOP]), 3);
+    }
+
+    #[test]
+    fn test_debounces_bursts_of_changes() {
+        assert_eq!(count_runs(&[CHANGED, CHANGED, CHANGED, TIMEOUT, STOP]), 2);
+    }
+
+    #[test]
+    fn test_stop_during_a_burst_skips_the_run() {
+        assert_eq!(count_runs(&[CHANGED, CHANGED, STOP, CHANGED, TIMEOUT]), 1);
+    }
+
+    #[test]
+    fn test_receiver_events() {
+        let (sender, mut receiver) = mpsc::channel();
+        sender.send(WatchEvent::Changed).unwrap();
+        assert_eq!(receiver.next_event(None), CHANGED);
+        assert_eq!(receiver.next_event(Some(Duration::from_millis(1))), TIMEOUT);
+
+        drop(sender);
+        assert_eq!(receiver.next_event(None), STOP);
+        assert_eq!(receiver.next_event(Some(Duration::from_millis(1))), STOP);
+    }
+}
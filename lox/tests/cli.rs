@@ -0,0 +1,468 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use tempfile::NamedTempFile;
+
+fn lox(args: &[&str]) -> String {
+    String::from_utf8(lox_output(args).stdout).unwrap()
+}
+
+fn lox_output(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(args)
+        .output()
+        .expect("failed to run lox")
+}
+
+fn lox_with_stdin(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run lox");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().expect("failed to run lox")
+}
+
+fn script(source: &str) -> NamedTempFile {
+    let mut script = NamedTempFile::new().unwrap();
+    write!(script, "{source}").unwrap();
+    script
+}
+
+const HELLO_WORLD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/hello_world.lox");
+
+#[test]
+fn test_print_tokens() {
+    let output = lox(&["--print-tokens", HELLO_WORLD]);
+
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec![
+            "   0  Print         28..33      print",
+            "   1  StringToken   34..49      \"Hello, World!\"",
+        ]
+    );
+}
+
+#[test]
+fn test_print_tokens_and_run() {
+    let script = script("print \"a\nb\";");
+    let path = script.path().to_str().unwrap();
+
+    assert_eq!(
+        lox(&["--print-tokens", "--run", path])
+            .lines()
+            .collect::<Vec<_>>(),
+        vec![
+            "   0  Print         0..5        print",
+            "   1  StringToken   6..11       \"a\\nb\"",
+            "   2  Semicolon     11..12      ;",
+            "a",
+            "b",
+        ]
+    );
+}
+
+#[test]
+fn test_print_ast() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/control_flow.lox");
+    let output = lox(&["--print-ast", path]);
+
+    assert_eq!(
+        output,
+        r#"If
+   |_ Variable: condition (4:9)
+   |_ Block
+      |_ Print
+         |_ "yes" (27:5)
+   |_ Block
+      |_ Print
+         |_ "no" (53:4)
+Var a
+   |_ 1 (71:1)
+While
+   |_ Binary < (81:6)
+      |_ Variable: a (81:1)
+      |_ 10 (85:2)
+   |_ Block
+      |_ Print
+         |_ Variable: a (101:1)
+      |_ Expression
+         |_ Assignment: a (108:9)
+            |_ Binary + (112:5)
+               |_ Variable: a (112:1)
+               |_ 1 (116:1)
+Block
+   |_ Var a
+      |_ 1 (135:1)
+   |_ While (desugared from for)
+      |_ Binary < (138:6)
+         |_ Variable: a (138:1)
+         |_ 10 (142:2)
+      |_ Block
+         |_ Print
+            |_ Variable: a (169:1)
+      |_ Assignment: a (146:9)
+         |_ Binary + (150:5)
+            |_ Variable: a (150:1)
+            |_ 1 (154:1)
+"#
+    );
+}
+
+#[test]
+fn test_print_ast_exits_65_on_parse_errors() {
+    let script = script("var = 1;");
+    let output = lox_output(&["--print-ast", script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn test_exit_codes() {
+    let ok = script("print 1;");
+    let parse_error = script("print 1");
+    let runtime_error = script("print 1; print -\"a\";");
+
+    let status =
+        |script: &NamedTempFile| lox_output(&[script.path().to_str().unwrap()]).status.code();
+
+    assert_eq!(status(&ok), Some(0));
+    assert_eq!(status(&parse_error), Some(65));
+    assert_eq!(status(&runtime_error), Some(70));
+}
+
+#[test]
+fn test_piped_errors_name_line_and_column() {
+    let parse_error = script("print 1;\nprint (2;\n");
+    let runtime_error = script("var a = 1;\n\nprint -\"a\";\n");
+
+    assert!(lox(&[parse_error.path().to_str().unwrap()]).contains("Unexpected Token at 2:9"));
+    assert!(lox(&[runtime_error.path().to_str().unwrap()]).contains("TypeError at 3:8"));
+}
+
+#[test]
+fn test_unterminated_strings_are_reported_without_the_parse_errors_they_cause() {
+    let unterminated = script("print 1;\nprint \"a;\nprint 2;\n");
+    let output = lox_output(&[unterminated.path().to_str().unwrap()]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(65));
+    assert!(stdout.contains("Unterminated String at 2:7"), "{stdout}");
+    assert!(
+        stdout.contains("expected closing `\"` before end of file"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("Error!"), "{stdout}");
+    assert!(!stdout.contains("E0013"), "{stdout}");
+}
+
+#[test]
+fn test_errors_and_warnings_are_reported_together() {
+    let problems = script("var a = ;\nloop {}\nprint (2;\n");
+    let output = lox(&[problems.path().to_str().unwrap()]);
+
+    assert!(output.starts_with("  x 2 errors, 1 warning\n"), "{output}");
+    assert_eq!(output.matches("1 | var a = ;").count(), 1, "{output}");
+    let listed = [
+        "Illegal Token at 1:9",
+        "infinite loop at 2:1",
+        "Unexpected Token at 3:9",
+    ]
+    .map(|message| output.find(message).unwrap());
+    assert!(listed.is_sorted(), "{output}");
+}
+
+#[test]
+fn test_explain() {
+    let output = lox_output(&["--explain", "E0012"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "E0012: a `(`, `[` or `{` that is never closed\n"
+    );
+
+    assert_eq!(lox_output(&["--explain", "E9999"]).status.code(), Some(1));
+    assert_eq!(lox_output(&["--explain"]).status.code(), Some(64));
+}
+
+#[test]
+fn test_reports_show_error_codes() {
+    let script = script("print (1;");
+    assert!(lox(&[script.path().to_str().unwrap()]).contains("E0011"));
+}
+
+#[test]
+fn test_input_ending_early_points_at_its_end() {
+    for (source, label) in [
+        ("var a =", "expected an expression"),
+        ("print 1", "expected `;`"),
+        ("print 1\n", "expected `;`"),
+    ] {
+        let script = script(source);
+        let output = lox(&[script.path().to_str().unwrap()]);
+        assert!(output.contains("E0013"), "{source:?}\n{output}");
+        assert!(output.contains(label), "{source:?}\n{output}");
+    }
+}
+
+#[test]
+fn test_clock_millis_does_not_go_backwards() {
+    let script = script(
+        "var first = clockMillis(); sleep(1); var second = clockMillis();
+        print first > 0; print second >= first;",
+    );
+
+    assert_eq!(lox(&[script.path().to_str().unwrap()]), "true\ntrue\n");
+}
+
+#[test]
+fn test_script_args() {
+    let script = script("print args();");
+    let path = script.path().to_str().unwrap();
+
+    assert_eq!(lox(&[path, "--", "foo", "bar"]), "[foo, bar]\n");
+    assert_eq!(lox(&[path, "--", "--no-color", "--"]), "[--no-color, --]\n");
+    assert_eq!(lox(&[path]), "[]\n");
+}
+
+#[test]
+fn test_script_from_stdin() {
+    let source = "var a = 1;\nprint a + 1;\n";
+
+    for args in [&["-"][..], &[]] {
+        let output = lox_with_stdin(args, source);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n", "{args:?}");
+        assert_eq!(output.status.code(), Some(0));
+    }
+}
+
+#[test]
+fn test_script_from_stdin_exit_code() {
+    let output = lox_with_stdin(&["-"], "print -\"a\";");
+    assert_eq!(output.status.code(), Some(70));
+}
+
+#[test]
+fn test_missing_script() {
+    let output = lox_output(&["does/not/exist.lox"]);
+
+    assert_eq!(output.status.code(), Some(66));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("could not read does/not/exist.lox"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_script_is_a_directory() {
+    let directory = env!("CARGO_MANIFEST_DIR");
+    assert_eq!(lox_output(&[directory]).status.code(), Some(66));
+}
+
+#[test]
+fn test_panic() {
+    let script = script("print 1; panic(\"giving up\"); print 2;");
+    let output = lox_output(&[script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Panic: giving up\n"
+    );
+}
+
+#[test]
+fn test_exit() {
+    let script = script("print 1; exit(3); print 2;");
+    let output = lox_output(&[script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+#[test]
+fn test_exit_with_error() {
+    let script = script("print 1; exitWithError(\"bad input\"); print 2;");
+    let output = lox_output(&[script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "bad input\n");
+}
+
+#[test]
+fn test_profile() {
+    let script = script(
+        "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }\nprint fib(20);",
+    );
+    let output = lox_output(&["--profile", script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "6765\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines = stderr.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("function"), "{stderr}");
+    let fib = lines[1].split_whitespace().collect::<Vec<_>>();
+    assert_eq!(fib[..2], ["fib", "21891"], "{stderr}");
+}
+
+#[test]
+fn test_trace() {
+    let script = script("var a = 1;\nfun f() { return a; }\nprint f();");
+    let output = lox_output(&["--trace", script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "var a = 1;\nfun f()\nprint f();\n  return a;\n"
+    );
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn test_seeded_random_numbers_repeat_across_runs() {
+    let script = script("seed(42); print [rand(), rand(), randInt(1, 6)];");
+    let run = || lox_output(&[script.path().to_str().unwrap()]);
+
+    let first = run();
+    assert_eq!(first.status.code(), Some(0));
+    assert_eq!(run().stdout, first.stdout);
+}
+
+#[test]
+fn test_fmt_rewrites_the_script() {
+    let script = script("print 1+2;");
+    let output = lox_output(&["fmt", script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read_to_string(script.path()).unwrap(), "print 1 + 2;\n");
+}
+
+#[test]
+fn test_fmt_check() {
+    let formatted = script("print 1 + 2;\n");
+    let unformatted = script("print 1+2;");
+
+    let check =
+        |script: &NamedTempFile| lox_output(&["fmt", "--check", script.path().to_str().unwrap()]);
+
+    assert_eq!(check(&formatted).status.code(), Some(0));
+    assert_eq!(check(&unformatted).status.code(), Some(1));
+    assert_eq!(
+        fs::read_to_string(unformatted.path()).unwrap(),
+        "print 1+2;"
+    );
+}
+
+#[test]
+fn test_fmt_refuses_to_write_with_parse_errors() {
+    let script = script("print 1+;");
+    let output = lox_output(&["fmt", script.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(fs::read_to_string(script.path()).unwrap(), "print 1+;");
+}
+
+#[test]
+fn test_fmt_stdin() {
+    let output = lox_with_stdin(&["fmt"], "var a=[1,2];");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "var a = [1, 2];\n"
+    );
+}
+
+#[test]
+fn test_check() {
+    let good = script("var a = 1;\nprint a;\npanic(\"not run\");\n");
+    let parse_error = script("print 1;\nvar = 2;\nprint (3;\n");
+    let warning = script("loop { print 1; }\n");
+
+    let check = |scripts: &[&NamedTempFile]| {
+        let mut args = vec!["check"];
+        args.extend(scripts.iter().map(|script| script.path().to_str().unwrap()));
+        lox_output(&args)
+    };
+
+    let output = check(&[&good]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+
+    let output = check(&[&warning]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("infinite loop"), "{stdout}");
+
+    let output = check(&[&good, &parse_error, &warning]);
+    assert_eq!(output.status.code(), Some(65));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let file_name = |script: &NamedTempFile| {
+        let name = script.path().file_name().unwrap();
+        name.to_str().unwrap().to_string()
+    };
+    assert!(stdout.contains(&file_name(&parse_error)), "{stdout}");
+    assert!(stdout.contains(&file_name(&warning)), "{stdout}");
+    assert!(!stdout.contains(&file_name(&good)), "{stdout}");
+}
+
+#[test]
+fn test_tokenize() {
+    let expressions = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/expressions.lox");
+    let output = lox_output(&["tokenize", expressions]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let tokens = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(tokens.len(), 84);
+    assert_eq!(
+        tokens[0],
+        serde_json::json!({"type": "Identifier", "value": "add", "lexeme": "add", "start": 0, "length": 3})
+    );
+
+    let cat = tokens.iter().find(|token| token["start"] == 158).unwrap();
+    assert_eq!(cat["type"], "StringToken");
+    assert_eq!(cat["value"], "cat");
+    assert_eq!(cat["lexeme"], "\"cat\"");
+
+    let pi = tokens.iter().find(|token| token["start"] == 183).unwrap();
+    assert_eq!(pi["type"], "Number");
+    assert_eq!(pi["value"], 314.0);
+}
+
+#[test]
+fn test_tokenize_reports_scan_errors_in_the_stream() {
+    let output = lox_with_stdin(&["tokenize"], "print 1 @ 2;");
+    assert_eq!(output.status.code(), Some(0));
+
+    let types = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["type"].clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        vec!["Print", "Number", "Diagnostic", "Number", "Semicolon"]
+    );
+}